@@ -0,0 +1,21 @@
+use bevy::prelude::*;
+
+use crate::music::{location_music_system, music_system, setup_music, MusicState};
+use crate::sfx::{setup_sfx, sfx_event_system, sfx_system, SFXSystem};
+
+// Music and sound effects. Leave this out to run without any audio output.
+pub struct AudioPlugin;
+
+impl Plugin for AudioPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(bevy_kira_audio::AudioPlugin)
+            .init_resource::<MusicState>()
+            .init_resource::<SFXSystem>()
+            .add_startup_system(setup_music)
+            .add_startup_system(setup_sfx)
+            .add_system(music_system)
+            .add_system(location_music_system)
+            .add_system(sfx_system)
+            .add_system(sfx_event_system);
+    }
+}
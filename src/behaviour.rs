@@ -0,0 +1,174 @@
+// What NPCs get up to. Each one has a Behaviour, picked when it's spawned, which decides how it
+// looks, how fast it goes, and where it heads next whenever it's got where it was going.
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use rand::prelude::*;
+
+use crate::clock::GameClock;
+use crate::environment::{Environment, Location, GRID_SIZE};
+use crate::npc::NPC;
+use crate::pathfinding::{position_tile, Destination};
+use crate::population::Leaving;
+use crate::rng::GameRng;
+
+// Aisles a shopper stops at before heading for the checkout
+const SHOPPING_STOPS: u8 = 3;
+// Seconds spent at each aisle, and in the checkout queue
+const BROWSE_SECONDS: [f64; 2] = [2., 5.];
+const QUEUE_SECONDS: f64 = 8.;
+// Seconds the dog gets to sniff about, and how many tiles the walker goes between sniffs
+const SNIFF_SECONDS: [f64; 2] = [2., 6.];
+const WALK_TILES: isize = 4;
+// Seconds a follower with nowhere to go waits before trying again
+const FOLLOW_RETRY_SECONDS: f64 = 1.;
+
+// In front of the aisles, and the back of the checkout queue
+const AISLES: [[usize; 2]; 5] = [[8, 3], [12, 3], [16, 4], [9, 10], [13, 10]];
+pub const CHECKOUT_QUEUE: [usize; 2] = [4, 10];
+// Round the park, clear of the tree, the swings and the signs
+const JOGGING_LOOP: [[usize; 2]; 5] = [[3, 4], [12, 4], [15, 8], [12, 15], [6, 14]];
+
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub enum Behaviour {
+    // Ambles about at random
+    Wanderer,
+    // Does the rounds of the aisles, then queues at the checkout
+    Shopper { stops_left: u8 },
+    // Laps the park, fast
+    Jogger { lap: usize },
+    // Slow, and stops every few steps
+    DogWalker,
+    // Sticks with someone, or the player if nobody's given
+    Follower(Option<Entity>),
+    // Stays put, like whoever's on the checkout
+    Stationary,
+}
+
+impl Behaviour {
+    pub fn shopper() -> Self {
+        Behaviour::Shopper {
+            stops_left: SHOPPING_STOPS,
+        }
+    }
+
+    pub fn jogger() -> Self {
+        Behaviour::Jogger { lap: 0 }
+    }
+
+    // As a multiple of walking pace
+    pub fn speed(&self) -> f32 {
+        match self {
+            Behaviour::Wanderer => 1.,
+            Behaviour::Shopper { .. } => 0.8,
+            Behaviour::Jogger { .. } => 2.5,
+            Behaviour::DogWalker => 0.5,
+            Behaviour::Follower(_) => 1.2,
+            Behaviour::Stationary => 0.,
+        }
+    }
+
+    // Who an NPC is, if the story hasn't said, for what they've got to say
+    pub fn identity(&self) -> &'static str {
+        match self {
+            Behaviour::Wanderer | Behaviour::Follower(_) => "Stranger",
+            Behaviour::Shopper { .. } => "Shopper",
+            Behaviour::Jogger { .. } => "Jogger",
+            Behaviour::DogWalker => "Dog walker",
+            Behaviour::Stationary => "Staff",
+        }
+    }
+
+    pub fn sprite(&self) -> &'static str {
+        match self {
+            Behaviour::Wanderer => "npc.png",
+            Behaviour::Shopper { .. } => "npc_shopper.png",
+            Behaviour::Jogger { .. } => "npc_jogger.png",
+            Behaviour::DogWalker => "npc_dog_walker.png",
+            Behaviour::Follower(_) => "npc_follower.png",
+            Behaviour::Stationary => "npc_staff.png",
+        }
+    }
+
+    // Where to go now the last place has been reached, and how long to stop first. None leaves
+    // the NPC to wander.
+    pub fn next_stop(
+        &mut self,
+        location: Location,
+        tile: [usize; 2],
+        rng: &mut GameRng,
+    ) -> Option<(Destination, f64)> {
+        match self {
+            Behaviour::Shopper { stops_left } if location == Location::Shops => {
+                let wait = if tile == CHECKOUT_QUEUE {
+                    QUEUE_SECONDS
+                } else {
+                    rng.gen_range(BROWSE_SECONDS[0]..BROWSE_SECONDS[1])
+                };
+                let stop = if *stops_left == 0 {
+                    *stops_left = SHOPPING_STOPS;
+                    CHECKOUT_QUEUE
+                } else {
+                    *stops_left -= 1;
+                    AISLES[rng.gen_range(0..AISLES.len())]
+                };
+                Some((Destination::Tile(stop), wait))
+            }
+            Behaviour::Jogger { lap } if location == Location::Park => {
+                *lap = (*lap + 1) % JOGGING_LOOP.len();
+                Some((Destination::Tile(JOGGING_LOOP[*lap]), 0.))
+            }
+            Behaviour::DogWalker => {
+                let mut step = |v: usize| {
+                    let v = v as isize + rng.gen_range(-WALK_TILES..=WALK_TILES);
+                    v.clamp(0, GRID_SIZE as isize - 1) as usize
+                };
+                let stop = [step(tile[0]), step(tile[1])];
+                let wait = rng.gen_range(SNIFF_SECONDS[0]..SNIFF_SECONDS[1]);
+                Some((Destination::Tile(stop), wait))
+            }
+            Behaviour::Follower(target) => {
+                let destination = match target {
+                    Some(e) => Destination::Entity(*e),
+                    None => Destination::Player,
+                };
+                Some((destination, FOLLOW_RETRY_SECONDS))
+            }
+            _ => None,
+        }
+    }
+}
+
+// NPCs that have got where they were going, and aren't on their way out
+type IdleNpcs<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static mut NPC,
+        &'static mut Behaviour,
+        &'static RigidBodyPositionComponent,
+    ),
+    (Without<Destination>, Without<Leaving>),
+>;
+
+// Gives every NPC that's got where it was going somewhere else to be
+pub fn behaviour_system(
+    mut commands: Commands,
+    mut npc_query: IdleNpcs,
+    environment_query: Query<&Environment>,
+    time: Res<GameClock>,
+    mut rng: ResMut<GameRng>,
+) {
+    let location = match environment_query.get_single() {
+        Ok(e) => e.location,
+        Err(_) => return,
+    };
+    let now = time.seconds_since_startup();
+    for (entity, mut npc, mut behaviour, position) in npc_query.iter_mut() {
+        let tile = position_tile(position.position.translation.vector);
+        if let Some((destination, wait)) = behaviour.next_stop(location, tile, &mut rng) {
+            npc.paused_until = now + wait;
+            commands.entity(entity).insert(destination);
+        }
+    }
+}
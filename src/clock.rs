@@ -0,0 +1,93 @@
+use bevy::core::CoreSystem;
+use bevy::prelude::*;
+use bevy_rapier2d::physics::TimestepMode;
+use bevy_rapier2d::prelude::*;
+use std::time::Duration;
+
+// The game's own clock. Gameplay reads this instead of bevy's `Time`, so that tests can step the
+// game by exactly as much as they like, whatever the wall clock is doing.
+#[derive(Debug, Clone)]
+pub struct GameClock {
+    elapsed: f64,
+    delta: f64,
+    // When set, every frame advances the clock by exactly this much
+    pub fixed_step: Option<f64>,
+    // Game seconds per real second
+    pub scale: f64,
+}
+
+impl Default for GameClock {
+    fn default() -> Self {
+        GameClock {
+            elapsed: 0.,
+            delta: 0.,
+            fixed_step: None,
+            scale: 1.,
+        }
+    }
+}
+
+impl GameClock {
+    pub fn fixed(step: f64) -> Self {
+        GameClock {
+            fixed_step: Some(step),
+            ..Default::default()
+        }
+    }
+
+    pub fn advance(&mut self, dt: f64) {
+        self.delta = dt;
+        self.elapsed += dt;
+    }
+
+    // Jumps ahead without it counting as a frame, i.e. nothing sees a huge delta
+    pub fn skip(&mut self, dt: f64) {
+        self.elapsed += dt;
+    }
+
+    // Same names as on `Time`, so systems don't care which one they're reading
+    pub fn seconds_since_startup(&self) -> f64 {
+        self.elapsed
+    }
+
+    pub fn delta_seconds(&self) -> f32 {
+        self.delta as f32
+    }
+
+    pub fn delta_seconds_f64(&self) -> f64 {
+        self.delta
+    }
+
+    pub fn delta(&self) -> Duration {
+        Duration::from_secs_f64(self.delta)
+    }
+}
+
+pub struct ClockPlugin;
+
+impl Plugin for ClockPlugin {
+    fn build(&self, app: &mut App) {
+        // init_resource keeps a clock that was put there before us, e.g. a fixed one from a test
+        app.init_resource::<GameClock>()
+            .add_startup_system_to_stage(StartupStage::PreStartup, fixed_physics_setup)
+            .add_system_to_stage(CoreStage::First, clock_system.after(CoreSystem::Time));
+    }
+}
+
+// On a fixed clock, physics has to step by the same amount every frame too
+fn fixed_physics_setup(
+    clock: Res<GameClock>,
+    mut rapier_config: ResMut<RapierConfiguration>,
+    mut integration_parameters: ResMut<IntegrationParameters>,
+) {
+    if let Some(step) = clock.fixed_step {
+        rapier_config.timestep_mode = TimestepMode::FixedTimestep;
+        integration_parameters.dt = step as f32;
+    }
+}
+
+fn clock_system(time: Res<Time>, mut clock: ResMut<GameClock>) {
+    let dt = clock.fixed_step.unwrap_or_else(|| time.delta_seconds_f64());
+    let scale = clock.scale;
+    clock.advance(dt * scale);
+}
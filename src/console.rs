@@ -0,0 +1,407 @@
+// The developer console: ` opens it, then type e.g. `sanity +20` or `goto park 5 5`. Only built
+// with `--features dev-console`.
+use bevy::app::{Events, ManualEventReader};
+use bevy::input::InputSystem;
+use bevy::prelude::*;
+use bevy::window::ReceivedCharacter;
+use bevy_rapier2d::prelude::*;
+use std::str::FromStr;
+
+use crate::behaviour::Behaviour;
+use crate::clock::GameClock;
+use crate::covid::ForcedCovidRisk;
+use crate::environment::{Environment, EnvironmentCollider, Location};
+use crate::events::{LocationChanged, SanityChanged};
+use crate::game::{GameState, DAY_LENGTH};
+use crate::npc::{spawn_npc, NPC};
+use crate::pickup::Pickup;
+use crate::player::Player;
+use crate::teleportation::{clear_location, teleport, Teleporter};
+use crate::{SCREEN_HEIGHT, SCREEN_WIDTH};
+
+const TOGGLE_KEY: KeyCode = KeyCode::Grave;
+// Lines of output kept on screen
+const OUTPUT_LINES: usize = 12;
+const LINE_HEIGHT: f32 = 20.;
+
+const HELP: [&str; 10] = [
+    "sanity +20 | sanity -5 | sanity 50",
+    "goto park 5 5",
+    "narrative jump 42",
+    "spawn npc 10 10",
+    "covid 0.8 | covid off",
+    "lock shops | unlock shops",
+    "time scale 4",
+    "day 14",
+    "F3 for the covid risk heatmap",
+    "Up/Down for history, ` to close",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConsoleCommand {
+    ChangeSanity(i32),
+    SetSanity(i32),
+    Goto(Location, [usize; 2]),
+    NarrativeJump(usize),
+    SpawnNpc([usize; 2]),
+    Covid(Option<f32>),
+    SetAccess(Location, bool),
+    TimeScale(f64),
+    Day(i32),
+    Help,
+}
+
+fn parse<T: FromStr>(s: Option<&str>, what: &str) -> Result<T, String> {
+    match s {
+        Some(s) => T::from_str(s).map_err(|_| format!("bad {}: {}", what, s)),
+        None => Err(format!("missing {}", what)),
+    }
+}
+
+fn parse_location(s: Option<&str>) -> Result<Location, String> {
+    match s {
+        Some("home") => Ok(Location::Home),
+        Some("park") => Ok(Location::Park),
+        Some("shops") => Ok(Location::Shops),
+        Some("clinic") => Ok(Location::Clinic),
+        Some(s) => Err(format!("bad location: {}", s)),
+        None => Err(String::from("missing location")),
+    }
+}
+
+pub fn parse_command(line: &str) -> Result<ConsoleCommand, String> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let mut args = words.iter().skip(1).copied();
+    let command = match words.first() {
+        Some(c) => c.to_lowercase(),
+        None => return Err(String::from("type help for commands")),
+    };
+    let rv = match command.as_str() {
+        "sanity" => {
+            let v = args.next().unwrap_or("");
+            if v.starts_with('+') || v.starts_with('-') {
+                ConsoleCommand::ChangeSanity(parse(Some(v.trim_start_matches('+')), "sanity")?)
+            } else {
+                ConsoleCommand::SetSanity(parse(Some(v), "sanity")?)
+            }
+        }
+        "goto" => ConsoleCommand::Goto(
+            parse_location(args.next())?,
+            [parse(args.next(), "x")?, parse(args.next(), "y")?],
+        ),
+        "narrative" => match args.next() {
+            Some("jump") => ConsoleCommand::NarrativeJump(parse(args.next(), "row")?),
+            _ => return Err(String::from("narrative jump <row>")),
+        },
+        "spawn" => match args.next() {
+            Some("npc") => {
+                ConsoleCommand::SpawnNpc([parse(args.next(), "x")?, parse(args.next(), "y")?])
+            }
+            _ => return Err(String::from("spawn npc <x> <y>")),
+        },
+        "covid" => match args.next() {
+            Some("off") => ConsoleCommand::Covid(None),
+            v => ConsoleCommand::Covid(Some(parse(v, "risk")?)),
+        },
+        "lock" => ConsoleCommand::SetAccess(parse_location(args.next())?, false),
+        "unlock" => ConsoleCommand::SetAccess(parse_location(args.next())?, true),
+        "time" => match args.next() {
+            Some("scale") => ConsoleCommand::TimeScale(parse(args.next(), "scale")?),
+            _ => return Err(String::from("time scale <n>")),
+        },
+        "day" => ConsoleCommand::Day(parse(args.next(), "day")?),
+        "help" => ConsoleCommand::Help,
+        c => return Err(format!("unknown command: {}", c)),
+    };
+    match args.next() {
+        Some(extra) => Err(format!("unexpected: {}", extra)),
+        None => Ok(rv),
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Console {
+    pub open: bool,
+    input: String,
+    history: Vec<String>,
+    // Where Up/Down has got to in the history, if anywhere
+    history_pos: Option<usize>,
+    output: Vec<String>,
+}
+
+impl Console {
+    pub fn print(&mut self, line: String) {
+        println!("console: {}", line);
+        self.output.push(line);
+    }
+
+    fn history_back(&mut self) {
+        let pos = match self.history_pos {
+            Some(0) => 0,
+            Some(p) => p - 1,
+            None if self.history.is_empty() => return,
+            None => self.history.len() - 1,
+        };
+        self.history_pos = Some(pos);
+        self.input = self.history[pos].clone();
+    }
+
+    fn history_forward(&mut self) {
+        match self.history_pos {
+            Some(p) if p + 1 < self.history.len() => {
+                self.history_pos = Some(p + 1);
+                self.input = self.history[p + 1].clone();
+            }
+            _ => {
+                self.history_pos = None;
+                self.input.clear();
+            }
+        }
+    }
+
+    fn text(&self) -> String {
+        let skip = self.output.len().saturating_sub(OUTPUT_LINES);
+        let mut lines: Vec<&str> = self.output.iter().skip(skip).map(|l| l.as_str()).collect();
+        let prompt = format!("> {}_", self.input);
+        lines.push(&prompt);
+        lines.join("\n")
+    }
+}
+
+#[derive(Component)]
+pub struct ConsoleUiTag {}
+
+pub struct ConsolePlugin;
+
+impl Plugin for ConsolePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Console>()
+            .add_event::<ConsoleCommand>()
+            .add_startup_system(setup_console_ui)
+            // Before the game sees the keyboard, so typing doesn't walk the player around
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                console_input_system
+                    .after(InputSystem)
+                    .after("replay")
+                    .before("record"),
+            )
+            .add_system(console_command_system)
+            .add_system(console_goto_system)
+            .add_system(console_ui_system);
+    }
+}
+
+fn console_input_system(
+    mut console: ResMut<Console>,
+    mut keys: ResMut<Input<KeyCode>>,
+    // Not there when running headless
+    chars: Option<Res<Events<ReceivedCharacter>>>,
+    mut char_reader: Local<ManualEventReader<ReceivedCharacter>>,
+    mut command_events: EventWriter<ConsoleCommand>,
+) {
+    let typed: Vec<char> = match &chars {
+        Some(chars) => char_reader.iter(chars).map(|c| c.char).collect(),
+        None => vec![],
+    };
+
+    if keys.just_pressed(TOGGLE_KEY) {
+        console.open = !console.open;
+        keys.reset(TOGGLE_KEY);
+        return;
+    }
+    if !console.open {
+        return;
+    }
+
+    for c in typed {
+        if !c.is_control() && c != '`' {
+            console.input.push(c);
+        }
+    }
+    if keys.just_pressed(KeyCode::Back) {
+        console.input.pop();
+    }
+    if keys.just_pressed(KeyCode::Up) {
+        console.history_back();
+    }
+    if keys.just_pressed(KeyCode::Down) {
+        console.history_forward();
+    }
+    if keys.just_pressed(KeyCode::Return) {
+        let line = std::mem::take(&mut console.input);
+        console.history_pos = None;
+        if !line.trim().is_empty() {
+            console.history.push(line.clone());
+        }
+        console.print(format!("> {}", line));
+        match parse_command(&line) {
+            Ok(ConsoleCommand::Help) => {
+                for l in HELP {
+                    console.print(String::from(l));
+                }
+            }
+            Ok(command) => command_events.send(command),
+            Err(e) => console.print(e),
+        }
+    }
+
+    // The keyboard is the console's while it's open
+    let pressed: Vec<KeyCode> = keys.get_pressed().copied().collect();
+    for key in pressed {
+        keys.reset(key);
+    }
+}
+
+fn console_command_system(
+    mut commands: Commands,
+    mut command_events: EventReader<ConsoleCommand>,
+    mut console: ResMut<Console>,
+    mut state: ResMut<GameState>,
+    mut clock: ResMut<GameClock>,
+    mut forced_risk: ResMut<ForcedCovidRisk>,
+    asset_server: Res<AssetServer>,
+    mut sanity_events: EventWriter<SanityChanged>,
+) {
+    for command in command_events.iter() {
+        match command {
+            ConsoleCommand::ChangeSanity(delta) => {
+                state.change_sanity(*delta, &mut sanity_events);
+                console.print(format!("sanity is {}", state.get_sanity()));
+            }
+            ConsoleCommand::SetSanity(v) => {
+                let delta = v - state.get_sanity();
+                state.change_sanity(delta, &mut sanity_events);
+                console.print(format!("sanity is {}", state.get_sanity()));
+            }
+            ConsoleCommand::NarrativeJump(id) => {
+                match state.jump_narrative(*id, clock.seconds_since_startup()) {
+                    Ok(()) => console.print(format!("narrative at row {}", id)),
+                    Err(e) => console.print(e),
+                }
+            }
+            ConsoleCommand::SpawnNpc(tile) => {
+                spawn_npc(&mut commands, &asset_server, *tile, Behaviour::Wanderer);
+                console.print(format!("spawned an npc at {:?}", tile));
+            }
+            ConsoleCommand::Covid(risk) => {
+                forced_risk.0 = *risk;
+                match risk {
+                    Some(r) => console.print(format!("covid risk pinned at {}", r)),
+                    None => console.print(String::from("covid risk back to normal")),
+                }
+            }
+            ConsoleCommand::SetAccess(location, to) => {
+                state.area_access.set_access(*location, *to);
+                console.print(format!("{:?} access: {}", location, to));
+            }
+            ConsoleCommand::TimeScale(scale) => {
+                if *scale <= 0. {
+                    console.print(String::from("time scale must be more than 0"));
+                } else {
+                    clock.scale = *scale;
+                    console.print(format!("time scale {}", scale));
+                }
+            }
+            ConsoleCommand::Day(day) => {
+                let target = (*day - 1) as f64 * DAY_LENGTH;
+                let now = clock.seconds_since_startup();
+                if target <= now {
+                    console.print(String::from("can't go back in time"));
+                } else {
+                    clock.skip(target - now);
+                    // Don't charge the player for all the sanity ticks they skipped
+                    state.last_sanity_tick_update = clock.seconds_since_startup();
+                    console.print(format!("skipped to day {}", day));
+                }
+            }
+            // goto is console_goto_system's, and help never gets this far
+            ConsoleCommand::Goto(..) | ConsoleCommand::Help => {}
+        }
+    }
+}
+
+fn console_goto_system(
+    mut commands: Commands,
+    mut command_events: EventReader<ConsoleCommand>,
+    mut console: ResMut<Console>,
+    mut player_info: Query<&mut RigidBodyPositionComponent, With<Player>>,
+    mut environment_query: Query<(&mut TextureAtlasSprite, &mut Environment)>,
+    environment_collider_query: Query<Entity, With<EnvironmentCollider>>,
+    npc_query: Query<(Entity, &NPC)>,
+    pickup_query: Query<(Entity, &Pickup)>,
+    mut location_events: EventWriter<LocationChanged>,
+) {
+    for command in command_events.iter() {
+        if let ConsoleCommand::Goto(location, tile) = command {
+            let mut player_position = player_info.single_mut();
+            clear_location(&mut commands, &npc_query, &pickup_query);
+            teleport(
+                &Teleporter::new(*location, *tile),
+                &mut player_position,
+                &mut environment_query,
+                &mut commands,
+                &environment_collider_query,
+                &mut location_events,
+            );
+            console.print(format!("went to {:?} {:?}", location, tile));
+        }
+    }
+}
+
+fn setup_console_ui(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let height = (OUTPUT_LINES as f32 + 2.) * LINE_HEIGHT;
+    commands
+        .spawn_bundle(SpriteBundle {
+            transform: Transform {
+                translation: [0., SCREEN_HEIGHT / 2. - height / 2., 200.].into(),
+                ..Default::default()
+            },
+            sprite: Sprite {
+                color: Color::rgba(0., 0., 0., 0.85),
+                custom_size: Some(Vec2::new(SCREEN_WIDTH, height)),
+                ..Default::default()
+            },
+            visibility: Visibility { is_visible: false },
+            ..Default::default()
+        })
+        .insert(ConsoleUiTag {});
+
+    commands
+        .spawn_bundle(Text2dBundle {
+            text: Text::with_section(
+                "",
+                TextStyle {
+                    font: asset_server.load("fonts/monofonto.ttf"),
+                    font_size: 18.,
+                    color: Color::rgb(0.6, 1., 0.6),
+                },
+                TextAlignment {
+                    vertical: VerticalAlign::Top,
+                    horizontal: HorizontalAlign::Left,
+                },
+            ),
+            transform: Transform {
+                translation: [-SCREEN_WIDTH / 2. + 10., SCREEN_HEIGHT / 2. - 10., 201.].into(),
+                ..Default::default()
+            },
+            visibility: Visibility { is_visible: false },
+            ..Default::default()
+        })
+        .insert(ConsoleUiTag {});
+}
+
+fn console_ui_system(
+    console: Res<Console>,
+    mut query: Query<(&mut Visibility, Option<&mut Text>), With<ConsoleUiTag>>,
+) {
+    if !console.is_changed() {
+        return;
+    }
+    for (mut visibility, text) in query.iter_mut() {
+        visibility.is_visible = console.open;
+        if let Some(mut text) = text {
+            text.sections[0].value = console.text();
+        }
+    }
+}
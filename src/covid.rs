@@ -1,47 +1,220 @@
+// Catching covid. Being near people builds up a dose, which fades again once you're away from
+// them, and the bigger the dose the more likely the player has caught it.
 use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
+use rand::Rng;
 
+use crate::clock::GameClock;
+use crate::environment::{transmission_at, Environment};
+use crate::epidemic::Infection;
+use crate::events::CovidExposure;
 use crate::player::Player;
-use crate::{game,npc,environment,music};
+use crate::protection::{Protection, Protections};
+use crate::rng::GameRng;
+use crate::{game, npc};
 
-const COVID_RISK_MULTIPLIER: f32 = 0.4;
+// Dose per second when right up against someone, before the location's rate is applied
+const DOSE_RATE: f32 = 2.;
+// How quickly that falls off with distance, in physics units, before the location's reach
+const DOSE_SPREAD: f32 = 1.5;
+// Nothing at all from anyone further away than this, before the location's reach
 const COVID_SAFETY_DISTANCE: f32 = 6.;
+// Seconds for a dose to halve once nobody is adding to it
+const DOSE_HALF_LIFE: f32 = 20.;
+// What gets through a mask, and what's left on sanitised hands
+const MASK_FACTOR: f32 = 0.3;
+const SANITISER_FACTOR: f32 = 0.75;
+// How much further away people are when the player is keeping 1.5m apart
+const KEEP_APART_DISTANCE: f32 = 1.5;
+
+// Pins the covid risk at a value, whoever is around. For testing from the developer console.
+#[derive(Debug, Clone, Default)]
+pub struct ForcedCovidRisk(pub Option<f32>);
+
+// The player's dose so far
+#[derive(Debug, Clone, Default)]
+pub struct CovidDose {
+    pub dose: f32,
+    // The dose it takes to catch it this time. Drawn once up front, which gives the same odds as
+    // rolling every frame with one draw instead of hundreds.
+    tolerance: Option<f32>,
+}
+
+impl CovidDose {
+    // The chance the player has caught it by now
+    pub fn chance(&self) -> f32 {
+        infection_chance(self.dose)
+    }
+}
+
+// How easily it spreads somewhere. Each location has one, and so do some parts of them, like the
+// queue at the checkout.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transmission {
+    // Multiplies the dose
+    pub rate: f32,
+    // Multiplies how far it carries
+    pub reach: f32,
+}
+
+impl Default for Transmission {
+    fn default() -> Self {
+        Transmission {
+            rate: 1.,
+            reach: 1.,
+        }
+    }
+}
+
+// How much of the player's dose came from this NPC
+#[derive(Component, Debug, Clone, Default)]
+pub struct Exposure {
+    pub dose: f32,
+}
+
+pub struct CovidPlugin;
+
+impl Plugin for CovidPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ForcedCovidRisk>()
+            .init_resource::<CovidDose>()
+            .add_system(covid_system);
+    }
+}
+
+// Dose per second from someone `d` away
+pub fn dose_rate(d: f32) -> f32 {
+    dose_rate_in(d, &Transmission::default())
+}
+
+pub fn dose_rate_in(d: f32, transmission: &Transmission) -> f32 {
+    if d >= safety_distance(transmission) {
+        return 0.;
+    }
+    let spread = DOSE_SPREAD * transmission.reach;
+    DOSE_RATE * transmission.rate * (-(d * d) / (2. * spread * spread)).exp()
+}
+
+// Nobody further away than this can pass it on
+pub fn safety_distance(transmission: &Transmission) -> f32 {
+    COVID_SAFETY_DISTANCE * transmission.reach
+}
+
+// The same, with whatever the player is protected by at `now`
+pub fn protected_dose_rate(
+    d: f32,
+    transmission: &Transmission,
+    protections: &Protections,
+    now: f64,
+) -> f32 {
+    let mut d = d;
+    if protections.active(Protection::KeepApart, now) {
+        d += KEEP_APART_DISTANCE;
+    }
+    let mut rate = dose_rate_in(d, transmission);
+    if protections.active(Protection::Mask, now) {
+        rate *= MASK_FACTOR;
+    }
+    if protections.active(Protection::Sanitiser, now) {
+        rate *= SANITISER_FACTOR;
+    }
+    rate
+}
+
+pub fn infection_chance(dose: f32) -> f32 {
+    1. - (-dose).exp()
+}
+
+// What's left of a dose after `dt` seconds
+pub fn dose_fade(dt: f32) -> f32 {
+    0.5f32.powf(dt / DOSE_HALF_LIFE)
+}
 
 pub fn covid_system(
-    covid_info: Query<(&npc::NPC, &RigidBodyPositionComponent)>,
-    mut player_info: Query<(&Player, &mut RigidBodyPositionComponent), Without<npc::NPC>>,
+    mut covid_info: Query<
+        (
+            &mut Exposure,
+            &Infection,
+            &RigidBodyPositionComponent,
+            Entity,
+        ),
+        With<npc::NPC>,
+    >,
+    player_info: Query<(&Player, &RigidBodyPositionComponent), Without<npc::NPC>>,
+    environment_query: Query<&Environment>,
     mut state: ResMut<game::GameState>,
-    time: Res<Time>,
-    mut environment_query: Query<(&mut TextureAtlasSprite, &mut environment::Environment)>,
-    environment_collider_query: Query<Entity, With<environment::EnvironmentCollider>>,
-    mut music_state: ResMut<music::MusicState>,
-    asset_server: Res<AssetServer>,
-    mut commands: Commands,
+    time: Res<GameClock>,
+    forced_risk: Res<ForcedCovidRisk>,
+    mut dose: ResMut<CovidDose>,
+    mut rng: ResMut<GameRng>,
+    mut exposure_events: EventWriter<CovidExposure>,
+    // Only tell everyone once per forced exposure, not on every frame it's pinned
+    mut exposed: Local<bool>,
 ) {
-    let (_, mut player_pos) = player_info.single_mut();
-    let player_vector = player_pos.position.translation.vector;
+    if let Some(risk) = forced_risk.0 {
+        state.set_covid_risk(risk, &time);
+        if risk >= 1. && !*exposed {
+            exposure_events.send(CovidExposure { risk });
+        }
+        *exposed = risk >= 1.;
+        return;
+    }
+    *exposed = false;
 
-    let mut covid_risk = 0.;
+    let (_, player_pos) = player_info.single();
+    let player_vector = player_pos.position.translation.vector;
+    let location = environment_query.single().location;
+    // wherever the player is standing
+    let transmission = transmission_at(location, player_vector.x, player_vector.y);
 
-    for (_, position) in covid_info.iter() {
-        let person_vector = position.position.translation.vector;
-        let displacement = player_vector - person_vector;
-        let d = displacement.magnitude();
-        if d < COVID_SAFETY_DISTANCE {
-            covid_risk += COVID_RISK_MULTIPLIER * (COVID_SAFETY_DISTANCE - d);
+    let dt = time.delta_seconds();
+    let now = time.seconds_since_startup();
+    let fade = dose_fade(dt);
+    dose.dose *= fade;
+    for (mut exposure, infection, position, _) in covid_info.iter_mut() {
+        exposure.dose *= fade;
+        // Only someone who has it can pass it on
+        if !infection.is_infectious() {
+            continue;
         }
+        let person_vector = position.position.translation.vector;
+        let d = (player_vector - person_vector).magnitude();
+        let taken = protected_dose_rate(d, &transmission, &state.protections, now)
+            * state.vaccination.dose_factor()
+            * dt;
+        exposure.dose += taken;
+        dose.dose += taken;
     }
 
-    state.set_covid_risk(covid_risk, &time);
-    if covid_risk >= 1. {
-        state.covid_narrative_switch(
-            &time,
-            &mut player_pos,
-            &mut environment_query,
-            &mut commands,
-            &environment_collider_query,
-            &mut music_state,
-            &asset_server,
+    state.set_covid_risk(dose.chance(), &time);
+    if dose.dose <= 0. {
+        return;
+    }
+
+    let tolerance = *dose
+        .tolerance
+        .get_or_insert_with(|| -(1. - rng.gen::<f32>()).ln());
+    if dose.dose < tolerance {
+        return;
+    }
+
+    let source = covid_info
+        .iter()
+        .max_by(|a, b| a.0.dose.partial_cmp(&b.0.dose).unwrap())
+        .map(|(e, _, _, entity)| (entity, e.dose));
+    if let Some((entity, from)) = source {
+        println!(
+            "caught covid at dose {:.2}, {:.2} of it from {:?}",
+            dose.dose, from, entity
         );
     }
+    exposure_events.send(CovidExposure {
+        risk: dose.chance(),
+    });
+
+    // Starting again from nothing
+    *dose = CovidDose::default();
+    for (mut exposure, _, _, _) in covid_info.iter_mut() {
+        exposure.dose = 0.;
+    }
 }
@@ -0,0 +1,360 @@
+// Talking to NPCs. F next to one starts a conversation, a line at a time in a speech bubble above
+// them; F again, or waiting, moves it on. What they say comes from narrative/dialogue.csv, by who
+// they are and how things stand. Seeing it through tops up Social and cheers the player up, but
+// they're stood close to someone the whole time.
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use std::fs::File;
+
+use crate::clock::GameClock;
+use crate::environment::Location;
+use crate::events::{DialogueFinished, NeedRestored, SanityChanged};
+use crate::game::GameState;
+use crate::narrative::{csv_header, get, non_empty};
+use crate::needs::Need;
+use crate::npc::{Identity, NPC};
+use crate::player::Player;
+use crate::protection::Protection;
+use crate::wrap::{measure, wrap_text};
+use crate::TILE_SIZE;
+
+pub const DIALOGUE_FILE: &str = "./narrative/dialogue.csv";
+// Lines for anyone without any of their own
+pub const ANYONE: &str = "Anyone";
+
+const TALK_KEY: KeyCode = KeyCode::F;
+// In physics units, a little past the personal space NPCs keep
+pub const TALK_DISTANCE: f32 = 3.5;
+// Walking further off than this ends the conversation
+const WALK_OFF_DISTANCE: f32 = 5.;
+// How long each line stays up if the player doesn't move it on
+pub const LINE_SECONDS: f64 = 3.;
+const TALK_SANITY: i32 = 3;
+const TALK_SOCIAL: f32 = 15.;
+// In pixels, above the NPC's middle
+const BUBBLE_HEIGHT: f32 = 100.;
+const BUBBLE_FONT_SIZE: f32 = 16.;
+// Longer lines wrap onto more lines of at most this many pixels
+const BUBBLE_TEXT_WIDTH: f32 = 220.;
+const BUBBLE_PADDING: f32 = 10.;
+// Above the NPCs, below the heatmap
+const BUBBLE_Z: f32 = 40.;
+
+// When a set of lines is the one to use
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DialogueCondition {
+    Always,
+    // The player has a mask on
+    Masked,
+    // The player is fully vaccinated
+    Vaccinated,
+    // The shops are shut
+    Lockdown,
+}
+
+impl DialogueCondition {
+    pub fn met(&self, state: &GameState, now: f64) -> bool {
+        match self {
+            DialogueCondition::Always => true,
+            DialogueCondition::Masked => state.protections.active(Protection::Mask, now),
+            DialogueCondition::Vaccinated => state.vaccination.fully_vaccinated(),
+            DialogueCondition::Lockdown => !state.area_access.can_access(Location::Shops),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DialogueLines {
+    pub identity: String,
+    pub condition: DialogueCondition,
+    pub lines: Vec<String>,
+}
+
+pub fn load_dialogue(file: &str) -> Vec<DialogueLines> {
+    let file = File::open(file).expect("error opening dialogue csv");
+    let mut rdr = csv::Reader::from_reader(file);
+    let headers = rdr.headers().expect("error reading row").clone();
+    let h = csv_header(&headers);
+
+    let mut rv = Vec::new();
+    for x in rdr.records() {
+        let x = x.unwrap();
+        let identity = get(&h, &x, "Identity");
+        if !non_empty(identity) {
+            continue;
+        }
+        rv.push(DialogueLines {
+            identity: String::from(identity),
+            condition: str2condition(get(&h, &x, "When")),
+            lines: get(&h, &x, "Lines").split('|').map(String::from).collect(),
+        });
+    }
+    rv
+}
+
+fn str2condition(s: &str) -> DialogueCondition {
+    match s {
+        "" => DialogueCondition::Always,
+        "Masked" => DialogueCondition::Masked,
+        "Vaccinated" => DialogueCondition::Vaccinated,
+        "Lockdown" => DialogueCondition::Lockdown,
+        _ => panic!("bad dialogue condition >>{}<<", s),
+    }
+}
+
+// The first lines in the table for `identity` whose condition holds, falling back on anyone's
+pub fn choose_lines<'a>(
+    table: &'a [DialogueLines],
+    identity: &str,
+    state: &GameState,
+    now: f64,
+) -> Option<&'a [String]> {
+    let find = |who: &str| {
+        table
+            .iter()
+            .find(|d| d.identity == who && d.condition.met(state, now))
+            .map(|d| d.lines.as_slice())
+    };
+    find(identity).or_else(|| find(ANYONE))
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DialogueTable {
+    pub entries: Vec<DialogueLines>,
+}
+
+// The conversation going on right now, if there is one
+#[derive(Debug, Clone, Default)]
+pub struct Conversation {
+    with: Option<(Entity, String)>,
+    lines: Vec<String>,
+    line: usize,
+    // When the current line went up
+    shown_at: f64,
+}
+
+impl Conversation {
+    pub fn start(&mut self, npc: Entity, name: &str, lines: &[String], now: f64) {
+        self.with = Some((npc, String::from(name)));
+        self.lines = lines.to_vec();
+        self.line = 0;
+        self.shown_at = now;
+    }
+
+    pub fn with(&self) -> Option<Entity> {
+        self.with.as_ref().map(|(e, _)| *e)
+    }
+
+    pub fn line(&self) -> Option<&str> {
+        self.with.as_ref()?;
+        self.lines.get(self.line).map(|l| l.as_str())
+    }
+
+    // On to the next line. Returns who it was with if that was the last one.
+    pub fn advance(&mut self, now: f64) -> Option<String> {
+        self.line += 1;
+        self.shown_at = now;
+        if self.line < self.lines.len() {
+            return None;
+        }
+        self.stop().map(|(_, name)| name)
+    }
+
+    pub fn stop(&mut self) -> Option<(Entity, String)> {
+        self.lines.clear();
+        self.line = 0;
+        self.with.take()
+    }
+}
+
+#[derive(Component)]
+pub struct SpeechBubble {}
+
+pub struct DialoguePlugin;
+
+impl Plugin for DialoguePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DialogueTable>()
+            .init_resource::<Conversation>()
+            .add_startup_system(setup_dialogue)
+            .add_system(talk_system.label("talk"))
+            .add_system(conversation_system.label("converse").after("talk"))
+            .add_system(dialogue_finished_system.after("converse"))
+            .add_system(speech_bubble_system.after("converse"));
+    }
+}
+
+fn setup_dialogue(mut table: ResMut<DialogueTable>) {
+    table.entries = load_dialogue(DIALOGUE_FILE);
+}
+
+// F next to an NPC starts a conversation with them
+pub fn talk_system(
+    keys: Res<Input<KeyCode>>,
+    table: Res<DialogueTable>,
+    mut conversation: ResMut<Conversation>,
+    state: Res<GameState>,
+    mut npc_query: Query<(Entity, &mut NPC, &Identity, &RigidBodyPositionComponent)>,
+    player_query: Query<&RigidBodyPositionComponent, (With<Player>, Without<NPC>)>,
+    time: Res<GameClock>,
+) {
+    if conversation.with().is_some() || !keys.just_pressed(TALK_KEY) {
+        return;
+    }
+    let player = match player_query.get_single() {
+        Ok(p) => p.position.translation.vector,
+        Err(_) => return,
+    };
+    let now = time.seconds_since_startup();
+    let nearest = npc_query
+        .iter_mut()
+        .map(|(e, npc, identity, p)| {
+            let d = (p.position.translation.vector - player).magnitude();
+            (d, e, npc, identity)
+        })
+        .filter(|(d, _, _, _)| *d < TALK_DISTANCE)
+        .min_by(|a, b| a.0.total_cmp(&b.0));
+    if let Some((_, entity, mut npc, identity)) = nearest {
+        if let Some(lines) = choose_lines(&table.entries, &identity.name, &state, now) {
+            conversation.start(entity, &identity.name, lines, now);
+            // Stops to chat
+            npc.paused_until = f64::INFINITY;
+        }
+    }
+}
+
+// Moves a conversation on a line at a time, and ends it if either of them walks off
+pub fn conversation_system(
+    keys: Res<Input<KeyCode>>,
+    mut conversation: ResMut<Conversation>,
+    mut npc_query: Query<(&mut NPC, &RigidBodyPositionComponent)>,
+    player_query: Query<&RigidBodyPositionComponent, (With<Player>, Without<NPC>)>,
+    time: Res<GameClock>,
+    mut dialogue_events: EventWriter<DialogueFinished>,
+) {
+    let npc = match conversation.with() {
+        Some(npc) => npc,
+        None => return,
+    };
+    let player = match player_query.get_single() {
+        Ok(p) => p.position.translation.vector,
+        Err(_) => return,
+    };
+    let now = time.seconds_since_startup();
+    // The press that started the conversation doesn't also move it on
+    let pressed = keys.just_pressed(TALK_KEY) && conversation.shown_at < now;
+
+    let distance = npc_query
+        .get(npc)
+        .map(|(_, p)| (p.position.translation.vector - player).magnitude())
+        .ok();
+    if distance.map(|d| d > WALK_OFF_DISTANCE).unwrap_or(true) {
+        // They've gone, or the player has
+        conversation.stop();
+    } else if pressed || now - conversation.shown_at > LINE_SECONDS {
+        if let Some(name) = conversation.advance(now) {
+            dialogue_events.send(DialogueFinished { name });
+        }
+    }
+    if conversation.with().is_none() {
+        // Free to go about their business again
+        if let Ok((mut npc, _)) = npc_query.get_mut(npc) {
+            npc.paused_until = now;
+        }
+    }
+}
+
+// Seeing a conversation through to the end
+pub fn dialogue_finished_system(
+    mut dialogue_events: EventReader<DialogueFinished>,
+    mut state: ResMut<GameState>,
+    time: Res<GameClock>,
+    mut sanity_events: EventWriter<SanityChanged>,
+    mut need_events: EventWriter<NeedRestored>,
+) {
+    let now = time.seconds_since_startup();
+    for e in dialogue_events.iter() {
+        if state.needs.interact(Need::Social, TALK_SOCIAL, now) {
+            state.change_sanity(TALK_SANITY, &mut sanity_events);
+            need_events.send(NeedRestored {
+                need: Need::Social,
+                amount: TALK_SOCIAL,
+                from: "chat",
+            });
+        }
+        if !state.talked_to.contains(&e.name) {
+            state.talked_to.push(e.name.clone());
+        }
+    }
+}
+
+// Puts the current line up over whoever's saying it
+pub fn speech_bubble_system(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    fonts: Res<Assets<Font>>,
+    conversation: Res<Conversation>,
+    bubble_query: Query<Entity, With<SpeechBubble>>,
+    npc_query: Query<&RigidBodyPositionComponent, With<NPC>>,
+) {
+    if !conversation.is_changed() {
+        return;
+    }
+    for e in bubble_query.iter() {
+        commands.entity(e).despawn();
+    }
+
+    let (line, npc) = match (conversation.line(), conversation.with()) {
+        (Some(line), Some(npc)) => (line, npc),
+        _ => return,
+    };
+    let position = match npc_query.get(npc) {
+        Ok(p) => p.position.translation.vector * TILE_SIZE,
+        Err(_) => return,
+    };
+    let translation = Vec3::new(position.x, position.y + BUBBLE_HEIGHT, BUBBLE_Z);
+    let font = asset_server.load("fonts/monofonto.ttf");
+    let measure = measure(fonts.get(&font), BUBBLE_FONT_SIZE);
+    let lines = wrap_text(measure.as_ref(), BUBBLE_TEXT_WIDTH, line);
+    let width = lines.iter().map(|l| measure.width(l)).fold(0., f32::max);
+    let height = lines.len() as f32 * BUBBLE_FONT_SIZE * 1.2;
+
+    commands
+        .spawn_bundle(SpriteBundle {
+            transform: Transform {
+                translation,
+                ..Default::default()
+            },
+            sprite: Sprite {
+                color: Color::rgba(1., 1., 1., 0.9),
+                custom_size: Some(Vec2::new(
+                    width + 2. * BUBBLE_PADDING,
+                    height + BUBBLE_PADDING,
+                )),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(SpeechBubble {});
+    commands
+        .spawn_bundle(Text2dBundle {
+            text: Text::with_section(
+                lines.join("\n"),
+                TextStyle {
+                    font,
+                    font_size: BUBBLE_FONT_SIZE,
+                    color: Color::BLACK,
+                },
+                TextAlignment {
+                    vertical: VerticalAlign::Center,
+                    horizontal: HorizontalAlign::Center,
+                },
+            ),
+            transform: Transform {
+                translation: translation + Vec3::Z,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(SpeechBubble {});
+}
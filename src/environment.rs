@@ -1,5 +1,8 @@
-use crate::music::MusicState;
+use crate::covid::Transmission;
 use crate::narrative::{NarrativeActions, NarrativeTextMessage};
+use crate::needs::{add_interactable, Interactable, Need};
+use crate::pathfinding::NavGrid;
+use crate::teleportation::{covid_teleport_system, teleportation_system};
 use crate::{
     pickup::{spawn_pickup, Pickup},
     teleportation::add_teleporter,
@@ -8,11 +11,15 @@ use crate::{teleportation::Teleporter, SCREEN_HEIGHT, SCREEN_WIDTH, TILE_SIZE};
 use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
 
+// Every location is this many tiles across and down
+pub const GRID_SIZE: usize = 20;
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Location {
     Home,
     Park,
     Shops,
+    Clinic,
 }
 
 #[derive(Debug, Clone, Component)]
@@ -26,14 +33,37 @@ impl Environment {
     }
 }
 
+// The rooms, the walls in them, and the teleporters between them
+pub struct EnvironmentPlugin;
+
+impl Plugin for EnvironmentPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<NavGrid>()
+            .add_startup_system_to_stage(StartupStage::PreStartup, setup_physics)
+            .add_startup_system(setup_environment)
+            .add_system(teleportation_system.label("teleport").label("door"))
+            // Goes second, so it can see whether the player has just taken a door
+            .add_system(covid_teleport_system.label("teleport").after("door"));
+    }
+}
+
+fn setup_physics(mut rapier_config: ResMut<RapierConfiguration>) {
+    println!("Setting up physics..");
+    // Set the scale
+    rapier_config.scale = TILE_SIZE;
+
+    // Set gravity
+    rapier_config.gravity = [0., 0.].into();
+    println!("..done!");
+}
+
 pub fn setup_environment(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
-    mut music_state: ResMut<MusicState>,
     mut texture_atlases: ResMut<Assets<TextureAtlas>>,
 ) {
     let texture_handle = asset_server.load("environment.png");
-    let texture_atlas = TextureAtlas::from_grid(texture_handle, Vec2::new(1000.0, 1000.0), 3, 1);
+    let texture_atlas = TextureAtlas::from_grid(texture_handle, Vec2::new(1000.0, 1000.0), 4, 1);
     let texture_atlas_handle = texture_atlases.add(texture_atlas);
 
     let x_pos = -(SCREEN_WIDTH / 2.) + 500.;
@@ -56,31 +86,43 @@ pub fn setup_environment(
         })
         .insert(Environment::new(starting_location.clone()));
 
-    create_environment(starting_location, &mut commands, &mut music_state);
+    create_environment(starting_location, &mut commands);
 }
 
-pub fn create_environment(
-    location: Location,
-    commands: &mut Commands,
-    music_state: &mut ResMut<MusicState>,
-) {
+pub fn create_environment(location: Location, commands: &mut Commands) {
     let (environment_colliders, mut teleporters) =
         get_environment_collider_and_teleporters(location);
 
     for collider in &environment_colliders {
         add_environment_collider(commands, collider);
     }
+    commands.insert_resource(NavGrid::new(&environment_colliders));
 
     for (environment_collider, teleporter) in teleporters.drain(..) {
         add_teleporter(commands, &environment_collider, teleporter);
     }
 
-    let music_track_index = match location {
-        Location::Home => 0,
-        Location::Park => 1,
-        Location::Shops => 2,
-    };
-    music_state.switch_tracks(music_track_index);
+    for (environment_collider, interactable) in get_interactables(location).drain(..) {
+        add_interactable(commands, &environment_collider, interactable);
+    }
+}
+
+// The walls and furniture in `location`, without building any of it
+pub fn environment_colliders(location: Location) -> Vec<EnvironmentCollider> {
+    get_environment_collider_and_teleporters(location).0
+}
+
+// Every tile the ways out of `location` cover
+pub fn teleporter_tiles(location: Location) -> Vec<[usize; 2]> {
+    get_environment_collider_and_teleporters(location)
+        .1
+        .iter()
+        .flat_map(|(c, _)| {
+            (c.x_coordinates..c.x_coordinates + c.width).flat_map(move |x| {
+                (c.y_coordinates..c.y_coordinates + c.height).map(move |y| [x, y])
+            })
+        })
+        .collect()
 }
 
 fn get_environment_collider_and_teleporters(
@@ -117,12 +159,14 @@ fn get_environment_collider_and_teleporters(
             let environment_colliders = vec![
                 EnvironmentCollider::new(0, 0, 2, 20),
                 EnvironmentCollider::new(2, 18, 18, 2),
-                EnvironmentCollider::new(4, 0, 16, 2),
+                EnvironmentCollider::new(4, 0, 6, 2),
+                EnvironmentCollider::new(13, 0, 7, 2),
                 EnvironmentCollider::new(18, 2, 2, 12),
                 EnvironmentCollider::new(5, 2, 2, 2), // home sign
                 EnvironmentCollider::new(2, 14, 3, 4), // tree
                 EnvironmentCollider::new(14, 2, 4, 3), // swings
                 EnvironmentCollider::new(16, 12, 2, 2), // shop sign
+                EnvironmentCollider::new(8, 2, 2, 2), // clinic sign
             ];
             let teleporters = vec![
                 (
@@ -133,6 +177,10 @@ fn get_environment_collider_and_teleporters(
                     EnvironmentCollider::new(18, 14, 2, 4),
                     Teleporter::new(Location::Shops, [2, 2]),
                 ),
+                (
+                    EnvironmentCollider::new(10, 0, 3, 1),
+                    Teleporter::new(Location::Clinic, [9, 15]),
+                ),
             ];
 
             (environment_colliders, teleporters)
@@ -162,9 +210,127 @@ fn get_environment_collider_and_teleporters(
 
             (environment_colliders, teleporters)
         }
+        Location::Clinic => {
+            let environment_colliders = vec![
+                EnvironmentCollider::new(0, 0, 1, 20),  // far left wall
+                EnvironmentCollider::new(1, 0, 19, 1),  // top wall
+                EnvironmentCollider::new(19, 1, 1, 18), // far right wall
+                EnvironmentCollider::new(1, 19, 8, 1),  // bottom wall, left of the door
+                EnvironmentCollider::new(12, 19, 8, 1), // bottom wall, right of the door
+                EnvironmentCollider::new(2, 1, 4, 4),   // left booth
+                EnvironmentCollider::new(8, 1, 4, 4),   // middle booth
+                EnvironmentCollider::new(14, 1, 4, 4),  // right booth
+                EnvironmentCollider::new(3, 12, 6, 2),  // reception
+                EnvironmentCollider::new(14, 12, 4, 2), // waiting chairs
+            ];
+            let teleporters = vec![(
+                EnvironmentCollider::new(9, 19, 3, 1),
+                Teleporter::new(Location::Park, [10, 3]),
+            )];
+
+            (environment_colliders, teleporters)
+        }
+    }
+}
+
+// The couch in front of the TV, which the stats keep an eye on
+pub const TV: &str = "TV";
+
+// The things the player can walk up to and use. Each zone sits one tile out from the furniture
+// it belongs to, so the player can stand in it.
+fn get_interactables(location: Location) -> Vec<(EnvironmentCollider, Interactable)> {
+    match location {
+        Location::Home => vec![
+            (
+                EnvironmentCollider::new(1, 1, 4, 4).grown(),
+                Interactable::new("shower", Need::Hygiene, 50.),
+            ),
+            (
+                EnvironmentCollider::new(15, 1, 4, 5).grown(),
+                Interactable::new("bed", Need::Sleep, 60.),
+            ),
+            (
+                EnvironmentCollider::new(4, 17, 8, 2).grown(),
+                Interactable::new("fridge", Need::Hunger, 40.),
+            ),
+            (
+                EnvironmentCollider::new(10, 1, 3, 3).grown(),
+                Interactable::new("computer", Need::Boredom, 25.),
+            ),
+            (
+                EnvironmentCollider::new(16, 11, 3, 8).grown(),
+                Interactable::new(TV, Need::Boredom, 15.),
+            ),
+        ],
+        Location::Park => vec![(
+            EnvironmentCollider::new(14, 2, 4, 3).grown(),
+            Interactable::new("swings", Need::Boredom, 30.),
+        )],
+        Location::Shops => vec![(
+            EnvironmentCollider::new(1, 13, 3, 3).grown(),
+            Interactable::new("checkout", Need::Hunger, 60.),
+        )],
+        Location::Clinic => vec![],
+    }
+}
+
+// How easily covid spreads in each location. Home only matters when someone's visiting.
+fn get_transmission(location: Location) -> Transmission {
+    match location {
+        Location::Home => Transmission {
+            rate: 1.2,
+            reach: 1.2,
+        },
+        // Out in the open air
+        Location::Park => Transmission {
+            rate: 0.3,
+            reach: 0.8,
+        },
+        // Indoors, and the air goes round and round
+        Location::Shops => Transmission {
+            rate: 1.5,
+            reach: 1.5,
+        },
+        // Indoors, but the windows are open and everyone's masked up
+        Location::Clinic => Transmission {
+            rate: 0.8,
+            reach: 1.2,
+        },
+    }
+}
+
+// Parts of a location where it spreads differently to the rest of it
+fn get_transmission_zones(location: Location) -> Vec<(EnvironmentCollider, Transmission)> {
+    match location {
+        Location::Home | Location::Park | Location::Clinic => vec![],
+        Location::Shops => vec![
+            (
+                EnvironmentCollider::new(1, 10, 6, 3), // checkout queue
+                Transmission {
+                    rate: 2.5,
+                    reach: 1.8,
+                },
+            ),
+            (
+                EnvironmentCollider::new(7, 3, 9, 4), // between the aisles
+                Transmission {
+                    rate: 1.8,
+                    reach: 1.5,
+                },
+            ),
+        ],
     }
 }
 
+// How easily it spreads at (x, y), in physics units
+pub fn transmission_at(location: Location, x: f32, y: f32) -> Transmission {
+    get_transmission_zones(location)
+        .into_iter()
+        .find(|(zone, _)| zone.contains(x, y))
+        .map(|(_, t)| t)
+        .unwrap_or_else(|| get_transmission(location))
+}
+
 #[derive(Component, Debug, Clone)]
 pub struct EnvironmentCollider {
     pub x_coordinates: usize,
@@ -182,6 +348,36 @@ impl EnvironmentCollider {
             height,
         }
     }
+
+    // The same area, extended by a tile on every side
+    fn grown(self) -> Self {
+        let x_coordinates = self.x_coordinates.saturating_sub(1);
+        let y_coordinates = self.y_coordinates.saturating_sub(1);
+        Self {
+            x_coordinates,
+            y_coordinates,
+            width: self.x_coordinates + self.width + 1 - x_coordinates,
+            height: self.y_coordinates + self.height + 1 - y_coordinates,
+        }
+    }
+
+    // The bottom left and top right corners, in physics units
+    pub fn bounds(&self) -> ([f32; 2], [f32; 2]) {
+        let (width, height) = (self.width as f32, self.height as f32);
+        let (centre_x, centre_y) =
+            tile_coords_to_screen_pos(self.x_coordinates, width, self.y_coordinates, height);
+        let (centre_x, centre_y) = (centre_x / TILE_SIZE, centre_y / TILE_SIZE);
+        (
+            [centre_x - width / 2., centre_y - height / 2.],
+            [centre_x + width / 2., centre_y + height / 2.],
+        )
+    }
+
+    // Whether (x, y), in physics units, is inside
+    fn contains(&self, x: f32, y: f32) -> bool {
+        let (min, max) = self.bounds();
+        (min[0]..=max[0]).contains(&x) && (min[1]..=max[1]).contains(&y)
+    }
 }
 
 fn add_environment_collider(commands: &mut Commands, environment_collider: &EnvironmentCollider) {
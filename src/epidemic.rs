@@ -0,0 +1,265 @@
+// Covid out in the community. Victoria as a whole moves through susceptible, exposed, infectious
+// and recovered a day at a time, and the NPCs the player meets are a sample of it. Out in public
+// they catch it from each other, and only the infectious ones can pass it on to the player.
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use rand::Rng;
+
+use crate::clock::GameClock;
+use crate::covid::{dose_fade, dose_rate_in};
+use crate::environment::{transmission_at, Environment};
+use crate::events::TextReceived;
+use crate::game::{GameState, DAY_LENGTH};
+use crate::npc::NPC;
+use crate::rng::GameRng;
+
+const VIC_POPULATION: f64 = 6_700_000.;
+// Per day: new exposures per infectious contact, exposed people becoming infectious, and
+// infectious people recovering
+const TRANSMISSION_RATE: f64 = 0.2;
+const INCUBATION_RATE: f64 = 0.2;
+const RECOVERY_RATE: f64 = 0.1;
+// How an NPC's own infection plays out, in days
+const INCUBATION_DAYS: f64 = 3.;
+const INFECTIOUS_DAYS: f64 = 7.;
+// How often VIC GOV texts the numbers
+const CASE_REPORT_DAYS: i32 = 7;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sir {
+    Susceptible,
+    Exposed,
+    Infectious,
+    Recovered,
+}
+
+// Where one NPC is up to
+#[derive(Component, Debug, Clone)]
+pub struct Infection {
+    pub state: Sir,
+    // When being exposed or infectious ends
+    pub until: f64,
+    dose: f32,
+    // The dose it takes for them to catch it
+    tolerance: f32,
+}
+
+impl Infection {
+    pub fn new(state: Sir, until: f64, tolerance: f32) -> Self {
+        Infection {
+            state,
+            until,
+            dose: 0.,
+            tolerance,
+        }
+    }
+
+    pub fn is_infectious(&self) -> bool {
+        self.state == Sir::Infectious
+    }
+
+    // Returns whether that was enough for them to catch it
+    pub fn expose(&mut self, dose: f32, now: f64) -> bool {
+        if self.state != Sir::Susceptible {
+            return false;
+        }
+        self.dose += dose;
+        if self.dose < self.tolerance {
+            return false;
+        }
+        self.state = Sir::Exposed;
+        self.until = now + INCUBATION_DAYS * DAY_LENGTH;
+        true
+    }
+
+    // Moves on to the next state once this one's run its course
+    pub fn progress(&mut self, now: f64) {
+        if now < self.until {
+            return;
+        }
+        match self.state {
+            Sir::Exposed => {
+                self.state = Sir::Infectious;
+                self.until = now + INFECTIOUS_DAYS * DAY_LENGTH;
+            }
+            Sir::Infectious => self.state = Sir::Recovered,
+            Sir::Susceptible | Sir::Recovered => {}
+        }
+    }
+}
+
+// Victoria, as fractions of the population
+#[derive(Debug, Clone)]
+pub struct Epidemic {
+    pub susceptible: f64,
+    pub exposed: f64,
+    pub infectious: f64,
+    pub recovered: f64,
+    // Everyone who's become infectious since the last report
+    cases_since_report: f64,
+    last_day: i32,
+}
+
+impl Default for Epidemic {
+    fn default() -> Self {
+        Epidemic {
+            susceptible: 0.85,
+            exposed: 0.05,
+            infectious: 0.1,
+            recovered: 0.,
+            cases_since_report: 0.,
+            last_day: 0,
+        }
+    }
+}
+
+impl Epidemic {
+    // The fraction of people who are infectious right now
+    pub fn prevalence(&self) -> f64 {
+        self.infectious
+    }
+
+    pub fn step_day(&mut self) {
+        let exposures = TRANSMISSION_RATE * self.susceptible * self.infectious;
+        let cases = INCUBATION_RATE * self.exposed;
+        let recoveries = RECOVERY_RATE * self.infectious;
+        self.susceptible -= exposures;
+        self.exposed += exposures - cases;
+        self.infectious += cases - recoveries;
+        self.recovered += recoveries;
+        self.cases_since_report += cases;
+    }
+
+    // What state someone out and about is likely to be in
+    fn draw(&self, now: f64, rng: &mut GameRng) -> Infection {
+        let tolerance = -(1. - rng.gen::<f32>()).ln();
+        let u: f64 = rng.gen();
+        // Part way through, since they didn't all catch it today
+        let left = rng.gen::<f64>();
+        if u < self.infectious {
+            Infection::new(
+                Sir::Infectious,
+                now + left * INFECTIOUS_DAYS * DAY_LENGTH,
+                tolerance,
+            )
+        } else if u < self.infectious + self.exposed {
+            Infection::new(
+                Sir::Exposed,
+                now + left * INCUBATION_DAYS * DAY_LENGTH,
+                tolerance,
+            )
+        } else if u < self.infectious + self.exposed + self.recovered {
+            Infection::new(Sir::Recovered, now, tolerance)
+        } else {
+            Infection::new(Sir::Susceptible, now, tolerance)
+        }
+    }
+
+    fn report(&mut self) -> String {
+        let cases = (self.cases_since_report * VIC_POPULATION).round() as u64;
+        let active = ((self.exposed + self.infectious) * VIC_POPULATION).round() as u64;
+        self.cases_since_report = 0.;
+        format!(
+            "COVID-19: {} new cases in Victoria this week, and {} active cases. Stay home. Stay safe. Save lives.",
+            with_commas(cases),
+            with_commas(active)
+        )
+    }
+}
+
+pub struct EpidemicPlugin;
+
+impl Plugin for EpidemicPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Epidemic>()
+            .add_system(epidemic_system)
+            // Before anyone who's just turned up gets cleared away by a teleport
+            .add_system(npc_infection_arrival_system.before("teleport"))
+            .add_system(npc_spread_system);
+    }
+}
+
+// Moves Victoria on a day at a time, and passes on the numbers every week
+pub fn epidemic_system(
+    mut epidemic: ResMut<Epidemic>,
+    state: Res<GameState>,
+    mut text_events: EventWriter<TextReceived>,
+) {
+    if state.date == 0 {
+        return;
+    }
+    if epidemic.last_day == 0 {
+        epidemic.last_day = state.date;
+    }
+    while epidemic.last_day < state.date {
+        epidemic.last_day += 1;
+        epidemic.step_day();
+        if epidemic.last_day % CASE_REPORT_DAYS == 0 {
+            let body = epidemic.report();
+            text_events.send(TextReceived {
+                sender: String::from("VIC GOV"),
+                body,
+            });
+        }
+    }
+}
+
+// Everyone who turns up is a sample of the community
+pub fn npc_infection_arrival_system(
+    mut commands: Commands,
+    new_npcs: Query<Entity, (With<NPC>, Without<Infection>)>,
+    epidemic: Res<Epidemic>,
+    time: Res<GameClock>,
+    mut rng: ResMut<GameRng>,
+) {
+    for e in new_npcs.iter() {
+        let infection = epidemic.draw(time.seconds_since_startup(), &mut rng);
+        commands.entity(e).insert(infection);
+    }
+}
+
+// NPCs catching it from each other, the same way the player does
+pub fn npc_spread_system(
+    mut npcs: Query<(&mut Infection, &RigidBodyPositionComponent, Entity), With<NPC>>,
+    environment_query: Query<&Environment>,
+    time: Res<GameClock>,
+) {
+    let location = environment_query.single().location;
+    let now = time.seconds_since_startup();
+    let dt = time.delta_seconds();
+    let fade = dose_fade(dt);
+
+    let infectious: Vec<_> = npcs
+        .iter()
+        .filter(|(i, _, _)| i.is_infectious())
+        .map(|(_, p, e)| (e, p.position.translation.vector))
+        .collect();
+
+    for (mut infection, position, entity) in npcs.iter_mut() {
+        infection.progress(now);
+        infection.dose *= fade;
+        let at = position.position.translation.vector;
+        let transmission = transmission_at(location, at.x, at.y);
+        let dose: f32 = infectious
+            .iter()
+            .filter(|(e, _)| *e != entity)
+            .map(|(_, p)| dose_rate_in((at - p).magnitude(), &transmission) * dt)
+            .sum();
+        if dose > 0. {
+            infection.expose(dose, now);
+        }
+    }
+}
+
+// 1234567 => 1,234,567
+fn with_commas(n: u64) -> String {
+    let digits = n.to_string();
+    let mut rv = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            rv.push(',');
+        }
+        rv.push(c);
+    }
+    rv
+}
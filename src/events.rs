@@ -0,0 +1,87 @@
+// The events systems use to talk to each other. Whoever causes something sends the event, and
+// everything that cares about it (audio, UI, stats, the narrative...) reads it, so no module
+// needs to know who else is listening.
+use bevy::prelude::*;
+
+use crate::environment::Location;
+use crate::narrative::NarrativeActions;
+use crate::needs::Need;
+use crate::pickup::Pickup;
+
+// Every other plugin relies on these being registered, exactly once
+pub struct EventsPlugin;
+
+impl Plugin for EventsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SanityChanged>()
+            .add_event::<TextReceived>()
+            .add_event::<LocationChanged>()
+            .add_event::<PickupCollected>()
+            .add_event::<CovidExposure>()
+            .add_event::<IsolationBreached>()
+            .add_event::<NarrativeEventFired>()
+            .add_event::<NeedRestored>()
+            .add_event::<DialogueFinished>();
+    }
+}
+
+// Sanity went up or down by `delta`, and is now `sanity`
+#[derive(Debug, Clone)]
+pub struct SanityChanged {
+    pub delta: i32,
+    pub sanity: i32,
+}
+
+// A text arrived on the phone
+#[derive(Debug, Clone)]
+pub struct TextReceived {
+    pub sender: String,
+    pub body: String,
+}
+
+// The player has just arrived somewhere new
+#[derive(Debug, Clone)]
+pub struct LocationChanged {
+    pub from: Location,
+    pub to: Location,
+}
+
+// The player walked over a pickup. `actions` are the narrative actions it carries.
+#[derive(Debug, Clone)]
+pub struct PickupCollected {
+    pub pickup: Pickup,
+    pub actions: NarrativeActions,
+}
+
+// The player has become a close contact
+#[derive(Debug, Clone)]
+pub struct CovidExposure {
+    pub risk: f32,
+}
+
+// The player went somewhere other than home while isolating
+#[derive(Debug, Clone)]
+pub struct IsolationBreached {
+    pub to: Location,
+}
+
+// The player used something (or made a call) that topped up a need
+#[derive(Debug, Clone)]
+pub struct NeedRestored {
+    pub need: Need,
+    pub amount: f32,
+    // What did it, e.g. "fridge", "phone" or "chat"
+    pub from: &'static str,
+}
+
+// A narrative row's criterion was met, so its actions should now happen
+#[derive(Debug, Clone)]
+pub struct NarrativeEventFired {
+    pub actions: NarrativeActions,
+}
+
+// The player talked an NPC through to the end of what they had to say
+#[derive(Debug, Clone)]
+pub struct DialogueFinished {
+    pub name: String,
+}
@@ -1,30 +1,44 @@
+use crate::clock::GameClock;
 use crate::environment::{Environment, Location};
-use crate::music::MusicState;
-use crate::narrative::{NarrativeActions, NarrativeCriterion, NarrativeEvent};
+use crate::epidemic::{Infection, Sir};
+use crate::events::{
+    CovidExposure, LocationChanged, NarrativeEventFired, PickupCollected, SanityChanged,
+    TextReceived,
+};
+use crate::narrative::{NarrativeActions, NarrativeCriterion, NarrativeEvent, SpawnableNpc};
+use crate::pathfinding::NavGrid;
+use crate::needs::Needs;
 use crate::player::Player;
-use crate::sfx::{SFXSystem, SoundEffect};
-use crate::{environment, narrative, teleportation, ui, SCREEN_HEIGHT, SCREEN_WIDTH};
+use crate::isolation::Isolation;
+use crate::protection::Protections;
+use crate::vaccination::{booking_text, Vaccination};
+use crate::{environment, narrative, TILE_SIZE};
 use crate::{npc, pickup};
 use bevy::prelude::*;
-use bevy_rapier2d::prelude::*;
 
 pub const STARTING_SANITY: i32 = 100;
+// Seconds in a game day
+pub const DAY_LENGTH: f64 = 5.;
 const COVID_RISK_THRESHOLD: f32 = 0.05;
+// NPCs closer than this (in pixels) count towards the crowd around the player
+const CROWD_DISTANCE: f32 = 4. * TILE_SIZE;
 
 pub struct AreaAccessControl {
     home: bool,
     park: bool,
     shops: bool,
+    clinic: bool,
+    // Everywhere but home is off limits, whatever the narrative says
+    isolating: bool,
+    // Places open to the fully vaccinated, even while they're locked to everyone else
+    vaccinated_only: Vec<Location>,
+    vaccinated: bool,
 }
 
 #[derive(Default)]
 pub struct GameState {
-    messages: Vec<TextMessage>,
-    // Option<...> for the Default trait
-    pub text_msg_parent: Option<Entity>,
     pub date: i32,
     pub last_date: i32,
-    pub last_msg_animation_time: f64,
 
     pub area_access: AreaAccessControl,
 
@@ -33,8 +47,22 @@ pub struct GameState {
     // The last time sanity changed due to the passage of time
     // This gets updated (a) when we change sanity, or (b) when we switch environment
     pub last_sanity_tick_update: f64,
-
-    // Covid risk related information
+    // Where we were last frame, and when we got there
+    last_location: Option<Location>,
+    location_entered_at: f64,
+    // Hunger, hygiene etc. -- these feed into sanity on every sanity tick
+    pub needs: Needs,
+    // Masks and so on, and when they run out
+    pub protections: Protections,
+    pub isolation: Isolation,
+    pub vaccination: Vaccination,
+    // Everyone the player has finished a conversation with, by name
+    pub talked_to: Vec<String>,
+    // Everyone the story has brought in. Whoever the player hasn't talked to yet turns up again
+    // wherever they go next, so the story can't be left waiting on someone who's been cleared away.
+    pub story_npcs: Vec<SpawnableNpc>,
+
+    // Covid risk related information. The risk is the chance the player has caught it.
     pub show_covid_risk: bool,
     pub covid_risk: f32,
     pub last_covid_risk_shown: f64,
@@ -47,63 +75,23 @@ pub struct GameState {
     next_covid_narrative_id: usize,
     in_covid_narrative: bool,
     narrative_last_event: f64,
+    narrative_finished: bool,
     game_over_image: Handle<Image>,
     game_over: bool,
     game_over_image_entity: Option<Entity>,
 }
 
-struct TextMessage {
-    text: String,
-    sender: String,
-    e: Option<Entity>,
-}
-
-pub fn debug_keys(
-    mut commands: Commands,
-    key: Res<Input<KeyCode>>,
-    mut state: ResMut<GameState>,
-    mut music_state: ResMut<MusicState>,
-    time: Res<Time>,
-    asset_server: Res<AssetServer>,
-    player: Query<(&Player, &Transform)>,
-) {
-    if key.just_pressed(KeyCode::C) {
-        state.show_covid_risk = !state.show_covid_risk;
-        state.last_covid_risk_shown = time.seconds_since_startup();
-    }
-    if key.just_pressed(KeyCode::V) {
-        state.covid_risk += 0.1;
-    }
-    if key.just_pressed(KeyCode::B) {
-        state.covid_risk -= 0.1;
-    }
-    if key.just_pressed(KeyCode::P) {
-        let (_, player_tx) = player.single();
-        state.change_sanity(3);
-        ui::spawn_sanity_number(
-            3,
-            &mut commands,
-            asset_server.load("fonts/monofonto.ttf"),
-            player_tx.translation,
-        );
-    }
-    if key.just_pressed(KeyCode::O) {
-        let (_, player_tx) = player.single();
-        ui::spawn_sanity_number(
-            -7,
-            &mut commands,
-            asset_server.load("fonts/monofonto.ttf"),
-            player_tx.translation,
-        );
-    }
-    if key.just_pressed(KeyCode::M) {
-        println!("M PRESSED");
-        let next_index = if music_state.changing_from == 0 { 1 } else { 0 };
-        music_state.switch_tracks(next_index);
-    }
-
-    if key.just_pressed(KeyCode::G) {
-        state.change_sanity(-100);
+// The game state, and the narrative that drives it
+pub struct NarrativePlugin;
+
+impl Plugin for NarrativePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameState>()
+            .add_startup_system(setup_state)
+            .add_system(logic)
+            .add_system(narrative_action_system)
+            .add_system(story_npc_system)
+            .add_system(covid_exposure_system);
     }
 }
 
@@ -114,52 +102,132 @@ pub fn setup_state(mut state: ResMut<GameState>, asset_server: Res<AssetServer>)
 pub fn logic(
     mut commands: Commands,
     mut state: ResMut<GameState>,
-    time: Res<Time>,
-    asset_server: Res<AssetServer>,
+    time: Res<GameClock>,
     player: Query<(&Player, &Transform)>,
     pickups_query: Query<(&pickup::Pickup,)>,
     environment_query: Query<(&environment::Environment,)>,
-    mut sfx_system: ResMut<SFXSystem>,
+    npc_query: Query<&Transform, With<npc::NPC>>,
+    mut sanity_events: EventWriter<SanityChanged>,
+    mut narrative_events: EventWriter<NarrativeEventFired>,
 ) {
     if state.sanity <= 0 {
         game_over(&mut commands, &mut state);
         return;
     }
 
-    state.date = 1 + (time.seconds_since_startup() / 5.) as i32;
+    state.date = 1 + (time.seconds_since_startup() / DAY_LENGTH) as i32;
     if state.last_date < state.date {
         state.last_date = state.date;
         state.new_day();
     }
 
     let (environment,) = environment_query.single();
-    let sanity_change = state.deduct_sanity_on_timer(time.seconds_since_startup(), environment);
-    if sanity_change != 0 {
-        let (_, player_tx) = player.single();
-        ui::spawn_sanity_number(
-            sanity_change,
-            &mut commands,
-            asset_server.load("fonts/monofonto.ttf"),
-            player_tx.translation,
-        );
-        if sanity_change > 0 {
-            sfx_system.play_sfx(SoundEffect::SanityUp);
-        } else {
-            sfx_system.play_sfx(SoundEffect::SanityDown);
-        }
-    }
+    let (_, player_tx) = player.single();
+    let nearby_npcs = npc_query
+        .iter()
+        .filter(|npc_tx| {
+            npc_tx.translation.truncate().distance(player_tx.translation.truncate()) < CROWD_DISTANCE
+        })
+        .count();
+    state.sanity_on_timer(
+        time.seconds_since_startup(),
+        environment,
+        nearby_npcs,
+        &mut sanity_events,
+    );
 
     state.run_narrative(
         &time,
-        &mut commands,
-        &asset_server,
-        &player,
         &pickups_query,
         &environment_query,
-        &mut sfx_system,
+        &mut narrative_events,
     );
 }
 
+// Carries out whatever the narrative (or a collected pickup) asks for
+pub fn narrative_action_system(
+    mut commands: Commands,
+    mut state: ResMut<GameState>,
+    asset_server: Res<AssetServer>,
+    time: Res<GameClock>,
+    mut fired_events: EventReader<NarrativeEventFired>,
+    mut pickup_events: EventReader<PickupCollected>,
+    mut sanity_events: EventWriter<SanityChanged>,
+    mut text_events: EventWriter<TextReceived>,
+) {
+    for e in fired_events.iter() {
+        state.do_narrative_actions(
+            e.actions.clone(),
+            &mut commands,
+            &asset_server,
+            &mut sanity_events,
+            &mut text_events,
+            time.seconds_since_startup(),
+        );
+    }
+    for e in pickup_events.iter() {
+        state.do_narrative_actions(
+            e.actions.clone(),
+            &mut commands,
+            &asset_server,
+            &mut sanity_events,
+            &mut text_events,
+            time.seconds_since_startup(),
+        );
+    }
+}
+
+// Someone the story brings along doesn't mind getting close to the player. They aren't a sample
+// of the community either, so they come healthy and stay that way.
+fn spawn_story_npc(
+    commands: &mut Commands,
+    asset_server: &Res<AssetServer>,
+    s: &SpawnableNpc,
+    tile: [usize; 2],
+) {
+    let npc = npc::spawn_npc(commands, asset_server, tile, s.behaviour);
+    commands
+        .entity(npc)
+        .insert(npc::Identity { name: s.name.clone() })
+        .insert(npc::IgnoresPersonalSpace {})
+        .insert(Infection::new(Sir::Susceptible, f64::INFINITY, f32::INFINITY));
+}
+
+// Brings anyone the story is still waiting on along to wherever the player has gone, as long as
+// it's somewhere you'd meet up
+pub fn story_npc_system(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    state: Res<GameState>,
+    mut location_events: EventReader<LocationChanged>,
+) {
+    for e in location_events.iter() {
+        if matches!(e.to, Location::Home | Location::Clinic) || state.isolation.active() {
+            continue;
+        }
+        // The resource won't have caught up with the move yet
+        let grid = NavGrid::new(&environment::environment_colliders(e.to));
+        for s in &state.story_npcs {
+            if state.talked_to.contains(&s.name) {
+                continue;
+            }
+            if let Some(tile) = grid.nearest_walkable(s.location) {
+                spawn_story_npc(&mut commands, &asset_server, s, tile);
+            }
+        }
+    }
+}
+
+pub fn covid_exposure_system(
+    mut state: ResMut<GameState>,
+    time: Res<GameClock>,
+    mut exposure_events: EventReader<CovidExposure>,
+) {
+    for _ in exposure_events.iter() {
+        state.covid_narrative_switch(&time);
+    }
+}
+
 fn game_over(commands: &mut Commands, state: &mut GameState) {
     if !state.game_over {
         state.game_over = true;
@@ -185,9 +253,43 @@ fn time_for_sanity_loss() -> f64 {
     10.
 }
 
-// How much sanity do we lose then?
-fn sanity_loss_tick() -> i32 {
-    -1
+// How much sanity do we lose (/gain) then? Depends on where we are, how crowded it is, and how
+// long we've been there.
+pub fn location_sanity_tick(location: Location, nearby_npcs: usize, time_in_location: f64) -> i32 {
+    match location {
+        // Slow decay, and cabin fever sets in the longer you stay in
+        Location::Home => {
+            if time_in_location > 60. {
+                -2
+            } else {
+                -1
+            }
+        }
+        // Slow recovery, better once you've settled in, unless it gets busy
+        Location::Park => {
+            let base = if time_in_location > 30. { 2 } else { 1 };
+            if nearby_npcs >= 2 {
+                base - 1
+            } else {
+                base
+            }
+        }
+        // A quiet shop is a nice outing, a crowded one is not, and the queue wears on you
+        Location::Shops => {
+            let crowd = match nearby_npcs {
+                0 => 1,
+                1 | 2 => 0,
+                n => -((n - 2) as i32),
+            };
+            if time_in_location > 60. {
+                crowd - 1
+            } else {
+                crowd
+            }
+        }
+        // In and out, and nobody to talk to while you wait
+        Location::Clinic => 0,
+    }
 }
 
 impl GameState {
@@ -200,183 +302,68 @@ impl GameState {
         let _dummy: Handle<Image> = asset_server.load("close_contact_alert.png");
     }
 
-    fn add_text_message(
-        &mut self,
-        sender: &str,
-        msg: &str,
-        time: &Res<Time>,
-        commands: &mut Commands,
-        asset_server: &Res<AssetServer>,
-        sfx_system: &mut SFXSystem,
-    ) {
-        self.messages.push(TextMessage {
-            sender: String::from(sender),
-            text: String::from(msg),
-            e: None,
-        });
-        self.last_msg_animation_time = time.seconds_since_startup();
-
-        sfx_system.play_sfx(SoundEffect::Text);
-
-        // Trigger a full rebuild -- delete everything else
-        for x in &mut self.messages {
-            if let Some(ety) = x.e {
-                commands.entity(ety).despawn_recursive();
-                x.e = None;
-            }
-        }
-
-        let message_font_size = 24.;
-        let sender_font_size = 18.;
-        let line_spacing = 2.;
-        let inter_message_spacing = 20.;
-
-        let text_style_sender = TextStyle {
-            font: asset_server.load("fonts/monofonto.ttf"),
-            font_size: sender_font_size,
-            color: Color::rgba(0., 0., 0., 1.),
-        };
-        let text_style_message = TextStyle {
-            font: asset_server.load("fonts/monofonto.ttf"),
-            font_size: message_font_size,
-            color: Color::rgba(0., 0., 0., 1.),
-        };
-        let align = TextAlignment {
-            vertical: VerticalAlign::Center,
-            horizontal: HorizontalAlign::Left,
-        };
-
-        let msg_xpos = (SCREEN_WIDTH / 2.) - (ui::rhs_width() / 2.);
-        let message_bubble_width = 235.;
-        let sender_ofs = -message_bubble_width / 2. + 10.;
-        let message_padding_left = -message_bubble_width / 2. + 25.;
-        let message_padding_right = 10.;
-
-        // This is the "physical bottom", i.e., if we had a one pixel object, we'd position it here
-        // in order to get it in the right place
-        let mut bottom = (-SCREEN_HEIGHT / 2.) + 190.;
-        let mut height_of_first = 0.;
-
-        for x in &mut self.messages.iter_mut().rev() {
-            let laid_out_message = ui::lay_out_text_monofonto(
-                message_font_size,
-                message_bubble_width - message_padding_right,
-                &x.text,
-            );
-
-            // Containing box
-            let ct_box_height = sender_font_size
-                + line_spacing
-                + (laid_out_message.len() as f32 * (line_spacing + message_font_size))
-                + 4.;
-
-            if height_of_first == 0. {
-                height_of_first = ct_box_height + inter_message_spacing;
-            }
-
-            let ctr_bottom = bottom + ct_box_height / 2.;
-            let mut ety = commands.spawn_bundle(SpriteBundle {
-                sprite: Sprite {
-                    color: Color::rgb(175. / 255., 233. / 255., 198. / 255.),
-                    custom_size: Some(Vec2::new(message_bubble_width, ct_box_height)),
-                    ..Default::default()
-                },
-                transform: Transform {
-                    translation: Vec3::new(msg_xpos, ctr_bottom - height_of_first, 11.),
-                    ..Default::default()
-                },
-                ..Default::default()
-            });
-            ety.insert(ui::TextMessageTag {
-                bottom_from: ctr_bottom - height_of_first,
-                bottom_to: ctr_bottom,
-            });
-
-            ety.with_children(|parent| {
-                // pretty bubble edges
-                parent.spawn_bundle(SpriteBundle {
-                    texture: asset_server.load("ui/top_bubble.png"),
-                    transform: Transform {
-                        translation: [0., ct_box_height / 2. - 50. / 2., 11.5].into(),
-                        ..Default::default()
-                    },
-                    ..Default::default()
-                });
-                parent.spawn_bundle(SpriteBundle {
-                    texture: asset_server.load("ui/bottom_bubble.png"),
-                    transform: Transform {
-                        translation: [0., -ct_box_height / 2. + 14. / 2., 11.55].into(),
-                        ..Default::default()
-                    },
-                    ..Default::default()
-                });
-
-                // We're going from the bottom so spawn the message first, then the sender. Note that
-                // lines are drawn bottom up
-                let mut inside_bottom = -ct_box_height / 2. + message_font_size / 2. + 2.;
-                for l in laid_out_message.iter().rev() {
-                    parent.spawn_bundle(Text2dBundle {
-                        text: Text::with_section(l.clone(), text_style_message.clone(), align),
-                        transform: Transform {
-                            translation: Vec3::new(message_padding_left, inside_bottom, 11.6),
-                            ..Default::default()
-                        },
-                        ..Default::default()
-                    });
-                    inside_bottom += message_font_size + line_spacing;
-                }
-
-                // and now the sender
-                parent.spawn_bundle(Text2dBundle {
-                    text: Text::with_section(x.sender.clone(), text_style_sender.clone(), align),
-                    transform: Transform {
-                        translation: Vec3::new(sender_ofs, inside_bottom, 11.6),
-                        ..Default::default()
-                    },
-                    ..Default::default()
-                });
-                // Don't need this calculation but included for if we add things
-                //inside_bottom += sender_font_size + line_spacing;
-            });
+    // Either the player has lost, or the story has run out
+    pub fn run_over(&self) -> bool {
+        self.game_over || self.narrative_finished
+    }
 
-            x.e = Some(ety.id());
-            bottom += ct_box_height + inter_message_spacing;
+    // i.e. the player is a close contact and isolating
+    pub fn in_covid_narrative(&self) -> bool {
+        self.in_covid_narrative
+    }
 
-            // could be S_H / 2, but we need to be a bit careful here because we over display. So
-            // just go whole hog and don't divide by 2
-            if bottom > SCREEN_HEIGHT / 1. {
-                // don't need to render any more
-                break;
-            }
+    // Carries on the main narrative from row `id`, as if the row before had only just happened
+    pub fn jump_narrative(&mut self, id: usize, now: f64) -> Result<(), String> {
+        if id >= self.main_narrative.len() {
+            return Err(format!(
+                "the narrative only has {} rows",
+                self.main_narrative.len()
+            ));
         }
+        self.in_covid_narrative = false;
+        self.next_covid_narrative_id = 0;
+        self.next_narrative_id = id;
+        self.narrative_last_event = now;
+        Ok(())
     }
 
     fn new_day(&mut self) {}
 
-    // Returns the change, if any (so it can be displayed to the user)
-    fn deduct_sanity_on_timer(&mut self, time_since_start: f64, environment: &Environment) -> i32 {
-        // Only reduce sanity if we're at home.
-        if environment.location != Location::Home {
-            return 0;
+    fn sanity_on_timer(
+        &mut self,
+        time_since_start: f64,
+        environment: &Environment,
+        nearby_npcs: usize,
+        sanity_events: &mut EventWriter<SanityChanged>,
+    ) {
+        if self.last_location != Some(environment.location) {
+            // Changing location restarts both the clock for the location and the sanity tick
+            self.last_location = Some(environment.location);
+            self.location_entered_at = time_since_start;
+            self.last_sanity_tick_update = time_since_start;
+            return;
         }
 
         if time_since_start - self.last_sanity_tick_update > time_for_sanity_loss() {
             self.last_sanity_tick_update += time_for_sanity_loss();
-            self.sanity += sanity_loss_tick();
-            return sanity_loss_tick();
+            let delta = self.needs.sanity_pressure()
+                + location_sanity_tick(
+                    environment.location,
+                    nearby_npcs,
+                    time_since_start - self.location_entered_at,
+                );
+            if delta != 0 {
+                self.change_sanity(delta, sanity_events);
+            }
         }
-        return 0;
     }
 
     fn run_narrative(
         &mut self,
-        time: &Res<Time>,
-        commands: &mut Commands,
-        asset_server: &Res<AssetServer>,
-        player_query: &Query<(&Player, &Transform)>,
+        time: &Res<GameClock>,
         pickups_query: &Query<(&pickup::Pickup,)>,
         environment_query: &Query<(&environment::Environment,)>,
-        sfx_system: &mut SFXSystem,
+        narrative_events: &mut EventWriter<NarrativeEventFired>,
     ) {
         if self.in_covid_narrative && self.next_covid_narrative_id >= self.covid_narrative.len() {
             // end of the covid narrative, so switch back to the regular narrative
@@ -392,17 +379,11 @@ impl GameState {
                 environment_query,
                 time,
             ) {
-                let (_, player_tx) = player_query.single();
-                self.do_narrative_actions(
-                    self.covid_narrative[self.next_covid_narrative_id]
+                narrative_events.send(NarrativeEventFired {
+                    actions: self.covid_narrative[self.next_covid_narrative_id]
                         .action
                         .clone(),
-                    time,
-                    commands,
-                    asset_server,
-                    player_tx,
-                    sfx_system,
-                );
+                });
                 self.narrative_last_event = time.seconds_since_startup();
                 self.next_covid_narrative_id += 1;
             }
@@ -412,7 +393,10 @@ impl GameState {
                 self.in_covid_narrative = false;
             }
         } else if self.next_narrative_id >= self.main_narrative.len() {
-            println!("Uh-oh, got to the end of the narrative!");
+            if !self.narrative_finished {
+                println!("Uh-oh, got to the end of the narrative!");
+                self.narrative_finished = true;
+            }
         } else {
             if self.criterion_met(
                 &self.main_narrative[self.next_narrative_id].criterion,
@@ -420,15 +404,9 @@ impl GameState {
                 environment_query,
                 time,
             ) {
-                let (_, player_tx) = player_query.single();
-                self.do_narrative_actions(
-                    self.main_narrative[self.next_narrative_id].action.clone(),
-                    time,
-                    commands,
-                    asset_server,
-                    player_tx,
-                    sfx_system,
-                );
+                narrative_events.send(NarrativeEventFired {
+                    actions: self.main_narrative[self.next_narrative_id].action.clone(),
+                });
                 if self.main_narrative[self.next_narrative_id].starts_act {
                     self.narrative_start_of_act = self.next_narrative_id;
                 }
@@ -443,7 +421,7 @@ impl GameState {
         c: &NarrativeCriterion,
         pickups_query: &Query<(&pickup::Pickup,)>,
         environment_query: &Query<(&environment::Environment,)>,
-        time: &Res<Time>,
+        time: &Res<GameClock>,
     ) -> bool {
         return match c {
             NarrativeCriterion::ElapsedRel(v) => {
@@ -454,44 +432,60 @@ impl GameState {
                 let (current_env,) = environment_query.single();
                 &current_env.location == l
             }
+            NarrativeCriterion::NeedBelow(n, v) => self.needs.get(*n) < *v,
+            NarrativeCriterion::IsolationOver => !self.isolation.active(),
+            NarrativeCriterion::Vaccinated(doses) => self.vaccination.doses() >= *doses,
+            NarrativeCriterion::TalkedTo(name, fallback) => {
+                self.talked_to.contains(name)
+                    || fallback.is_some_and(|v| {
+                        time.seconds_since_startup() - self.narrative_last_event > v
+                    })
+            }
+            NarrativeCriterion::Protected(p) => {
+                self.protections.active(*p, time.seconds_since_startup())
+            }
         };
     }
 
-    pub fn change_sanity(&mut self, delta: i32) {
+    pub fn change_sanity(&mut self, delta: i32, sanity_events: &mut EventWriter<SanityChanged>) {
         // no need to clamp on the bottom -- that ends the game
         self.sanity = i32::min(self.sanity + delta, 100);
+        sanity_events.send(SanityChanged {
+            delta,
+            sanity: self.sanity,
+        });
     }
 
     pub fn get_sanity(&self) -> i32 {
         return self.sanity;
     }
 
-    pub fn do_narrative_actions(
+    fn do_narrative_actions(
         &mut self,
         a: NarrativeActions,
-        time: &Res<Time>,
         commands: &mut Commands,
         asset_server: &Res<AssetServer>,
-        player_tx: &Transform,
-        sfx_system: &mut SFXSystem,
+        sanity_events: &mut EventWriter<SanityChanged>,
+        text_events: &mut EventWriter<TextReceived>,
+        now: f64,
     ) {
         if let Some(ds) = a.change_sanity {
-            self.change_sanity(ds);
-            ui::spawn_sanity_number(
-                ds,
-                commands,
-                asset_server.load("fonts/monofonto.ttf"),
-                player_tx.translation,
-            );
-            if ds > 0 {
-                sfx_system.play_sfx(SoundEffect::SanityUp);
-            } else {
-                sfx_system.play_sfx(SoundEffect::SanityDown);
-            }
+            self.change_sanity(ds, sanity_events);
+        }
+
+        for (n, delta) in a.change_needs {
+            self.needs.change(n, delta);
+        }
+
+        for p in a.protections {
+            self.protections.start(p, now);
         }
 
         for m in a.send_texts {
-            self.add_text_message(&m.sender, &m.body, time, commands, asset_server, sfx_system);
+            text_events.send(TextReceived {
+                sender: m.sender,
+                body: m.body,
+            });
         }
 
         for s in a.spawn_item {
@@ -505,15 +499,29 @@ impl GameState {
         }
 
         for s in a.spawn_npc {
-            npc::spawn_npc(commands, asset_server, s.location);
+            spawn_story_npc(commands, asset_server, &s, s.location);
+            self.story_npcs.push(s);
         }
 
         for (l, new_val) in a.teleporter_control {
             self.area_access.set_access(l, new_val);
         }
+
+        for l in a.vaccinated_unlocks {
+            self.area_access.set_vaccinated_only(l);
+        }
+
+        if a.book_vaccine {
+            if let Some(day) = self.vaccination.book(self.date) {
+                text_events.send(TextReceived {
+                    sender: String::from("Department of Health"),
+                    body: booking_text(self.vaccination.doses() + 1, day),
+                });
+            }
+        }
     }
 
-    pub fn set_covid_risk(&mut self, covid_risk: f32, time: &Res<Time>) {
+    pub fn set_covid_risk(&mut self, covid_risk: f32, time: &Res<GameClock>) {
         let old_scr = self.show_covid_risk;
         self.covid_risk = covid_risk;
         if covid_risk > COVID_RISK_THRESHOLD {
@@ -527,51 +535,18 @@ impl GameState {
         }
     }
 
-    pub fn covid_narrative_switch(
-        &mut self,
-        time: &Res<Time>,
-        player_position: &mut Mut<RigidBodyPositionComponent>,
-        environment_query: &mut Query<(&mut TextureAtlasSprite, &mut environment::Environment)>,
-        commands: &mut Commands,
-        environment_collider_query: &Query<Entity, With<environment::EnvironmentCollider>>,
-        music_state: &mut ResMut<MusicState>,
-        asset_server: &Res<AssetServer>,
-    ) {
-        // The player has been exposed to covid so we need to switch over to the covid narrative,
-        // and handle a million housekeeping questions
+    fn covid_narrative_switch(&mut self, time: &Res<GameClock>) {
+        // The player has been exposed to covid so we need to switch over to the covid narrative.
+        // Getting them home and showing the alert is handled by whoever else hears about it.
+        if self.in_covid_narrative {
+            // already isolating
+            return;
+        }
 
-        // Narrative stuff
         self.in_covid_narrative = true;
         // when we return to the main narrative, back up to the start of the last act
         self.next_narrative_id = self.narrative_start_of_act;
         self.narrative_last_event = time.seconds_since_startup(); // establish the start of the Covid arc
-
-        // Teleport back home
-        let teleporter = teleportation::Teleporter::new(environment::Location::Home, [5, 5].into());
-        teleportation::teleport(
-            &teleporter,
-            player_position,
-            environment_query,
-            commands,
-            environment_collider_query,
-            music_state,
-            asset_server,
-        );
-
-        // Spawn the scary transition screen
-        let xpos = -SCREEN_WIDTH / 2. + 1000. / 2.;
-        commands
-            .spawn_bundle(SpriteBundle {
-                texture: asset_server.load("close_contact_alert.png"),
-                transform: Transform {
-                    translation: [xpos, 0., 50.].into(),
-                    ..Default::default()
-                },
-                ..Default::default()
-            })
-            .insert(ui::CovidTransitionUiTag {
-                time_left: ui::TRANSITION_LENGTH,
-            });
     }
 }
 
@@ -581,25 +556,69 @@ impl Default for AreaAccessControl {
             home: true,
             park: true,
             shops: true,
+            clinic: true,
+            isolating: false,
+            vaccinated_only: vec![],
+            vaccinated: false,
         }
     }
 }
 
 impl AreaAccessControl {
     pub fn can_access(&self, l: environment::Location) -> bool {
+        if self.isolating && l != environment::Location::Home {
+            return false;
+        }
+        self.unlocked(l)
+    }
+
+    // Whether the narrative has `l` open, leaving isolation aside
+    pub fn unlocked(&self, l: environment::Location) -> bool {
+        if self.vaccinated && self.vaccinated_only.contains(&l) {
+            return true;
+        }
         match l {
             environment::Location::Home => self.home,
             environment::Location::Park => self.park,
             environment::Location::Shops => self.shops,
+            environment::Location::Clinic => self.clinic,
         }
     }
 
+    // How many of the public places the narrative has shut, 0 to 2
+    pub fn lockdown_level(&self) -> usize {
+        [self.park, self.shops].iter().filter(|open| !**open).count()
+    }
+
     pub fn set_access(&mut self, l: environment::Location, to: bool) {
         println!("access control: set {:?} to {}", l, if to { "unlocked" } else { "locked" });
         match l {
             environment::Location::Home => self.home = to,
             environment::Location::Park => self.park = to,
             environment::Location::Shops => self.shops = to,
+            environment::Location::Clinic => self.clinic = to,
         };
     }
+
+    // Opens `l` to the fully vaccinated, whether or not it's locked
+    pub fn set_vaccinated_only(&mut self, l: environment::Location) {
+        println!("access control: {:?} open to the fully vaccinated", l);
+        if !self.vaccinated_only.contains(&l) {
+            self.vaccinated_only.push(l);
+        }
+    }
+
+    pub fn set_vaccinated(&mut self, to: bool) {
+        println!("access control: {} fully vaccinated", if to { "now" } else { "not" });
+        self.vaccinated = to;
+    }
+
+    pub fn isolating(&self) -> bool {
+        self.isolating
+    }
+
+    pub fn set_isolating(&mut self, to: bool) {
+        println!("access control: {} isolating", if to { "now" } else { "no longer" });
+        self.isolating = to;
+    }
 }
@@ -0,0 +1,201 @@
+// A debug overlay showing how risky each tile is, going by where the NPCs are right now, with a
+// circle around each NPC at the distance they stop being a risk. F3 toggles it, in any build.
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::clock::GameClock;
+use crate::covid::{infection_chance, protected_dose_rate, safety_distance};
+use crate::environment::{
+    tile_coords_to_screen_pos, transmission_at, Environment, Location, GRID_SIZE,
+};
+use crate::epidemic::Infection;
+use crate::game::GameState;
+use crate::npc::NPC;
+use crate::protection::Protections;
+use crate::vaccination::Vaccination;
+use crate::TILE_SIZE;
+
+const TOGGLE_KEY: KeyCode = KeyCode::F3;
+// Seconds between redraws
+const REFRESH_SECONDS: f64 = 0.25;
+// Each tile shows the chance of catching it from standing there this long
+pub const STANDING_SECONDS: f32 = 5.;
+// Above the NPCs and the player, below the console
+const Z: f32 = 50.;
+
+#[derive(Debug, Clone, Default)]
+pub struct RiskHeatmap {
+    pub visible: bool,
+    // When it was last redrawn
+    refreshed: f64,
+}
+
+#[derive(Component)]
+pub struct HeatmapTile {
+    pub tile: [usize; 2],
+}
+
+#[derive(Component)]
+pub struct SafetyCircle {}
+
+pub struct HeatmapPlugin;
+
+impl Plugin for HeatmapPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RiskHeatmap>()
+            .add_startup_system(setup_heatmap)
+            .add_system(heatmap_toggle_system.label("heatmap toggle"))
+            // So hiding it can't leave one last lot of circles behind
+            .add_system(heatmap_system.after("heatmap toggle"));
+    }
+}
+
+// The middle of a tile, in physics units
+fn tile_centre(tile: [usize; 2]) -> (f32, f32) {
+    let (x, y) = tile_coords_to_screen_pos(tile[0], 1., tile[1], 1.);
+    (x / TILE_SIZE, y / TILE_SIZE)
+}
+
+// The risk covid_system would give the player for standing on `tile`, next to the infectious
+// NPCs at `infectious`
+pub fn tile_risk(
+    location: Location,
+    tile: [usize; 2],
+    infectious: &[(f32, f32)],
+    protections: &Protections,
+    vaccination: &Vaccination,
+    now: f64,
+) -> f32 {
+    let (x, y) = tile_centre(tile);
+    let transmission = transmission_at(location, x, y);
+    let rate: f32 = infectious
+        .iter()
+        .map(|(nx, ny)| {
+            let d = ((x - nx).powi(2) + (y - ny).powi(2)).sqrt();
+            protected_dose_rate(d, &transmission, protections, now) * vaccination.dose_factor()
+        })
+        .sum();
+    infection_chance(rate * STANDING_SECONDS)
+}
+
+// Clear when it's safe, through yellow to red when it's a sure thing
+fn risk_colour(risk: f32) -> Color {
+    if risk <= 0. {
+        return Color::rgba(0., 0., 0., 0.);
+    }
+    Color::rgba(1., 1. - risk, 0., 0.2 + 0.4 * risk)
+}
+
+fn setup_heatmap(mut commands: Commands) {
+    for x in 0..GRID_SIZE {
+        for y in 0..GRID_SIZE {
+            let (pos_x, pos_y) = tile_coords_to_screen_pos(x, 1., y, 1.);
+            commands
+                .spawn_bundle(SpriteBundle {
+                    transform: Transform {
+                        translation: [pos_x, pos_y, Z].into(),
+                        ..Default::default()
+                    },
+                    sprite: Sprite {
+                        color: risk_colour(0.),
+                        custom_size: Some(Vec2::new(TILE_SIZE, TILE_SIZE)),
+                        ..Default::default()
+                    },
+                    visibility: Visibility { is_visible: false },
+                    ..Default::default()
+                })
+                .insert(HeatmapTile { tile: [x, y] });
+        }
+    }
+}
+
+fn heatmap_toggle_system(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    mut heatmap: ResMut<RiskHeatmap>,
+    mut tile_query: Query<&mut Visibility, With<HeatmapTile>>,
+    circle_query: Query<Entity, With<SafetyCircle>>,
+) {
+    if !keys.just_pressed(TOGGLE_KEY) {
+        return;
+    }
+    heatmap.visible = !heatmap.visible;
+    // Redraw straight away
+    heatmap.refreshed = f64::NEG_INFINITY;
+    println!("covid risk heatmap: {}", heatmap.visible);
+    for mut v in tile_query.iter_mut() {
+        v.is_visible = heatmap.visible;
+    }
+    if !heatmap.visible {
+        for e in circle_query.iter() {
+            commands.entity(e).despawn();
+        }
+    }
+}
+
+pub fn heatmap_system(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut heatmap: ResMut<RiskHeatmap>,
+    mut tile_query: Query<(&HeatmapTile, &mut Sprite)>,
+    circle_query: Query<Entity, With<SafetyCircle>>,
+    npc_query: Query<(&Infection, &RigidBodyPositionComponent), With<NPC>>,
+    environment_query: Query<&Environment>,
+    state: Res<GameState>,
+    time: Res<GameClock>,
+) {
+    let now = time.seconds_since_startup();
+    if !heatmap.visible || now - heatmap.refreshed < REFRESH_SECONDS {
+        return;
+    }
+    heatmap.refreshed = now;
+
+    let location = environment_query.single().location;
+    let npcs: Vec<_> = npc_query
+        .iter()
+        .map(|(i, p)| {
+            let v = p.position.translation.vector;
+            (i.is_infectious(), (v.x, v.y))
+        })
+        .collect();
+    let infectious: Vec<_> = npcs.iter().filter(|(i, _)| *i).map(|(_, p)| *p).collect();
+
+    for (tile, mut sprite) in tile_query.iter_mut() {
+        let risk = tile_risk(
+            location,
+            tile.tile,
+            &infectious,
+            &state.protections,
+            &state.vaccination,
+            now,
+        );
+        sprite.color = risk_colour(risk);
+    }
+
+    for e in circle_query.iter() {
+        commands.entity(e).despawn();
+    }
+    for (is_infectious, (x, y)) in npcs {
+        let diameter = 2. * safety_distance(&transmission_at(location, x, y)) * TILE_SIZE;
+        let color = if is_infectious {
+            Color::rgba(0.8, 0., 0., 0.8)
+        } else {
+            Color::rgba(0.3, 0.3, 0.3, 0.5)
+        };
+        commands
+            .spawn_bundle(SpriteBundle {
+                texture: asset_server.load("safety_circle.png"),
+                transform: Transform {
+                    translation: [x * TILE_SIZE, y * TILE_SIZE, Z + 1.].into(),
+                    ..Default::default()
+                },
+                sprite: Sprite {
+                    color,
+                    custom_size: Some(Vec2::new(diameter, diameter)),
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .insert(SafetyCircle {});
+    }
+}
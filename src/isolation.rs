@@ -0,0 +1,196 @@
+// Isolating at home after being a close contact. It lasts a week of game days, unless a rapid
+// antigen test (RAT) comes back negative first. If the player really has caught it they feel
+// rotten for a few of those days, and walking out the front door earns a fine and starts the
+// whole thing again.
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::environment::{Environment, Location};
+use crate::events::{
+    CovidExposure, IsolationBreached, PickupCollected, SanityChanged, TextReceived,
+};
+use crate::game::GameState;
+use crate::narrative::NarrativeActions;
+use crate::needs::Need;
+use crate::pickup::{spawn_pickup, Pickup};
+use crate::rng::GameRng;
+
+pub const ISOLATION_DAYS: i32 = 7;
+// A RAT turns up on the first day, and every couple of days after
+const RAT_EVERY_DAYS: i32 = 2;
+const RAT_LOCATION: [usize; 2] = [12, 10];
+// Which days of isolation someone who has it feels sick on
+const SYMPTOM_DAYS: std::ops::RangeInclusive<i32> = 2..=4;
+const SYMPTOM_SANITY: i32 = -5;
+const BREACH_SANITY: i32 = -20;
+const BREACH_FINE: &str = "$5,452";
+
+const HEALTH: &str = "Department of Health";
+
+#[derive(Debug, Clone, Default)]
+pub struct Isolation {
+    active: bool,
+    // The date isolation (last) started
+    started: i32,
+    // Whether the player has actually caught it, which only a RAT can tell them
+    pub infected: bool,
+    // The last day already dealt with
+    last_day: i32,
+}
+
+impl Isolation {
+    pub fn start(&mut self, date: i32, infected: bool) {
+        self.active = true;
+        self.started = date;
+        self.infected = infected;
+        self.last_day = 0;
+    }
+
+    pub fn active(&self) -> bool {
+        self.active
+    }
+
+    // Days into isolation, starting from 0
+    pub fn day(&self, date: i32) -> i32 {
+        date - self.started
+    }
+
+    pub fn days_left(&self, date: i32) -> i32 {
+        i32::max(0, ISOLATION_DAYS - self.day(date))
+    }
+
+    pub fn symptomatic(&self, date: i32) -> bool {
+        self.active && self.infected && SYMPTOM_DAYS.contains(&self.day(date))
+    }
+
+    fn rat_due(&self, date: i32) -> bool {
+        let day = self.day(date);
+        day >= 1 && (day - 1) % RAT_EVERY_DAYS == 0
+    }
+
+    fn release(&mut self) {
+        self.active = false;
+    }
+}
+
+pub struct IsolationPlugin;
+
+impl Plugin for IsolationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(isolation_start_system)
+            .add_system(isolation_day_system)
+            .add_system(rat_system)
+            .add_system(breach_system);
+    }
+}
+
+// The more of a dose it took, the likelier it is they've really caught it
+pub fn isolation_start_system(
+    mut state: ResMut<GameState>,
+    mut exposure_events: EventReader<CovidExposure>,
+    mut rng: ResMut<GameRng>,
+) {
+    for e in exposure_events.iter() {
+        if state.isolation.active() {
+            continue;
+        }
+        let infected = rng.gen::<f32>() < e.risk;
+        let date = state.date;
+        state.isolation.start(date, infected);
+        state.area_access.set_isolating(true);
+    }
+}
+
+pub fn isolation_day_system(
+    mut commands: Commands,
+    mut state: ResMut<GameState>,
+    asset_server: Res<AssetServer>,
+    environment_query: Query<&Environment>,
+    pickup_query: Query<&Pickup>,
+    mut sanity_events: EventWriter<SanityChanged>,
+    mut text_events: EventWriter<TextReceived>,
+) {
+    let date = state.date;
+    let day = state.isolation.day(date);
+    if !state.isolation.active() || day <= state.isolation.last_day {
+        return;
+    }
+    state.isolation.last_day = day;
+
+    if state.isolation.days_left(date) == 0 {
+        state.isolation.release();
+        state.area_access.set_isolating(false);
+        return;
+    }
+
+    if state.isolation.symptomatic(date) {
+        text_events.send(TextReceived {
+            sender: String::from("Yourself"),
+            body: String::from("Ugh. Headache, sore throat, and everything tastes like cardboard"),
+        });
+        state.change_sanity(SYMPTOM_SANITY, &mut sanity_events);
+        state.needs.change(Need::Sleep, -20.);
+        state.needs.change(Need::Hunger, -10.);
+    }
+
+    let home = environment_query.single().location == Location::Home;
+    let have_one = pickup_query.iter().any(|p| matches!(p, Pickup::Rat));
+    if state.isolation.rat_due(date) && home && !have_one {
+        spawn_pickup(
+            Pickup::Rat,
+            RAT_LOCATION,
+            &mut commands,
+            &asset_server,
+            NarrativeActions::default(),
+        );
+    }
+}
+
+// Taking a RAT. A negative one ends isolation there and then.
+pub fn rat_system(
+    mut state: ResMut<GameState>,
+    mut pickup_events: EventReader<PickupCollected>,
+    mut text_events: EventWriter<TextReceived>,
+) {
+    for e in pickup_events.iter() {
+        if !matches!(e.pickup, Pickup::Rat) || !state.isolation.active() {
+            continue;
+        }
+        let body = if state.isolation.infected {
+            format!(
+                "Positive RAT result recorded. Keep isolating: {} days to go.",
+                state.isolation.days_left(state.date)
+            )
+        } else {
+            state.isolation.release();
+            state.area_access.set_isolating(false);
+            String::from("Negative RAT result recorded. You no longer need to isolate.")
+        };
+        text_events.send(TextReceived {
+            sender: String::from(HEALTH),
+            body,
+        });
+    }
+}
+
+// Out and about while isolating: a fine, and isolation starts over
+pub fn breach_system(
+    mut state: ResMut<GameState>,
+    mut breach_events: EventReader<IsolationBreached>,
+    mut sanity_events: EventWriter<SanityChanged>,
+    mut text_events: EventWriter<TextReceived>,
+) {
+    for _ in breach_events.iter() {
+        let date = state.date;
+        let infected = state.isolation.infected;
+        state.isolation.start(date, infected);
+        state.change_sanity(BREACH_SANITY, &mut sanity_events);
+        text_events.send(TextReceived {
+            sender: String::from(HEALTH),
+            body: format!(
+                "You have left home while under an isolation order. You have been fined {}, and your {} days of isolation start again today.",
+                BREACH_FINE, ISOLATION_DAYS
+            ),
+        });
+    }
+}
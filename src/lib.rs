@@ -0,0 +1,105 @@
+pub mod audio;
+pub mod behaviour;
+pub mod clock;
+#[cfg(feature = "dev-console")]
+pub mod console;
+pub mod covid;
+pub mod dialogue;
+pub mod environment;
+pub mod epidemic;
+pub mod events;
+pub mod game;
+pub mod heatmap;
+pub mod isolation;
+pub mod music;
+pub mod narrative;
+pub mod needs;
+pub mod npc;
+pub mod pathfinding;
+pub mod phone;
+pub mod pickup;
+pub mod player;
+pub mod population;
+pub mod protection;
+pub mod replay;
+pub mod rng;
+pub mod sfx;
+pub mod stats;
+pub mod teleportation;
+pub mod ui;
+pub mod vaccination;
+pub mod wrap;
+
+use bevy::app::PluginGroupBuilder;
+use bevy::prelude::*;
+
+pub use audio::AudioPlugin;
+pub use clock::ClockPlugin;
+#[cfg(feature = "dev-console")]
+pub use console::ConsolePlugin;
+pub use covid::CovidPlugin;
+pub use dialogue::DialoguePlugin;
+pub use environment::EnvironmentPlugin;
+pub use epidemic::EpidemicPlugin;
+pub use events::EventsPlugin;
+pub use game::NarrativePlugin;
+pub use heatmap::HeatmapPlugin;
+pub use isolation::IsolationPlugin;
+pub use needs::NeedsPlugin;
+pub use npc::NpcPlugin;
+pub use phone::PhonePlugin;
+pub use pickup::PickupPlugin;
+pub use player::PlayerPlugin;
+pub use population::PopulationPlugin;
+pub use protection::ProtectionPlugin;
+pub use replay::ReplayPlugin;
+pub use rng::RngPlugin;
+pub use stats::StatsPlugin;
+pub use ui::UiPlugin;
+pub use vaccination::VaccinationPlugin;
+
+pub const SCREEN_HEIGHT: f32 = 1030.0;
+pub const SCREEN_WIDTH: f32 = 1324.0;
+pub const TILE_SIZE: f32 = 50.;
+
+// The value after `name` on the command line, e.g. `--seed 1234`
+pub fn launch_option(name: &str) -> Option<String> {
+    let args: Vec<String> = std::env::args().collect();
+    args.iter()
+        .position(|a| a == name)
+        .and_then(|i| args.get(i + 1))
+        .cloned()
+}
+
+// The whole game. Expects DefaultPlugins and the Rapier physics plugin to already be there.
+pub struct MelsimPlugins;
+
+impl PluginGroup for MelsimPlugins {
+    fn build(&mut self, group: &mut PluginGroupBuilder) {
+        group
+            .add(ClockPlugin)
+            .add(RngPlugin)
+            .add(ReplayPlugin)
+            .add(EventsPlugin)
+            .add(PlayerPlugin)
+            .add(EnvironmentPlugin)
+            .add(NarrativePlugin)
+            .add(NeedsPlugin)
+            .add(CovidPlugin)
+            .add(EpidemicPlugin)
+            .add(IsolationPlugin)
+            .add(VaccinationPlugin)
+            .add(ProtectionPlugin)
+            .add(NpcPlugin)
+            .add(PopulationPlugin)
+            .add(DialoguePlugin)
+            .add(PickupPlugin)
+            .add(StatsPlugin)
+            .add(AudioPlugin)
+            .add(UiPlugin)
+            .add(PhonePlugin)
+            .add(HeatmapPlugin);
+        #[cfg(feature = "dev-console")]
+        group.add(ConsolePlugin);
+    }
+}
@@ -1,6 +1,9 @@
 use bevy::prelude::*;
 use bevy_kira_audio::{Audio, AudioChannel, AudioSource};
 
+use crate::environment::Location;
+use crate::events::LocationChanged;
+
 #[derive(Default, Clone, Debug)]
 pub struct MusicState {
     pub tracks: Vec<Handle<AudioSource>>,
@@ -45,6 +48,21 @@ pub fn setup_music(
     music_state.last_track_change = -20.; // hack to get the first one immediately in playing state
 }
 
+pub fn location_music_system(
+    mut location_events: EventReader<LocationChanged>,
+    mut music_state: ResMut<MusicState>,
+) {
+    for e in location_events.iter() {
+        let music_track_index = match e.to {
+            Location::Home => 0,
+            Location::Park => 1,
+            // The clinic shares the shops track on purpose: both are quick errands indoors
+            Location::Shops | Location::Clinic => 2,
+        };
+        music_state.switch_tracks(music_track_index);
+    }
+}
+
 pub fn music_system(audio: Res<Audio>, mut music_state: ResMut<MusicState>, time: Res<Time>) {
     if let Some(index) = music_state.next_track_index.take() {
         music_state.last_track_change = time.seconds_since_startup();
@@ -1,7 +1,10 @@
 use bevy::prelude::Component;
 
+use crate::behaviour::Behaviour;
 use crate::environment::Location;
+use crate::needs::Need;
 use crate::pickup;
+use crate::protection::Protection;
 use csv::StringRecord;
 use std::collections::HashMap;
 use std::fs::File;
@@ -16,9 +19,14 @@ pub struct NarrativeEvent {
 
 #[derive(Debug)]
 pub enum NarrativeCriterion {
-    ElapsedRel(f64),         // at least this many seconds have elasped since last event
-    ClearedAll,              // all items in the environment must be cleared
-    InEnvironment(Location), // current location is here
+    ElapsedRel(f64),               // at least this many seconds have elasped since last event
+    ClearedAll,                    // all items in the environment must be cleared
+    InEnvironment(Location),       // current location is here
+    NeedBelow(Need, f32),          // the given need has dropped below this value
+    Protected(Protection),         // the player is wearing a mask etc. right now
+    IsolationOver,                 // the player is out of isolation (or was never in it)
+    Vaccinated(u8),                // the player has had at least this many doses
+    TalkedTo(String, Option<f64>), // finished a conversation with this NPC, or waited this long
 }
 
 #[derive(Debug, Default, Clone, Component)]
@@ -28,6 +36,10 @@ pub struct NarrativeActions {
     pub spawn_item: Vec<SpawnablePickup>,
     pub spawn_npc: Vec<SpawnableNpc>,
     pub teleporter_control: Vec<(Location, bool)>,
+    pub change_needs: Vec<(Need, f32)>,
+    pub protections: Vec<Protection>,
+    pub book_vaccine: bool,
+    pub vaccinated_unlocks: Vec<Location>,
 }
 
 impl NarrativeActions {
@@ -68,7 +80,10 @@ pub struct SpawnablePickup {
 
 #[derive(Clone, Debug)]
 pub struct SpawnableNpc {
+    // Who they are, e.g. "Friend"
+    pub name: String,
     pub location: [usize; 2],
+    pub behaviour: Behaviour,
 }
 
 pub fn load_csv(file: &str) -> Vec<NarrativeEvent> {
@@ -90,6 +105,25 @@ pub fn load_csv(file: &str) -> Vec<NarrativeEvent> {
                 &x,
                 "Location change?",
             ))))
+        } else if non_empty(get_opt(&h, &x, "Need Below?")) {
+            {
+            let (need, threshold) = str2need_value("Need Below?", get_opt(&h, &x, "Need Below?"));
+            Some(NarrativeCriterion::NeedBelow(need, threshold))
+        }
+        } else if non_empty(get_opt(&h, &x, "Isolation over?")) {
+            Some(NarrativeCriterion::IsolationOver)
+        } else if non_empty(get_opt(&h, &x, "Vaccinated?")) {
+            Some(NarrativeCriterion::Vaccinated(
+                u8::from_str(get_opt(&h, &x, "Vaccinated?")).expect("bad parse doses"),
+            ))
+        } else if non_empty(get_opt(&h, &x, "Talked to?")) {
+            Some(str2talked_to(get_opt(&h, &x, "Talked to?")))
+        } else if non_empty(get_opt(&h, &x, "Protected?")) {
+            Some(NarrativeCriterion::Protected(str2protection(get_opt(
+                &h,
+                &x,
+                "Protected?",
+            ))))
         } else {
             None
         };
@@ -131,6 +165,20 @@ pub fn load_csv(file: &str) -> Vec<NarrativeEvent> {
             a.change_sanity = Some(i32::from_str_radix(sanity, 10).expect("bad parse sanity"));
         }
 
+        let needs = get_opt(&h, &x, "Change Needs?");
+        if non_empty(needs) {
+            for change in needs.split(";") {
+                a.change_needs.push(str2need_value("Change Needs?", change));
+            }
+        }
+
+        let protect = get_opt(&h, &x, "Protect?");
+        if non_empty(protect) {
+            for p in protect.split(";") {
+                a.protections.push(str2protection(p));
+            }
+        }
+
         let spawn_item = get(&h, &x, "Spawn Item?");
         if non_empty(spawn_item) {
             a.spawn_item.push(str2spawnitem(spawn_item));
@@ -140,10 +188,16 @@ pub fn load_csv(file: &str) -> Vec<NarrativeEvent> {
         if non_empty(spawn_npc) {
             let parts: Vec<&str> = spawn_npc.split(";").collect();
             a.spawn_npc.push(SpawnableNpc {
+                name: String::from(parts[0]),
                 location: [
                     usize::from_str(parts[1]).unwrap(),
                     usize::from_str(parts[2]).unwrap(),
                 ],
+                behaviour: parts
+                    .get(3)
+                    .copied()
+                    .map(str2behaviour)
+                    .unwrap_or(Behaviour::Wanderer),
             });
         }
 
@@ -154,6 +208,17 @@ pub fn load_csv(file: &str) -> Vec<NarrativeEvent> {
             }
         }
 
+        let vaccinated_unlocks = get_opt(&h, &x, "Unlock for vaccinated?");
+        if non_empty(vaccinated_unlocks) {
+            for location in vaccinated_unlocks.split(";") {
+                a.vaccinated_unlocks.push(str2location(location));
+            }
+        }
+
+        if non_empty(get_opt(&h, &x, "Book vaccine?")) {
+            a.book_vaccine = true;
+        }
+
         let locks = get(&h, &x, "Lock area?");
         if non_empty(locks) {
             for location in locks.split(";") {
@@ -177,42 +242,127 @@ fn str2location(s: &str) -> Location {
         "Park" => Location::Park,
         "Home" => Location::Home,
         "Shops" => Location::Shops,
+        "Clinic" => Location::Clinic,
         _ => panic!("bad location >>{}<<", s),
     }
 }
 
-fn str2spawnitem(s: &str) -> SpawnablePickup {
+fn str2behaviour(s: &str) -> Behaviour {
     match s {
-        "Care Package" => SpawnablePickup {
-            prototype: pickup::Pickup::Potplant,
-            location: (1, 16),
-            narrative_actions: action().change_sanity(20),
-        },
-        "TV" => SpawnablePickup {
-            prototype: pickup::Pickup::Potplant,
-            location: (16, 10),
-            narrative_actions: action().change_sanity(10),
-        },
-        "Fridge" | "Pillow" | "Soap" | "Towel" | "Video Game" => SpawnablePickup {
-            prototype: pickup::Pickup::Potplant,
-            location: (5, 5),
-            narrative_actions: action().change_sanity(10),
-        },
+        "Wanderer" => Behaviour::Wanderer,
+        "Shopper" => Behaviour::shopper(),
+        "Jogger" => Behaviour::jogger(),
+        "Dog walker" => Behaviour::DogWalker,
+        // Follows the player
+        "Follower" => Behaviour::Follower(None),
+        "Stationary" => Behaviour::Stationary,
+        _ => panic!("bad behaviour >>{}<<", s),
+    }
+}
+
+fn str2need(s: &str) -> Need {
+    match s {
+        "Hunger" => Need::Hunger,
+        "Hygiene" => Need::Hygiene,
+        "Sleep" => Need::Sleep,
+        "Social" => Need::Social,
+        "Boredom" => Need::Boredom,
+        _ => panic!("bad need >>{}<<", s),
+    }
+}
+
+fn str2protection(s: &str) -> Protection {
+    match s {
+        "Mask" => Protection::Mask,
+        "Sanitiser" => Protection::Sanitiser,
+        "Keep 1.5m" => Protection::KeepApart,
+        _ => panic!("bad protection >>{}<<", s),
+    }
+}
+
+// e.g. "Hunger:+30" or "Sleep:-10", from the `column` cell
+fn str2need_value(column: &str, s: &str) -> (Need, f32) {
+    let (need, value) = s
+        .split_once(":")
+        .unwrap_or_else(|| panic!("bad {} >>{}<<", column, s));
+    let value = f32::from_str(value.trim()).unwrap_or_else(|_| panic!("bad {} >>{}<<", column, s));
+    (str2need(need.trim()), value)
+}
+
+// e.g. "Friend", or "Friend;60" to move on anyway after a minute
+fn str2talked_to(s: &str) -> NarrativeCriterion {
+    let mut parts = s.split(";");
+    let name = String::from(parts.next().unwrap());
+    let fallback = parts
+        .next()
+        .map(|t| f64::from_str(t).unwrap_or_else(|_| panic!("bad Talked to? >>{}<<", s)));
+    NarrativeCriterion::TalkedTo(name, fallback)
+}
+
+fn str2spawnitem(s: &str) -> SpawnablePickup {
+    let (location, narrative_actions) = match s {
+        "Care Package" => (
+            (1, 16),
+            action().change_sanity(20).change_need(Need::Hunger, 50.),
+        ),
+        "TV" => (
+            (16, 10),
+            action().change_sanity(10).change_need(Need::Boredom, 30.),
+        ),
+        "Fridge" => (
+            (5, 5),
+            action().change_sanity(10).change_need(Need::Hunger, 40.),
+        ),
+        "Pillow" => (
+            (5, 5),
+            action().change_sanity(10).change_need(Need::Sleep, 50.),
+        ),
+        "Soap" | "Towel" => (
+            (5, 5),
+            action().change_sanity(10).change_need(Need::Hygiene, 40.),
+        ),
+        "Video Game" => (
+            (5, 5),
+            action().change_sanity(10).change_need(Need::Boredom, 40.),
+        ),
+        "Mask" => ((5, 5), action().protect(Protection::Mask)),
+        "Sanitiser" => ((5, 5), action().protect(Protection::Sanitiser)),
+        "Keep 1.5m" => ((8, 10), action().protect(Protection::KeepApart)),
         _ => panic!("bad spawn: {}", s),
+    };
+    let prototype = match s {
+        "TV" => pickup::Pickup::Tv,
+        "Mask" => pickup::Pickup::Mask,
+        "Sanitiser" => pickup::Pickup::Sanitiser,
+        "Keep 1.5m" => pickup::Pickup::KeepApart,
+        _ => pickup::Pickup::Potplant,
+    };
+    SpawnablePickup {
+        prototype,
+        location,
+        narrative_actions,
     }
 }
 
 // TODO: whitespace?
-fn non_empty(s: &str) -> bool {
+pub(crate) fn non_empty(s: &str) -> bool {
     s.len() > 0
 }
 
-fn get<'a>(h: &'a HashMap<&str, usize>, r: &'a StringRecord, v: &'a str) -> &'a str {
+pub(crate) fn get<'a>(h: &'a HashMap<&str, usize>, r: &'a StringRecord, v: &'a str) -> &'a str {
     let idx = h.get(v).unwrap();
     return &r[*idx];
 }
 
-fn csv_header(rec: &StringRecord) -> HashMap<&str, usize> {
+// For columns that only some narrative files have
+pub(crate) fn get_opt<'a>(h: &'a HashMap<&str, usize>, r: &'a StringRecord, v: &'a str) -> &'a str {
+    match h.get(v) {
+        Some(idx) => &r[*idx],
+        None => "",
+    }
+}
+
+pub(crate) fn csv_header(rec: &StringRecord) -> HashMap<&str, usize> {
     let mut rv = HashMap::new();
 
     for (i, field) in rec.iter().enumerate() {
@@ -316,6 +466,16 @@ impl NarrativeActions {
         self
     }
 
+    fn change_need(mut self, need: Need, by: f32) -> Self {
+        self.change_needs.push((need, by));
+        self
+    }
+
+    fn protect(mut self, p: Protection) -> Self {
+        self.protections.push(p);
+        self
+    }
+
     fn spawn_pickup(
         mut self,
         what: pickup::Pickup,
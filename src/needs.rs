@@ -0,0 +1,257 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::clock::GameClock;
+use crate::environment::{tile_coords_to_screen_pos, Environment, EnvironmentCollider, Location};
+use crate::events::NeedRestored;
+use crate::game::GameState;
+use crate::player::Player;
+use crate::TILE_SIZE;
+
+// Needs are stored as "how satisfied is this need", so 100 is fully met and 0 is desperate
+pub const NEED_MAX: f32 = 100.;
+// Below this, a need starts eating into sanity every sanity tick
+pub const NEED_LOW_THRESHOLD: f32 = 25.;
+// How long before the same need can be topped up again by interacting with something
+const INTERACTION_COOLDOWN: f64 = 10.;
+// How much social contact a phone call gives you
+const PHONE_CALL_AMOUNT: f32 = 20.;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Need {
+    Hunger,
+    Hygiene,
+    Sleep,
+    Social,
+    Boredom,
+}
+
+pub const ALL_NEEDS: [Need; 5] = [
+    Need::Hunger,
+    Need::Hygiene,
+    Need::Sleep,
+    Need::Social,
+    Need::Boredom,
+];
+
+impl Need {
+    fn index(self) -> usize {
+        self as usize
+    }
+
+    pub fn short_label(self) -> &'static str {
+        match self {
+            Need::Hunger => "H",
+            Need::Hygiene => "W",
+            Need::Sleep => "Z",
+            Need::Social => "S",
+            Need::Boredom => "B",
+        }
+    }
+
+    pub fn colour(self) -> Color {
+        match self {
+            Need::Hunger => Color::rgb(0.93, 0.55, 0.18),
+            Need::Hygiene => Color::rgb(0.25, 0.6, 0.9),
+            Need::Sleep => Color::rgb(0.55, 0.4, 0.8),
+            Need::Social => Color::rgb(0.3, 0.75, 0.4),
+            Need::Boredom => Color::rgb(0.9, 0.8, 0.2),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Needs {
+    values: [f32; 5],
+    last_interaction: [f64; 5],
+}
+
+impl Default for Needs {
+    fn default() -> Self {
+        Needs {
+            values: [NEED_MAX; 5],
+            last_interaction: [-INTERACTION_COOLDOWN; 5],
+        }
+    }
+}
+
+impl Needs {
+    pub fn get(&self, need: Need) -> f32 {
+        self.values[need.index()]
+    }
+
+    pub fn change(&mut self, need: Need, delta: f32) {
+        let v = &mut self.values[need.index()];
+        *v = f32::clamp(*v + delta, 0., NEED_MAX);
+    }
+
+    pub fn decay(&mut self, location: Location, dt: f32) {
+        for need in ALL_NEEDS {
+            self.change(need, -decay_rate(need, location) * dt);
+        }
+    }
+
+    // Sanity change contributed by the needs on every sanity tick
+    pub fn sanity_pressure(&self) -> i32 {
+        -(self
+            .values
+            .iter()
+            .filter(|v| **v < NEED_LOW_THRESHOLD)
+            .count() as i32)
+    }
+
+    // Returns whether the interaction did anything (it may still be cooling down)
+    pub fn interact(&mut self, need: Need, amount: f32, now: f64) -> bool {
+        if now - self.last_interaction[need.index()] < INTERACTION_COOLDOWN {
+            return false;
+        }
+        self.last_interaction[need.index()] = now;
+        self.change(need, amount);
+        true
+    }
+}
+
+pub struct NeedsPlugin;
+
+impl Plugin for NeedsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(needs_decay_system)
+            .add_system(interaction_system);
+    }
+}
+
+// Units lost per second. Negative values mean being in that location restores the need.
+fn decay_rate(need: Need, location: Location) -> f32 {
+    match (location, need) {
+        (Location::Home, Need::Hunger) => 0.8,
+        (Location::Home, Need::Hygiene) => 0.6,
+        (Location::Home, Need::Sleep) => 0.7,
+        (Location::Home, Need::Social) => 0.9,
+        (Location::Home, Need::Boredom) => 1.2,
+
+        (Location::Park, Need::Hunger) => 1.0,
+        (Location::Park, Need::Hygiene) => 0.8,
+        (Location::Park, Need::Sleep) => 0.8,
+        (Location::Park, Need::Social) => -0.5,
+        (Location::Park, Need::Boredom) => -2.0,
+
+        (Location::Shops, Need::Hunger) => 0.5,
+        (Location::Shops, Need::Hygiene) => 1.0,
+        (Location::Shops, Need::Sleep) => 0.8,
+        (Location::Shops, Need::Social) => -1.0,
+        (Location::Shops, Need::Boredom) => -1.0,
+
+        (Location::Clinic, Need::Hunger) => 0.8,
+        (Location::Clinic, Need::Hygiene) => 0.8,
+        (Location::Clinic, Need::Sleep) => 0.8,
+        (Location::Clinic, Need::Social) => -0.5,
+        (Location::Clinic, Need::Boredom) => 0.5,
+    }
+}
+
+// Something in the environment the player can use to restore a need, e.g. the fridge
+#[derive(Component, Debug, Clone)]
+pub struct Interactable {
+    pub name: &'static str,
+    pub need: Need,
+    pub amount: f32,
+}
+
+impl Interactable {
+    pub fn new(name: &'static str, need: Need, amount: f32) -> Self {
+        Self { name, need, amount }
+    }
+}
+
+pub fn needs_decay_system(
+    mut state: ResMut<GameState>,
+    environment_query: Query<&Environment>,
+    time: Res<GameClock>,
+) {
+    let environment = environment_query.single();
+    state
+        .needs
+        .decay(environment.location, time.delta_seconds());
+}
+
+pub fn interaction_system(
+    key: Res<Input<KeyCode>>,
+    narrow_phase: Res<NarrowPhase>,
+    interactable_query: Query<(Entity, &Interactable)>,
+    player_query: Query<Entity, With<Player>>,
+    mut state: ResMut<GameState>,
+    time: Res<GameClock>,
+    mut need_events: EventWriter<NeedRestored>,
+) {
+    let now = time.seconds_since_startup();
+
+    // A phone call can be made from anywhere
+    if key.just_pressed(KeyCode::T) && state.needs.interact(Need::Social, PHONE_CALL_AMOUNT, now) {
+        need_events.send(NeedRestored {
+            need: Need::Social,
+            amount: PHONE_CALL_AMOUNT,
+            from: "phone",
+        });
+    }
+
+    if !key.just_pressed(KeyCode::E) {
+        return;
+    }
+
+    let player_entity = player_query.single();
+    for (interactable_entity, interactable) in interactable_query.iter() {
+        for (collider_a, collider_b, intersecting) in
+            narrow_phase.intersections_with(interactable_entity.handle())
+        {
+            if !intersecting {
+                continue;
+            }
+            if collider_a.entity() == player_entity || collider_b.entity() == player_entity {
+                if state
+                    .needs
+                    .interact(interactable.need, interactable.amount, now)
+                {
+                    need_events.send(NeedRestored {
+                        need: interactable.need,
+                        amount: interactable.amount,
+                        from: interactable.name,
+                    });
+                }
+            }
+        }
+    }
+}
+
+pub fn add_interactable(
+    commands: &mut Commands,
+    environment_collider: &EnvironmentCollider,
+    interactable: Interactable,
+) {
+    let (x_pos, y_pos) = (
+        environment_collider.x_coordinates,
+        environment_collider.y_coordinates,
+    );
+    let (width, height) = (
+        environment_collider.width as f32,
+        environment_collider.height as f32,
+    );
+
+    let (collider_x, collider_y) = tile_coords_to_screen_pos(x_pos, width, y_pos, height);
+
+    let collider_flags = ColliderFlags {
+        active_events: ActiveEvents::all(),
+        ..Default::default()
+    }
+    .into();
+
+    commands
+        .spawn_bundle(ColliderBundle {
+            flags: collider_flags,
+            collider_type: ColliderType::Sensor.into(),
+            position: [collider_x / TILE_SIZE, collider_y / TILE_SIZE].into(),
+            shape: ColliderShape::cuboid(width as f32 / 2., height as f32 / 2.).into(),
+            ..Default::default()
+        })
+        .insert(interactable)
+        .insert(environment_collider.clone());
+}
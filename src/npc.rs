@@ -9,6 +9,8 @@ use rand::prelude::*;
 pub struct NPC {
     pub last_moved: Timer,
     pub velocity: Vector2<f32>,
+    // Stood still until then, by the game clock
+    pub paused_until: f64,
 }
 
 impl NPC {
@@ -16,38 +18,241 @@ impl NPC {
         Self {
             last_moved: Timer::from_seconds(1.0, true),
             velocity: [1.0, 0.0].into(),
+            paused_until: 0.,
         }
     }
 }
 
+// Who an NPC is, e.g. "Friend" or "Jogger"
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct Identity {
+    pub name: String,
+}
+
+// Scripted to walk right up to the player, e.g. a friend meeting them
+#[derive(Component)]
+pub struct IgnoresPersonalSpace {}
+
 use crate::{
-    environment::tile_coords_to_screen_pos,
-    player::{SPRITE_SIZE_X, SPRITE_SIZE_Y},
+    behaviour::{behaviour_system, Behaviour},
+    clock::GameClock,
+    covid::Exposure,
+    environment::{tile_coords_to_screen_pos, EnvironmentCollider},
+    needs::Interactable,
+    pathfinding::{pathfinding_system, tile_position, Destination, Path},
+    player::{Player, SPRITE_SIZE_X, SPRITE_SIZE_Y},
+    rng::GameRng,
+    teleportation::Teleporter,
     TILE_SIZE,
 };
 
+// In physics units per second
+const NPC_SPEED: f32 = 1.;
+// How far ahead NPCs look for walls and furniture, in physics units
+const LOOK_AHEAD: f32 = 1.5;
+// How close NPCs let each other get, and how close they let the player get
+const NPC_SPACING: f32 = 1.5;
+const PERSONAL_SPACE: f32 = 2.5;
+// How hard each of those pushes an NPC around, against carrying on the way it was going
+const WALL_WEIGHT: f32 = 2.;
+const SPACING_WEIGHT: f32 = 1.;
+const PERSONAL_SPACE_WEIGHT: f32 = 2.;
+// How close to the middle of a tile on its path an NPC has to get before making for the next one
+const WAYPOINT_DISTANCE: f32 = 0.25;
+
+pub struct NpcPlugin;
+
+impl Plugin for NpcPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(npc_system)
+            .add_system(pathfinding_system.label("pathfinding").before("teleport"))
+            .add_system(behaviour_system.before("teleport"));
+    }
+}
+
+// Something solid, by its bottom left and top right corners
+pub type Obstacle = (Vector2<f32>, Vector2<f32>);
+
+// Away from `from`, harder the further inside `range` it is
+fn repel(position: Vector2<f32>, from: Vector2<f32>, range: f32) -> Vector2<f32> {
+    let away = position - from;
+    let d = away.magnitude();
+    if d >= range {
+        return Vector2::zeros();
+    }
+    if d < f32::EPSILON {
+        // right on top of each other, so anywhere will do
+        return Vector2::new(1., 0.);
+    }
+    away / d * (range - d) / range
+}
+
+// Where an NPC heading along `heading` at `position` should go instead, so as not to walk into
+// walls, furniture, each other or (if given) the player
+pub fn steer(
+    position: Vector2<f32>,
+    heading: Vector2<f32>,
+    obstacles: &[Obstacle],
+    others: &[Vector2<f32>],
+    player: Option<Vector2<f32>>,
+) -> Vector2<f32> {
+    let heading = if heading.magnitude() > f32::EPSILON {
+        heading.normalize()
+    } else {
+        Vector2::new(1., 0.)
+    };
+    // The NPC is as big as the player
+    let half_size = Vector2::new(SPRITE_SIZE_X, SPRITE_SIZE_Y) / TILE_SIZE / 2.;
+    let ahead = position + heading * LOOK_AHEAD;
+
+    let mut push = Vector2::zeros();
+    for (min, max) in obstacles {
+        let (min, max) = (min - half_size, max + half_size);
+        let nearest = Vector2::new(
+            position.x.clamp(min.x, max.x),
+            position.y.clamp(min.y, max.y),
+        );
+        if nearest == position {
+            // Already overlapping it, so out the nearest side
+            let exits = [
+                (position.x - min.x, Vector2::new(-1., 0.)),
+                (max.x - position.x, Vector2::new(1., 0.)),
+                (position.y - min.y, Vector2::new(0., -1.)),
+                (max.y - position.y, Vector2::new(0., 1.)),
+            ];
+            let (_, out) = exits.iter().min_by(|a, b| a.0.total_cmp(&b.0)).unwrap();
+            push += out * WALL_WEIGHT;
+        } else {
+            push += repel(position, nearest, LOOK_AHEAD) * WALL_WEIGHT;
+        }
+
+        // Walking straight at it only slows the NPC down, so turn as well
+        let blocked = (min.x..=max.x).contains(&ahead.x) && (min.y..=max.y).contains(&ahead.y);
+        if blocked {
+            let left = Vector2::new(-heading.y, heading.x);
+            let centre = (min + max) / 2.;
+            let side = if left.dot(&(centre - position)) > 0. {
+                -left
+            } else {
+                left
+            };
+            push += side * WALL_WEIGHT;
+        }
+    }
+    for other in others {
+        push += repel(position, *other, NPC_SPACING) * SPACING_WEIGHT;
+    }
+    if let Some(player) = player {
+        push += repel(position, player, PERSONAL_SPACE) * PERSONAL_SPACE_WEIGHT;
+    }
+
+    let steered = heading + push;
+    if steered.magnitude() < f32::EPSILON {
+        return Vector2::new(-heading.y, heading.x) * NPC_SPEED;
+    }
+    steered.normalize() * NPC_SPEED
+}
+
+// Everything an NPC needs to steer itself
+type MovingNpcs<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static mut NPC,
+        &'static Behaviour,
+        &'static mut RigidBodyVelocityComponent,
+        &'static RigidBodyPositionComponent,
+        Option<&'static IgnoresPersonalSpace>,
+        Option<&'static mut Path>,
+        Option<&'static Destination>,
+        Entity,
+    ),
+>;
+
 pub fn npc_system(
-    mut npc_query: Query<(&mut NPC, &mut RigidBodyVelocityComponent)>,
-    time: Res<Time>,
+    mut npc_query: MovingNpcs,
+    player_query: Query<&RigidBodyPositionComponent, (With<Player>, Without<NPC>)>,
+    obstacle_query: Query<&EnvironmentCollider, (Without<Teleporter>, Without<Interactable>)>,
+    time: Res<GameClock>,
+    mut rng: ResMut<GameRng>,
 ) {
-    for (mut npc, mut rigid_body_velocity) in npc_query.iter_mut() {
+    let obstacles: Vec<Obstacle> = obstacle_query
+        .iter()
+        .map(|c| {
+            let (min, max) = c.bounds();
+            (min.into(), max.into())
+        })
+        .collect();
+    let npcs: Vec<(Entity, Vector2<f32>)> = npc_query
+        .iter()
+        .map(|(_, _, _, p, _, _, _, e)| (e, p.position.translation.vector))
+        .collect();
+    let player = player_query
+        .get_single()
+        .ok()
+        .map(|p| p.position.translation.vector);
+
+    for (
+        mut npc,
+        behaviour,
+        mut rigid_body_velocity,
+        position,
+        ignores_personal_space,
+        mut path,
+        destination,
+        entity,
+    ) in npc_query.iter_mut()
+    {
+        let position = position.position.translation.vector;
+
+        // Following a path, or waiting for one, rather than wandering
+        if let Some(path) = path.as_mut() {
+            if let Some(tile) = path.waypoint() {
+                if (tile_position(tile) - position).magnitude() < WAYPOINT_DISTANCE {
+                    path.advance();
+                }
+            }
+        }
+        let paused = time.seconds_since_startup() < npc.paused_until;
+        let heading = match (path.as_ref().and_then(|p| p.waypoint()), destination) {
+            _ if paused => Some(Vector2::zeros()),
+            (Some(tile), _) => Some(tile_position(tile) - position),
+            (None, Some(_)) => Some(Vector2::zeros()),
+            (None, None) => None,
+        };
+
         {
             let timer = &mut npc.last_moved;
             timer.tick(time.delta());
         }
 
         let timer = &npc.last_moved;
-        if timer.just_finished() {
-            let mut rng = rand::thread_rng();
+        if timer.just_finished() && heading.is_none() {
             let rand: f64 = rng.gen();
             if rand >= 0.80 {
                 set_new_direction(rand, &mut npc.velocity);
             }
         }
 
-        rigid_body_velocity.linvel = npc.velocity;
-
-        // TODO: If NPC collides with wall, IMMEDIATELY change position.
+        let others: Vec<Vector2<f32>> = npcs
+            .iter()
+            .filter(|(e, _)| *e != entity)
+            .map(|(_, p)| *p)
+            .collect();
+        let player = player.filter(|_| ignores_personal_space.is_none());
+        match heading {
+            // Somewhere to be, so only turn aside for whatever's in the way
+            Some(heading) if heading.magnitude() > f32::EPSILON => {
+                rigid_body_velocity.linvel =
+                    steer(position, heading, &obstacles, &others, player) * behaviour.speed();
+            }
+            // There, or stopped for a bit, so stay put
+            Some(_) => rigid_body_velocity.linvel = Vector2::zeros(),
+            None => {
+                npc.velocity = steer(position, npc.velocity, &obstacles, &others, player);
+                rigid_body_velocity.linvel = npc.velocity * behaviour.speed();
+            }
+        }
     }
 }
 
@@ -70,7 +275,12 @@ fn set_new_direction(rand: f64, current_velocity: &mut Vector2<f32>) {
 }
 
 // TODO: add ability to set location of NPC
-pub fn spawn_npc(commands: &mut Commands, asset_server: &Res<AssetServer>, position: [usize; 2]) {
+pub fn spawn_npc(
+    commands: &mut Commands,
+    asset_server: &Res<AssetServer>,
+    position: [usize; 2],
+    behaviour: Behaviour,
+) -> Entity {
     println!(
         "Spawning NPC: size: x: {:?} y: {:?}",
         SPRITE_SIZE_X, SPRITE_SIZE_Y
@@ -91,7 +301,7 @@ pub fn spawn_npc(commands: &mut Commands, asset_server: &Res<AssetServer>, posit
 
     commands
         .spawn_bundle(SpriteBundle {
-            texture: asset_server.load("npc.png"),
+            texture: asset_server.load(behaviour.sprite()),
             transform: Transform {
                 translation: [0., 0., 1.].into(),
                 ..Default::default()
@@ -99,6 +309,11 @@ pub fn spawn_npc(commands: &mut Commands, asset_server: &Res<AssetServer>, posit
             ..Default::default()
         })
         .insert(NPC::new())
+        .insert(Identity {
+            name: String::from(behaviour.identity()),
+        })
+        .insert(behaviour)
+        .insert(Exposure::default())
         .insert_bundle(RigidBodyBundle {
             position: [pos_x / TILE_SIZE, pos_y / TILE_SIZE].into(),
             velocity: RigidBodyVelocity::new([1., 0.].into(), Default::default()).into(),
@@ -110,5 +325,6 @@ pub fn spawn_npc(commands: &mut Commands, asset_server: &Res<AssetServer>, posit
             ..Default::default()
         })
         .insert(ColliderPositionSync::Discrete)
-        .insert(ColliderDebugRender::with_id(1));
+        .insert(ColliderDebugRender::with_id(1))
+        .id()
 }
@@ -0,0 +1,238 @@
+// Getting NPCs from one tile to another. Each location's walls and furniture are baked into a grid
+// of the tiles an NPC can stand on, and A* finds the way across it. Give an NPC a Destination and
+// it gets a Path to follow; without one it wanders.
+use bevy::prelude::*;
+use bevy_rapier2d::{na::Vector2, prelude::*};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+use crate::clock::GameClock;
+use crate::environment::{tile_coords_to_screen_pos, EnvironmentCollider, GRID_SIZE};
+use crate::npc::NPC;
+use crate::player::Player;
+use crate::{SCREEN_HEIGHT, SCREEN_WIDTH, TILE_SIZE};
+
+// NPCs take up as many tiles as the player, and stand on the top left one of them
+const FOOTPRINT: [usize; 2] = [2, 3];
+// How often an NPC heading for the player works out the way again, since the player moves
+const REPLAN_SECONDS: f64 = 1.;
+
+// Which tiles an NPC can stand on in the current location
+#[derive(Debug, Clone)]
+pub struct NavGrid {
+    free: [[bool; GRID_SIZE]; GRID_SIZE],
+}
+
+impl Default for NavGrid {
+    fn default() -> Self {
+        NavGrid {
+            free: [[true; GRID_SIZE]; GRID_SIZE],
+        }
+    }
+}
+
+impl NavGrid {
+    pub fn new(colliders: &[EnvironmentCollider]) -> Self {
+        let mut free = [[true; GRID_SIZE]; GRID_SIZE];
+        for c in colliders {
+            for column in free.iter_mut().skip(c.x_coordinates).take(c.width) {
+                for tile in column.iter_mut().skip(c.y_coordinates).take(c.height) {
+                    *tile = false;
+                }
+            }
+        }
+        NavGrid { free }
+    }
+
+    // Whether an NPC fits with its top left on `tile`
+    pub fn walkable(&self, tile: [usize; 2]) -> bool {
+        let [x, y] = tile;
+        if x + FOOTPRINT[0] > GRID_SIZE || y + FOOTPRINT[1] > GRID_SIZE {
+            return false;
+        }
+        (x..x + FOOTPRINT[0]).all(|x| (y..y + FOOTPRINT[1]).all(|y| self.free[x][y]))
+    }
+
+    fn neighbours(&self, tile: [usize; 2]) -> impl Iterator<Item = [usize; 2]> + '_ {
+        let [x, y] = tile;
+        let mut rv = Vec::with_capacity(4);
+        if x > 0 {
+            rv.push([x - 1, y]);
+        }
+        if y > 0 {
+            rv.push([x, y - 1]);
+        }
+        rv.push([x + 1, y]);
+        rv.push([x, y + 1]);
+        rv.into_iter().filter(move |t| self.walkable(*t))
+    }
+
+    // The closest tile to `tile` an NPC can stand on, if there's anywhere at all
+    pub fn nearest_walkable(&self, tile: [usize; 2]) -> Option<[usize; 2]> {
+        let mut tiles: Vec<[usize; 2]> = (0..GRID_SIZE)
+            .flat_map(|x| (0..GRID_SIZE).map(move |y| [x, y]))
+            .filter(|t| self.walkable(*t))
+            .collect();
+        tiles.sort_by_key(|t| distance(*t, tile));
+        tiles.first().copied()
+    }
+
+    // The tiles from `from` to `to`, both included, or None if there's no way through. Either end
+    // that's blocked moves to the nearest tile that isn't.
+    pub fn find_path(&self, from: [usize; 2], to: [usize; 2]) -> Option<Vec<[usize; 2]>> {
+        let from = self.nearest_walkable(from)?;
+        let to = self.nearest_walkable(to)?;
+
+        let mut open = BinaryHeap::new();
+        let mut came_from: HashMap<[usize; 2], [usize; 2]> = HashMap::new();
+        let mut cost: HashMap<[usize; 2], usize> = HashMap::new();
+        cost.insert(from, 0);
+        open.push(Reverse((distance(from, to), from)));
+
+        while let Some(Reverse((_, tile))) = open.pop() {
+            if tile == to {
+                let mut rv = vec![tile];
+                while let Some(previous) = came_from.get(rv.last().unwrap()) {
+                    rv.push(*previous);
+                }
+                rv.reverse();
+                return Some(rv);
+            }
+            let here = cost[&tile];
+            for next in self.neighbours(tile) {
+                if cost.get(&next).map(|c| here + 1 < *c).unwrap_or(true) {
+                    cost.insert(next, here + 1);
+                    came_from.insert(next, tile);
+                    open.push(Reverse((here + 1 + distance(next, to), next)));
+                }
+            }
+        }
+        None
+    }
+}
+
+// Tiles apart, going along the grid
+fn distance(a: [usize; 2], b: [usize; 2]) -> usize {
+    (a[0] as isize - b[0] as isize).unsigned_abs() + (a[1] as isize - b[1] as isize).unsigned_abs()
+}
+
+// Where an NPC standing on `tile` is, in physics units
+pub fn tile_position(tile: [usize; 2]) -> Vector2<f32> {
+    let (x, y) =
+        tile_coords_to_screen_pos(tile[0], FOOTPRINT[0] as f32, tile[1], FOOTPRINT[1] as f32);
+    Vector2::new(x / TILE_SIZE, y / TILE_SIZE)
+}
+
+// The tile something at `position`, in physics units, is standing on
+pub fn position_tile(position: Vector2<f32>) -> [usize; 2] {
+    let x = position.x + SCREEN_WIDTH / 2. / TILE_SIZE - FOOTPRINT[0] as f32 / 2.;
+    let y = (SCREEN_HEIGHT / 2. - 30.) / TILE_SIZE - FOOTPRINT[1] as f32 / 2. - position.y;
+    let clamp = |v: f32| (v.round().max(0.) as usize).min(GRID_SIZE - 1);
+    [clamp(x), clamp(y)]
+}
+
+// Where an NPC is headed
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub enum Destination {
+    Tile([usize; 2]),
+    // Right up to the player, or someone else, wherever they go
+    Player,
+    Entity(Entity),
+}
+
+// The way there, a tile at a time
+#[derive(Component, Debug, Clone, Default)]
+pub struct Path {
+    tiles: Vec<[usize; 2]>,
+    next: usize,
+}
+
+impl Path {
+    pub fn new(tiles: Vec<[usize; 2]>) -> Self {
+        Path { tiles, next: 0 }
+    }
+
+    // The tile to make for next, or None once it's there
+    pub fn waypoint(&self) -> Option<[usize; 2]> {
+        self.tiles.get(self.next).copied()
+    }
+
+    pub fn advance(&mut self) {
+        self.next += 1;
+    }
+
+    pub fn finished(&self) -> bool {
+        self.next >= self.tiles.len()
+    }
+}
+
+// Works out a Path for each NPC that's been given somewhere to go
+pub fn pathfinding_system(
+    mut commands: Commands,
+    grid: Res<NavGrid>,
+    npc_query: Query<
+        (
+            Entity,
+            &Destination,
+            &RigidBodyPositionComponent,
+            Option<&Path>,
+        ),
+        With<NPC>,
+    >,
+    player_query: Query<Entity, With<Player>>,
+    position_query: Query<&RigidBodyPositionComponent>,
+    time: Res<GameClock>,
+    mut last_replan: Local<f64>,
+) {
+    let now = time.seconds_since_startup();
+    let replan = now - *last_replan > REPLAN_SECONDS;
+    if replan {
+        *last_replan = now;
+    }
+
+    for (entity, destination, position, path) in npc_query.iter() {
+        let goal = match destination {
+            Destination::Tile(tile) => match path {
+                // There, so back to wandering
+                Some(p) if p.finished() => {
+                    commands
+                        .entity(entity)
+                        .remove::<Destination>()
+                        .remove::<Path>();
+                    continue;
+                }
+                Some(_) => continue,
+                None => *tile,
+            },
+            Destination::Player | Destination::Entity(_) => {
+                if path.map(|p| !p.finished()).unwrap_or(false) && !replan {
+                    continue;
+                }
+                let target = match destination {
+                    Destination::Entity(e) => Some(*e),
+                    _ => player_query.get_single().ok(),
+                };
+                match target.and_then(|e| position_query.get(e).ok()) {
+                    Some(p) => position_tile(p.position.translation.vector),
+                    None => continue,
+                }
+            }
+        };
+        let from = position_tile(position.position.translation.vector);
+        match grid.find_path(from, goal) {
+            Some(tiles) => {
+                commands.entity(entity).insert(Path::new(tiles));
+            }
+            None => {
+                println!(
+                    "{:?} can't find a way from {:?} to {:?}",
+                    entity, from, goal
+                );
+                commands
+                    .entity(entity)
+                    .remove::<Destination>()
+                    .remove::<Path>();
+            }
+        }
+    }
+}
@@ -0,0 +1,91 @@
+// Everyone who texts the player, from narrative/contacts.csv
+use bevy::prelude::*;
+use std::fs::File;
+
+use crate::narrative::{csv_header, get, non_empty};
+
+pub const CONTACTS_FILE: &str = "./narrative/contacts.csv";
+
+// The old one-size-fits-all bubble colour, for anyone not in the contacts file
+const DEFAULT_COLOUR: Color = Color::rgb(175. / 255., 233. / 255., 198. / 255.);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Channel {
+    // Texts from people, each with their own thread
+    Personal,
+    // Official announcements, all in the one alerts thread
+    Alerts,
+}
+
+#[derive(Clone, Debug)]
+pub struct Contact {
+    // As it appears in the narrative's Sender column
+    pub sender: String,
+    pub name: String,
+    pub channel: Channel,
+    pub colour: Color,
+    // Relative to assets/
+    pub avatar: Option<String>,
+}
+
+impl Contact {
+    pub fn unknown(sender: &str) -> Contact {
+        println!("no contact for {}, using the defaults", sender);
+        Contact {
+            sender: String::from(sender),
+            name: String::from(sender),
+            channel: Channel::Personal,
+            colour: DEFAULT_COLOUR,
+            avatar: None,
+        }
+    }
+}
+
+pub fn load_contacts(file: &str) -> Vec<Contact> {
+    let file = File::open(file).expect("error opening contacts csv");
+    let mut rdr = csv::Reader::from_reader(file);
+    let headers = rdr.headers().expect("error reading row").clone();
+    let h = csv_header(&headers);
+
+    let mut rv = Vec::new();
+    for x in rdr.records() {
+        let x = x.unwrap();
+        let sender = get(&h, &x, "Sender");
+        if !non_empty(sender) {
+            continue;
+        }
+        let avatar = get(&h, &x, "Avatar");
+        rv.push(Contact {
+            sender: String::from(sender),
+            name: String::from(get(&h, &x, "Name")),
+            channel: str2channel(get(&h, &x, "Channel")),
+            colour: str2colour(get(&h, &x, "Colour")),
+            avatar: if non_empty(avatar) {
+                Some(String::from(avatar))
+            } else {
+                None
+            },
+        });
+    }
+    rv
+}
+
+fn str2channel(s: &str) -> Channel {
+    match s {
+        "Personal" => Channel::Personal,
+        "Alerts" => Channel::Alerts,
+        _ => panic!("bad channel >>{}<<", s),
+    }
+}
+
+// #rrggbb
+fn str2colour(s: &str) -> Color {
+    let hex = s.trim_start_matches('#');
+    if !non_empty(s) {
+        return DEFAULT_COLOUR;
+    }
+    match u32::from_str_radix(hex, 16) {
+        Ok(v) if hex.len() == 6 => Color::rgb_u8((v >> 16) as u8, (v >> 8) as u8, v as u8),
+        _ => panic!("bad colour >>{}<<", s),
+    }
+}
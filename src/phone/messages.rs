@@ -0,0 +1,685 @@
+// The text messages in a conversation thread, and the bubbles they're drawn in.
+//
+// Every text is laid out once, when it arrives (and again once the font has loaded, if it came in
+// before then). Only the bubbles that fit on the phone exist at
+// any time: a bubble that scrolls off gets handed the next message that scrolls on, and a new text
+// only needs one bubble filling in while the rest slide up.
+use bevy::input::mouse::{MouseScrollUnit, MouseWheel};
+use bevy::prelude::*;
+
+use super::{screen_xpos, Phone, PhoneAction, PhoneButton};
+use crate::clock::GameClock;
+use crate::ui::ease_in_out_circ;
+use crate::wrap::{measure, wrap_text};
+use crate::SCREEN_HEIGHT;
+
+const MESSAGE_FONT_SIZE: f32 = 24.;
+const SENDER_FONT_SIZE: f32 = 18.;
+const MESSAGE_LINE_SPACING: f32 = 2.;
+const INTER_MESSAGE_SPACING: f32 = 20.;
+const MESSAGE_BUBBLE_WIDTH: f32 = 235.;
+const MESSAGE_PADDING_RIGHT: f32 = 10.;
+
+// How long a new text takes to slide in
+const SLIDE_TIME: f64 = 0.3;
+// Where bubbles with nothing to show wait to be reused, well off the screen
+const PARKED_Y: f32 = -2. * SCREEN_HEIGHT;
+
+// How far one wheel click / one Page Up scrolls the phone
+const SCROLL_LINE: f32 = 40.;
+const SCROLL_PAGE: f32 = 400.;
+
+// The edges, sender and lines that make up each bubble
+type BubbleParts<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static mut Transform,
+        Option<&'static mut Text>,
+        Option<&'static mut Sprite>,
+    ),
+    Without<MessageBubble>,
+>;
+
+// How a bubble looks
+#[derive(Clone, Copy, Debug)]
+pub struct BubbleStyle {
+    pub colour: Color,
+    pub text_colour: Color,
+}
+
+struct TextMessage {
+    // What goes at the top of the bubble
+    sender: String,
+    text: String,
+    lines: Vec<String>,
+    // Whether the lines were wrapped with the real font, rather than an estimate
+    measured: bool,
+    style: BubbleStyle,
+    // Height of the bubble
+    height: f32,
+    // Height of every older message (and the gaps between them) stacked up below this one
+    start: f32,
+}
+
+// One conversation, and how far back through it the phone is scrolled
+#[derive(Default)]
+pub struct MessageLog {
+    messages: Vec<TextMessage>,
+    // All the messages stacked up, gaps included
+    total_height: f32,
+    // How far (in pixels) the phone has been scrolled back from the newest message
+    scroll: f32,
+    // Texts that arrived while the thread was closed, or scrolled back
+    pub unread: usize,
+    // The newest text slides everything up by this much, starting at this time
+    slide: f32,
+    slide_started: f64,
+    // Goes up every time the messages are laid out again, so the bubbles know to refill
+    layout: u32,
+}
+
+// The wrapped lines for a text, and the height of the bubble they go in
+fn lay_out(text: &str, font: Option<&Font>) -> (Vec<String>, f32) {
+    let lines = wrap_text(
+        measure(font, MESSAGE_FONT_SIZE).as_ref(),
+        MESSAGE_BUBBLE_WIDTH - MESSAGE_PADDING_RIGHT,
+        text,
+    );
+    let height = SENDER_FONT_SIZE
+        + MESSAGE_LINE_SPACING
+        + (lines.len() as f32 * (MESSAGE_LINE_SPACING + MESSAGE_FONT_SIZE))
+        + 4.;
+    (lines, height)
+}
+
+impl MessageLog {
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+
+    // The first line of the newest message, for the contacts list
+    pub fn preview(&self) -> Option<&str> {
+        self.messages
+            .last()
+            .and_then(|m| m.lines.first())
+            .map(|l| l.as_str())
+    }
+
+    // The whole of the newest message, its wrapped lines joined back up
+    pub fn newest(&self) -> Option<String> {
+        self.messages.last().map(|m| m.text.clone())
+    }
+
+    // The wrapped lines of message `i`
+    pub fn lines(&self, i: usize) -> &[String] {
+        &self.messages[i].lines
+    }
+
+    // Whether any of the texts were laid out before the font had loaded
+    pub fn estimated(&self) -> bool {
+        self.messages.iter().any(|m| !m.measured)
+    }
+
+    // Wraps whatever was laid out with the estimate again, now the font is here, and restacks
+    // everything to fit
+    pub fn relayout(&mut self, font: &Font) {
+        let mut start = 0.;
+        for m in self.messages.iter_mut() {
+            if !m.measured {
+                let (lines, height) = lay_out(&m.text, Some(font));
+                m.lines = lines;
+                m.height = height;
+                m.measured = true;
+            }
+            m.start = start;
+            start += m.height + INTER_MESSAGE_SPACING;
+        }
+        self.total_height = start;
+        self.scroll = f32::min(self.scroll, self.max_scroll());
+        self.slide = 0.;
+        self.layout += 1;
+    }
+
+    // `reading` is whether the thread is open on the phone right now. `font` is the bubble font,
+    // if it's loaded yet.
+    pub fn add(
+        &mut self,
+        sender: &str,
+        style: BubbleStyle,
+        text: &str,
+        font: Option<&Font>,
+        now: f64,
+        reading: bool,
+    ) {
+        let (lines, height) = lay_out(text, font);
+        self.messages.push(TextMessage {
+            sender: String::from(sender),
+            text: String::from(text),
+            lines,
+            measured: font.is_some(),
+            style,
+            height,
+            start: self.total_height,
+        });
+        self.total_height += height + INTER_MESSAGE_SPACING;
+
+        if !reading {
+            self.unread += 1;
+        } else if self.scroll > 0. {
+            // The reader is looking at something older: keep it where it is, and tell them
+            self.scroll += height + INTER_MESSAGE_SPACING;
+            self.unread += 1;
+        } else {
+            self.slide = height + INTER_MESSAGE_SPACING;
+            self.slide_started = now;
+        }
+    }
+
+    // Opening a thread starts at the newest message
+    pub fn open(&mut self) {
+        self.scroll = 0.;
+        self.slide = 0.;
+        self.unread = 0;
+    }
+
+    // Positive `by` scrolls back towards older messages
+    pub fn scroll(&mut self, by: f32) {
+        let scroll = f32::clamp(self.scroll + by, 0., self.max_scroll());
+        if scroll == self.scroll {
+            return;
+        }
+        self.scroll = scroll;
+        self.slide = 0.;
+        if scroll == 0. {
+            self.unread = 0;
+        }
+    }
+
+    pub fn scroll_to_newest(&mut self) {
+        self.scroll(-self.scroll);
+    }
+
+    pub fn scrolled_back(&self) -> bool {
+        self.scroll > 0.
+    }
+
+    // Scrolled back as far as this, the oldest message sits at the top of the phone
+    fn max_scroll(&self) -> f32 {
+        f32::max(
+            0.,
+            self.total_height - INTER_MESSAGE_SPACING - (messages_top() - messages_bottom()),
+        )
+    }
+
+    // Where message `i`'s bottom edge ends up once it's done sliding
+    fn bottom_of(&self, i: usize) -> f32 {
+        let m = &self.messages[i];
+        messages_bottom() - self.scroll + self.total_height
+            - (m.start + m.height + INTER_MESSAGE_SPACING)
+    }
+
+    // The messages (at least partly) on the phone, newest first
+    fn on_screen(&self) -> Vec<usize> {
+        // could be S_H / 2, but we need to be a bit careful here because we over display. So
+        // just go whole hog and don't divide by 2
+        let view_top = SCREEN_HEIGHT / 1.;
+        let mut rv = vec![];
+        for i in (0..self.messages.len()).rev() {
+            let bottom = self.bottom_of(i);
+            if bottom > view_top {
+                break;
+            }
+            if bottom + self.messages[i].height >= messages_bottom() {
+                rv.push(i);
+            }
+        }
+        rv
+    }
+
+    // How far below their resting place the bubbles are right now
+    fn slide_offset(&self, now: f64) -> f32 {
+        let t = f64::min(SLIDE_TIME, now - self.slide_started) / SLIDE_TIME;
+        self.slide * (1. - ease_in_out_circ(t as f32))
+    }
+}
+
+// One of the bubbles on the phone. The parts are its children, kept so they can be refilled when
+// the bubble is handed another message.
+#[derive(Component)]
+pub struct MessageBubble {
+    // (thread, message)
+    message: Option<(usize, usize)>,
+    // Which layout of the thread it was filled from
+    layout: u32,
+    // Where its middle sits once it's done sliding
+    y: f32,
+    top_edge: Entity,
+    bottom_edge: Entity,
+    sender: Entity,
+    // From the bottom line up; there may be more of these than the message needs
+    lines: Vec<Entity>,
+}
+
+#[derive(Component)]
+pub struct NewMessagesTag {}
+
+pub fn setup_messages(mut commands: Commands, asset_server: Res<AssetServer>) {
+    // shown when texts arrive while scrolled back through the history
+    commands
+        .spawn_bundle(SpriteBundle {
+            transform: Transform {
+                translation: [screen_xpos(), new_messages_ypos(), 31.].into(),
+                ..Default::default()
+            },
+            sprite: Sprite {
+                color: Color::rgb(0.2, 0.55, 0.3),
+                custom_size: Some(Vec2::new(240., 30.)),
+                ..Default::default()
+            },
+            visibility: Visibility { is_visible: false },
+            ..Default::default()
+        })
+        .insert(NewMessagesTag {})
+        .insert(PhoneButton {
+            action: PhoneAction::ScrollToNewest,
+            size: Vec2::new(240., 30.),
+        })
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(Text2dBundle {
+                    text: Text::with_section(
+                        "",
+                        TextStyle {
+                            font: asset_server.load("fonts/monofonto.ttf"),
+                            font_size: 20.,
+                            color: Color::rgb(1., 1., 1.),
+                        },
+                        TextAlignment {
+                            vertical: VerticalAlign::Center,
+                            horizontal: HorizontalAlign::Center,
+                        },
+                    ),
+                    transform: Transform {
+                        translation: [0., 0., 0.5].into(),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                })
+                .insert(NewMessagesTag {});
+        });
+}
+
+// Mouse wheel, Page Up/Down, and End to get back to the newest
+pub fn message_scroll_system(
+    mut wheel_events: EventReader<MouseWheel>,
+    keys: Res<Input<KeyCode>>,
+    mut phone: ResMut<Phone>,
+) {
+    let mut by = 0.;
+    for e in wheel_events.iter() {
+        by += match e.unit {
+            MouseScrollUnit::Line => e.y * SCROLL_LINE,
+            MouseScrollUnit::Pixel => e.y,
+        };
+    }
+    if keys.just_pressed(KeyCode::PageUp) {
+        by += SCROLL_PAGE;
+    }
+    if keys.just_pressed(KeyCode::PageDown) {
+        by -= SCROLL_PAGE;
+    }
+
+    let scrolled_back = phone
+        .open_log()
+        .map(|(_, log)| log.scrolled_back())
+        .unwrap_or(false);
+    if by != 0. {
+        if let Some(log) = phone.open_log_mut() {
+            log.scroll(by);
+        }
+    } else if keys.just_pressed(KeyCode::End) && scrolled_back {
+        if let Some(log) = phone.open_log_mut() {
+            log.scroll_to_newest();
+        }
+    }
+}
+
+// Hands out bubbles to whatever has just come on screen, taking them from whatever has just gone
+// off it
+pub fn message_bubble_system(
+    mut commands: Commands,
+    phone: Res<Phone>,
+    time: Res<GameClock>,
+    asset_server: Res<AssetServer>,
+    mut bubbles: Query<(Entity, &mut MessageBubble, &mut Sprite)>,
+    mut parts: BubbleParts,
+) {
+    if !phone.is_changed() {
+        return;
+    }
+
+    // Nothing to show if the phone isn't on a thread
+    let (thread, on_screen, layout) = match phone.open_log() {
+        Some((thread, log)) => (thread, log.on_screen(), log.layout),
+        None => (0, vec![], 0),
+    };
+    let mut placed = vec![];
+    let mut free = vec![];
+    for (e, mut bubble, _) in bubbles.iter_mut() {
+        match bubble.message {
+            Some((t, i)) if t == thread && on_screen.contains(&i) && bubble.layout == layout => {
+                let log = phone.open_log().unwrap().1;
+                bubble.y = log.bottom_of(i) + log.messages[i].height / 2.;
+                placed.push(i);
+            }
+            _ => {
+                bubble.message = None;
+                free.push(e);
+            }
+        }
+    }
+    if on_screen.is_empty() {
+        return;
+    }
+
+    let log = phone.open_log().unwrap().1;
+    let font = asset_server.load("fonts/monofonto.ttf");
+    for i in on_screen {
+        if placed.contains(&i) {
+            continue;
+        }
+        let message = &log.messages[i];
+        let y = log.bottom_of(i) + message.height / 2.;
+        match free.pop() {
+            Some(e) => {
+                let (_, mut bubble, mut sprite) = bubbles.get_mut(e).unwrap();
+                bubble.message = Some((thread, i));
+                bubble.layout = layout;
+                bubble.y = y;
+                sprite.custom_size = Some(Vec2::new(MESSAGE_BUBBLE_WIDTH, message.height));
+                sprite.color = message.style.colour;
+                fill_bubble(&mut commands, e, &mut bubble, message, &font, &mut parts);
+            }
+            None => {
+                let offset = log.slide_offset(time.seconds_since_startup());
+                spawn_bubble(
+                    &mut commands,
+                    &asset_server,
+                    (thread, i),
+                    layout,
+                    message,
+                    y - offset,
+                );
+            }
+        }
+    }
+}
+
+// Moves the bubbles to where they should be, partway through sliding or not
+pub fn message_slide_system(
+    phone: Res<Phone>,
+    time: Res<GameClock>,
+    mut query: Query<(&MessageBubble, &mut Transform)>,
+) {
+    let offset = match phone.open_log() {
+        Some((_, log)) => log.slide_offset(time.seconds_since_startup()),
+        None => 0.,
+    };
+    for (bubble, mut t) in query.iter_mut() {
+        t.translation.y = match bubble.message {
+            Some(_) => bubble.y - offset,
+            None => PARKED_Y,
+        };
+    }
+}
+
+pub fn new_messages_indicator(
+    phone: Res<Phone>,
+    mut query: Query<(&mut Visibility, Option<&mut Text>), With<NewMessagesTag>>,
+) {
+    if !phone.is_changed() {
+        return;
+    }
+    let unread = match phone.open_log() {
+        Some((_, log)) => log.unread,
+        None => 0,
+    };
+    for (mut v, text) in query.iter_mut() {
+        v.is_visible = unread > 0;
+        if let Some(mut text) = text {
+            text.sections[0].value = match unread {
+                1 => String::from("1 new message"),
+                n => format!("{} new messages", n),
+            };
+        }
+    }
+}
+
+fn sender_style(font: &Handle<Font>, colour: Color) -> TextStyle {
+    TextStyle {
+        font: font.clone(),
+        font_size: SENDER_FONT_SIZE,
+        color: colour,
+    }
+}
+
+fn message_style(font: &Handle<Font>, colour: Color) -> TextStyle {
+    TextStyle {
+        font: font.clone(),
+        font_size: MESSAGE_FONT_SIZE,
+        color: colour,
+    }
+}
+
+const ALIGN: TextAlignment = TextAlignment {
+    vertical: VerticalAlign::Center,
+    horizontal: HorizontalAlign::Left,
+};
+
+// Where the pretty bubble edges go, relative to the middle of a bubble this high
+fn top_edge_y(height: f32) -> f32 {
+    height / 2. - 50. / 2.
+}
+
+fn bottom_edge_y(height: f32) -> f32 {
+    -height / 2. + 14. / 2.
+}
+
+// Lines are counted from the bottom up; the sender goes on the line above the last one
+fn line_y(height: f32, line: usize) -> f32 {
+    -height / 2.
+        + MESSAGE_FONT_SIZE / 2.
+        + 2.
+        + line as f32 * (MESSAGE_FONT_SIZE + MESSAGE_LINE_SPACING)
+}
+
+fn line_translation(height: f32, line: usize) -> Vec3 {
+    Vec3::new(-MESSAGE_BUBBLE_WIDTH / 2. + 25., line_y(height, line), 11.6)
+}
+
+fn sender_translation(message: &TextMessage) -> Vec3 {
+    Vec3::new(
+        -MESSAGE_BUBBLE_WIDTH / 2. + 10.,
+        line_y(message.height, message.lines.len()),
+        11.6,
+    )
+}
+
+fn spawn_bubble(
+    commands: &mut Commands,
+    asset_server: &Res<AssetServer>,
+    message_id: (usize, usize),
+    layout: u32,
+    message: &TextMessage,
+    y: f32,
+) {
+    let font = asset_server.load("fonts/monofonto.ttf");
+    let style = message.style;
+    let mut top_edge = None;
+    let mut bottom_edge = None;
+    let mut sender = None;
+    let mut lines = vec![];
+
+    commands
+        .spawn_bundle(SpriteBundle {
+            sprite: Sprite {
+                color: style.colour,
+                custom_size: Some(Vec2::new(MESSAGE_BUBBLE_WIDTH, message.height)),
+                ..Default::default()
+            },
+            transform: Transform {
+                translation: Vec3::new(screen_xpos(), y, 11.),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .with_children(|parent| {
+            // pretty bubble edges, in the bubble's colour
+            top_edge = Some(
+                parent
+                    .spawn_bundle(SpriteBundle {
+                        sprite: Sprite {
+                            color: style.colour,
+                            ..Default::default()
+                        },
+                        texture: asset_server.load("ui/top_bubble.png"),
+                        transform: Transform {
+                            translation: [0., top_edge_y(message.height), 11.5].into(),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    })
+                    .id(),
+            );
+            bottom_edge = Some(
+                parent
+                    .spawn_bundle(SpriteBundle {
+                        sprite: Sprite {
+                            color: style.colour,
+                            ..Default::default()
+                        },
+                        texture: asset_server.load("ui/bottom_bubble.png"),
+                        transform: Transform {
+                            translation: [0., bottom_edge_y(message.height), 11.55].into(),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    })
+                    .id(),
+            );
+
+            for (n, l) in message.lines.iter().rev().enumerate() {
+                lines.push(
+                    parent
+                        .spawn_bundle(Text2dBundle {
+                            text: Text::with_section(
+                                l.clone(),
+                                message_style(&font, style.text_colour),
+                                ALIGN,
+                            ),
+                            transform: Transform {
+                                translation: line_translation(message.height, n),
+                                ..Default::default()
+                            },
+                            ..Default::default()
+                        })
+                        .id(),
+                );
+            }
+
+            sender = Some(
+                parent
+                    .spawn_bundle(Text2dBundle {
+                        text: Text::with_section(
+                            message.sender.clone(),
+                            sender_style(&font, style.text_colour),
+                            ALIGN,
+                        ),
+                        transform: Transform {
+                            translation: sender_translation(message),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    })
+                    .id(),
+            );
+        })
+        .insert(MessageBubble {
+            message: Some(message_id),
+            layout,
+            y,
+            top_edge: top_edge.unwrap(),
+            bottom_edge: bottom_edge.unwrap(),
+            sender: sender.unwrap(),
+            lines,
+        });
+}
+
+// Puts another message in a bubble that's already there. Only needs new entities if the message
+// has more lines than the bubble has ever shown.
+fn fill_bubble(
+    commands: &mut Commands,
+    e: Entity,
+    bubble: &mut MessageBubble,
+    message: &TextMessage,
+    font: &Handle<Font>,
+    parts: &mut BubbleParts,
+) {
+    let style = message.style;
+    if let Ok((mut t, _, Some(mut sprite))) = parts.get_mut(bubble.top_edge) {
+        t.translation.y = top_edge_y(message.height);
+        sprite.color = style.colour;
+    }
+    if let Ok((mut t, _, Some(mut sprite))) = parts.get_mut(bubble.bottom_edge) {
+        t.translation.y = bottom_edge_y(message.height);
+        sprite.color = style.colour;
+    }
+    if let Ok((mut t, Some(mut text), _)) = parts.get_mut(bubble.sender) {
+        t.translation = sender_translation(message);
+        text.sections[0].value = message.sender.clone();
+        text.sections[0].style.color = style.text_colour;
+    }
+
+    let mut wanted = message.lines.iter().rev();
+    for (n, line) in bubble.lines.iter().enumerate() {
+        if let Ok((mut t, Some(mut text), _)) = parts.get_mut(*line) {
+            t.translation = line_translation(message.height, n);
+            // the spares just go blank
+            text.sections[0].value = wanted.next().cloned().unwrap_or_default();
+            text.sections[0].style.color = style.text_colour;
+        }
+    }
+    for l in wanted {
+        let n = bubble.lines.len();
+        let line = commands
+            .spawn_bundle(Text2dBundle {
+                text: Text::with_section(l.clone(), message_style(font, style.text_colour), ALIGN),
+                transform: Transform {
+                    translation: line_translation(message.height, n),
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .id();
+        commands.entity(e).push_children(&[line]);
+        bubble.lines.push(line);
+    }
+}
+
+// Where the newest text message sits on the phone, and how high the oldest can go
+pub fn messages_bottom() -> f32 {
+    (-SCREEN_HEIGHT / 2.) + 190.
+}
+
+pub fn messages_top() -> f32 {
+    (SCREEN_HEIGHT / 2.) - 130.
+}
+
+fn new_messages_ypos() -> f32 {
+    messages_bottom() - 25.
+}
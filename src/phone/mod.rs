@@ -0,0 +1,419 @@
+// The phone down the right hand side of the screen: a home screen, a contacts list, and a thread
+// per contact, plus one for government alerts.
+//
+// Keyboard: Tab / Shift+Tab to pick, Return to open, Backspace or Escape to go back, Home for the
+// home screen. Or click on things, including the phone's home button.
+mod contacts;
+mod messages;
+mod screens;
+
+use bevy::prelude::*;
+
+pub use contacts::{load_contacts, Channel, Contact, CONTACTS_FILE};
+pub use messages::{BubbleStyle, MessageBubble, MessageLog};
+
+use crate::clock::GameClock;
+use crate::events::TextReceived;
+use crate::ui::rhs_width;
+use crate::{SCREEN_HEIGHT, SCREEN_WIDTH};
+use messages::{
+    message_bubble_system, message_scroll_system, message_slide_system, new_messages_indicator,
+    setup_messages,
+};
+use screens::phone_screen_system;
+
+// Every alerts-channel sender shares this thread
+pub const ALERTS_THREAD: usize = 0;
+// How long the banner for a text in another thread stays up
+const NOTIFICATION_TIME: f64 = 3.;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PhoneScreen {
+    Home,
+    Contacts,
+    Thread(usize),
+}
+
+struct Thread {
+    log: MessageLog,
+    // None for the alerts thread
+    contact: Option<usize>,
+    // When the last text came in, counting texts; 0 for never
+    last_heard: u64,
+}
+
+// A text that arrived in a thread that isn't open
+pub struct Notification {
+    pub thread: usize,
+    pub text: String,
+    pub at: f64,
+}
+
+pub struct Phone {
+    screen: PhoneScreen,
+    contacts: Vec<Contact>,
+    // The alerts thread first, then one for each personal contact
+    threads: Vec<Thread>,
+    // Which item on the home screen or contacts list the keyboard is on
+    selected: usize,
+    received: u64,
+    pub notification: Option<Notification>,
+}
+
+impl Default for Phone {
+    fn default() -> Self {
+        Phone::new(vec![])
+    }
+}
+
+impl Phone {
+    pub fn new(contacts: Vec<Contact>) -> Phone {
+        let mut phone = Phone {
+            screen: PhoneScreen::Home,
+            contacts: vec![],
+            threads: vec![Thread {
+                log: MessageLog::default(),
+                contact: None,
+                last_heard: 0,
+            }],
+            selected: 0,
+            received: 0,
+            notification: None,
+        };
+        for c in contacts {
+            phone.add_contact(c);
+        }
+        phone
+    }
+
+    fn add_contact(&mut self, contact: Contact) -> usize {
+        let i = self.contacts.len();
+        if contact.channel == Channel::Personal {
+            self.threads.push(Thread {
+                log: MessageLog::default(),
+                contact: Some(i),
+                last_heard: 0,
+            });
+        }
+        self.contacts.push(contact);
+        i
+    }
+
+    fn contact_index(&mut self, sender: &str) -> usize {
+        match self.contacts.iter().position(|c| c.sender == sender) {
+            Some(i) => i,
+            None => self.add_contact(Contact::unknown(sender)),
+        }
+    }
+
+    fn thread_of(&self, contact: usize) -> usize {
+        match self.contacts[contact].channel {
+            Channel::Alerts => ALERTS_THREAD,
+            Channel::Personal => self
+                .threads
+                .iter()
+                .position(|t| t.contact == Some(contact))
+                .unwrap(),
+        }
+    }
+
+    pub fn receive(&mut self, sender: &str, body: &str, font: Option<&Font>, now: f64) {
+        let c = self.contact_index(sender);
+        let thread = self.thread_of(c);
+        let contact = &self.contacts[c];
+        let (label, style) = match contact.channel {
+            Channel::Personal => (
+                contact.name.clone(),
+                BubbleStyle {
+                    colour: contact.colour,
+                    text_colour: Color::BLACK,
+                },
+            ),
+            Channel::Alerts => (
+                format!("ALERT: {}", contact.name.to_uppercase()),
+                BubbleStyle {
+                    colour: contact.colour,
+                    text_colour: Color::WHITE,
+                },
+            ),
+        };
+
+        let reading = self.screen == PhoneScreen::Thread(thread);
+        self.received += 1;
+        let t = &mut self.threads[thread];
+        t.log.add(&label, style, body, font, now, reading);
+        t.last_heard = self.received;
+        if !reading {
+            self.notification = Some(Notification {
+                thread,
+                text: format!("{}: {}", self.contacts[c].name, body.replace('|', " ")),
+                at: now,
+            });
+        }
+    }
+
+    pub fn screen(&self) -> PhoneScreen {
+        self.screen
+    }
+
+    pub fn open(&mut self, screen: PhoneScreen) {
+        self.screen = screen;
+        self.selected = 0;
+        if let PhoneScreen::Thread(t) = screen {
+            self.threads[t].log.open();
+            if matches!(&self.notification, Some(n) if n.thread == t) {
+                self.notification = None;
+            }
+        }
+    }
+
+    // Where texts from `sender` go, if they've a contact yet
+    pub fn thread_for(&self, sender: &str) -> Option<usize> {
+        self.contacts
+            .iter()
+            .position(|c| c.sender == sender)
+            .map(|c| self.thread_of(c))
+    }
+
+    pub fn open_thread_of(&mut self, sender: &str) {
+        let c = self.contact_index(sender);
+        let thread = self.thread_of(c);
+        self.open(PhoneScreen::Thread(thread));
+    }
+
+    pub fn back(&mut self) {
+        match self.screen {
+            PhoneScreen::Home => {}
+            PhoneScreen::Contacts | PhoneScreen::Thread(ALERTS_THREAD) => {
+                self.open(PhoneScreen::Home)
+            }
+            PhoneScreen::Thread(_) => self.open(PhoneScreen::Contacts),
+        }
+    }
+
+    // The thread on screen, if it's on a thread
+    pub fn open_log(&self) -> Option<(usize, &MessageLog)> {
+        match self.screen {
+            PhoneScreen::Thread(t) => Some((t, &self.threads[t].log)),
+            _ => None,
+        }
+    }
+
+    pub fn open_log_mut(&mut self) -> Option<&mut MessageLog> {
+        match self.screen {
+            PhoneScreen::Thread(t) => Some(&mut self.threads[t].log),
+            _ => None,
+        }
+    }
+
+    pub fn log(&self, thread: usize) -> &MessageLog {
+        &self.threads[thread].log
+    }
+
+    // Whether any texts are still wrapped with the estimate, waiting on the font
+    pub fn estimated(&self) -> bool {
+        self.threads.iter().any(|t| t.log.estimated())
+    }
+
+    pub fn relayout(&mut self, font: &Font) {
+        for t in self.threads.iter_mut().filter(|t| t.log.estimated()) {
+            t.log.relayout(font);
+        }
+    }
+
+    pub fn thread_contact(&self, thread: usize) -> Option<&Contact> {
+        self.threads[thread].contact.map(|c| &self.contacts[c])
+    }
+
+    pub fn thread_title(&self, thread: usize) -> &str {
+        match self.thread_contact(thread) {
+            Some(c) => &c.name,
+            None => "Alerts",
+        }
+    }
+
+    // Unread across every personal thread, for the badge on the home screen
+    pub fn unread_personal(&self) -> usize {
+        self.threads
+            .iter()
+            .filter(|t| t.contact.is_some())
+            .map(|t| t.log.unread)
+            .sum()
+    }
+
+    // Personal threads, most recently heard from first
+    pub fn contact_threads(&self) -> Vec<usize> {
+        let mut rv: Vec<usize> = (0..self.threads.len())
+            .filter(|t| self.threads[*t].contact.is_some())
+            .collect();
+        rv.sort_by_key(|t| std::cmp::Reverse(self.threads[*t].last_heard));
+        rv
+    }
+
+    // What the keyboard can pick from on this screen
+    pub fn items(&self) -> Vec<PhoneScreen> {
+        match self.screen {
+            PhoneScreen::Home => vec![PhoneScreen::Contacts, PhoneScreen::Thread(ALERTS_THREAD)],
+            PhoneScreen::Contacts => self
+                .contact_threads()
+                .into_iter()
+                .map(PhoneScreen::Thread)
+                .collect(),
+            PhoneScreen::Thread(_) => vec![],
+        }
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    pub fn select_next(&mut self, by: isize) {
+        let n = self.items().len() as isize;
+        if n > 0 {
+            self.selected = (self.selected as isize + by).rem_euclid(n) as usize;
+        }
+    }
+
+    pub fn open_selected(&mut self) {
+        if let Some(screen) = self.items().get(self.selected) {
+            self.open(*screen);
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PhoneAction {
+    Home,
+    Back,
+    Open(PhoneScreen),
+    ScrollToNewest,
+}
+
+// Something on the phone that does `action` when clicked
+#[derive(Component)]
+pub struct PhoneButton {
+    pub action: PhoneAction,
+    pub size: Vec2,
+}
+
+pub struct PhonePlugin;
+
+impl Plugin for PhonePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Phone>()
+            .add_startup_system(setup_phone)
+            .add_startup_system(setup_messages)
+            .add_system(text_received_system.label("phone"))
+            .add_system(phone_keys_system.label("phone"))
+            .add_system(phone_click_system.label("phone"))
+            .add_system(message_scroll_system.label("phone"))
+            .add_system(notification_system.label("phone"))
+            .add_system(message_bubble_system.label("bubbles").after("phone"))
+            .add_system(message_slide_system.after("bubbles"))
+            .add_system(new_messages_indicator.after("phone"))
+            .add_system(phone_screen_system.after("phone"));
+    }
+}
+
+pub fn setup_phone(mut commands: Commands, mut phone: ResMut<Phone>) {
+    *phone = Phone::new(load_contacts(CONTACTS_FILE));
+
+    // the home button drawn on the phone
+    commands
+        .spawn_bundle((
+            Transform::from_xyz(screen_xpos() - 10., -396., 31.),
+            GlobalTransform::default(),
+        ))
+        .insert(PhoneButton {
+            action: PhoneAction::Home,
+            size: Vec2::new(60., 60.),
+        });
+}
+
+pub fn text_received_system(
+    mut text_events: EventReader<TextReceived>,
+    mut phone: ResMut<Phone>,
+    time: Res<GameClock>,
+    fonts: Res<Assets<Font>>,
+    asset_server: Res<AssetServer>,
+) {
+    let font = fonts.get(asset_server.load::<Font, _>("fonts/monofonto.ttf"));
+    // Anything that came in before the font did gets wrapped again now it's here
+    if let Some(font) = font {
+        if phone.estimated() {
+            phone.relayout(font);
+        }
+    }
+    for e in text_events.iter() {
+        phone.receive(&e.sender, &e.body, font, time.seconds_since_startup());
+    }
+}
+
+pub fn phone_keys_system(keys: Res<Input<KeyCode>>, mut phone: ResMut<Phone>) {
+    if keys.just_pressed(KeyCode::Tab) {
+        let shift = keys.pressed(KeyCode::LShift) || keys.pressed(KeyCode::RShift);
+        phone.select_next(if shift { -1 } else { 1 });
+    }
+    if keys.just_pressed(KeyCode::Return) {
+        phone.open_selected();
+    }
+    if keys.just_pressed(KeyCode::Back) || keys.just_pressed(KeyCode::Escape) {
+        phone.back();
+    }
+    if keys.just_pressed(KeyCode::Home) && phone.screen() != PhoneScreen::Home {
+        phone.open(PhoneScreen::Home);
+    }
+}
+
+pub fn phone_click_system(
+    buttons: Res<Input<MouseButton>>,
+    windows: Option<Res<Windows>>,
+    query: Query<(&PhoneButton, &Transform, Option<&Visibility>)>,
+    mut phone: ResMut<Phone>,
+) {
+    if !buttons.just_pressed(MouseButton::Left) {
+        return;
+    }
+    let cursor = match windows.and_then(|w| w.get_primary().and_then(|w| w.cursor_position())) {
+        // the window's origin is bottom left, ours is the middle
+        Some(p) => Vec2::new(p.x - SCREEN_WIDTH / 2., p.y - SCREEN_HEIGHT / 2.),
+        None => return,
+    };
+
+    // whatever's on top, if things overlap
+    let clicked = query
+        .iter()
+        .filter(|(button, t, v)| {
+            v.map(|v| v.is_visible).unwrap_or(true)
+                && f32::abs(cursor.x - t.translation.x) < button.size.x / 2.
+                && f32::abs(cursor.y - t.translation.y) < button.size.y / 2.
+        })
+        .max_by(|(_, a, _), (_, b, _)| a.translation.z.total_cmp(&b.translation.z));
+    if let Some((button, _, _)) = clicked {
+        match button.action {
+            PhoneAction::Home => phone.open(PhoneScreen::Home),
+            PhoneAction::Back => phone.back(),
+            PhoneAction::Open(screen) => phone.open(screen),
+            PhoneAction::ScrollToNewest => {
+                if let Some(log) = phone.open_log_mut() {
+                    log.scroll_to_newest();
+                }
+            }
+        }
+    }
+}
+
+pub fn notification_system(mut phone: ResMut<Phone>, time: Res<GameClock>) {
+    let expired = match &phone.notification {
+        Some(n) => time.seconds_since_startup() - n.at > NOTIFICATION_TIME,
+        None => false,
+    };
+    if expired {
+        phone.notification = None;
+    }
+}
+
+// The middle of the phone's screen, left to right
+pub fn screen_xpos() -> f32 {
+    (SCREEN_WIDTH / 2.) - (rhs_width() / 2.)
+}
@@ -0,0 +1,346 @@
+// Drawing the home screen, the contacts list, the header along the top, and the banner for texts in
+// other threads. There are only ever a handful of these, so they're all redrawn whenever the phone
+// changes.
+use bevy::prelude::*;
+
+use super::messages::messages_top;
+use super::{screen_xpos, Phone, PhoneAction, PhoneButton, PhoneScreen, ALERTS_THREAD};
+use crate::SCREEN_HEIGHT;
+
+const SCREEN_INNER_WIDTH: f32 = 260.;
+const HEADER_YPOS: f32 = 415.;
+const HEADER_HEIGHT: f32 = 54.;
+const APP_ICON_SIZE: f32 = 100.;
+const CONTACT_ROW_HEIGHT: f32 = 64.;
+const AVATAR_SIZE: f32 = 48.;
+
+const MESSAGES_APP_COLOUR: Color = Color::rgb(0.2, 0.55, 0.3);
+const ALERTS_APP_COLOUR: Color = Color::rgb(0.65, 0.11, 0.11);
+const ALERTS_HEADER_COLOUR: Color = Color::rgb(0.15, 0.15, 0.2);
+const BADGE_COLOUR: Color = Color::rgb(0.85, 0.1, 0.1);
+const SELECTED_COLOUR: Color = Color::rgb(0.88, 0.88, 0.88);
+const PREVIEW_COLOUR: Color = Color::rgb(0.4, 0.4, 0.4);
+
+// Everything that gets thrown away when the phone is redrawn
+#[derive(Component)]
+pub struct PhoneScreenTag {}
+
+pub fn phone_screen_system(
+    mut commands: Commands,
+    phone: Res<Phone>,
+    asset_server: Res<AssetServer>,
+    old: Query<Entity, With<PhoneScreenTag>>,
+) {
+    if !phone.is_changed() {
+        return;
+    }
+    for e in old.iter() {
+        commands.entity(e).despawn_recursive();
+    }
+
+    let font = asset_server.load("fonts/monofonto.ttf");
+    let mut draw = Draw {
+        commands: &mut commands,
+        font: &font,
+    };
+    match phone.screen() {
+        PhoneScreen::Home => draw_home(&mut draw, &phone),
+        PhoneScreen::Contacts => draw_contacts(&mut draw, &phone, &asset_server),
+        PhoneScreen::Thread(t) => draw_thread_header(&mut draw, &phone, t, &asset_server),
+    }
+
+    if let Some(n) = &phone.notification {
+        let (colour, text_colour) = match phone.thread_contact(n.thread) {
+            Some(c) => (c.colour, Color::BLACK),
+            None => (ALERTS_HEADER_COLOUR, Color::WHITE),
+        };
+        let size = Vec2::new(SCREEN_INNER_WIDTH, HEADER_HEIGHT);
+        let banner = draw.rect([screen_xpos(), HEADER_YPOS, 28.], size, colour);
+        draw.commands.entity(banner).insert(PhoneButton {
+            action: PhoneAction::Open(PhoneScreen::Thread(n.thread)),
+            size,
+        });
+        draw.text(
+            [screen_xpos(), HEADER_YPOS, 29.],
+            &truncate(&n.text, 30),
+            18.,
+            text_colour,
+            HorizontalAlign::Center,
+        );
+    }
+}
+
+struct Draw<'a, 'w, 's> {
+    commands: &'a mut Commands<'w, 's>,
+    font: &'a Handle<Font>,
+}
+
+impl<'a, 'w, 's> Draw<'a, 'w, 's> {
+    fn rect(&mut self, at: [f32; 3], size: Vec2, colour: Color) -> Entity {
+        self.commands
+            .spawn_bundle(SpriteBundle {
+                sprite: Sprite {
+                    color: colour,
+                    custom_size: Some(size),
+                    ..Default::default()
+                },
+                transform: Transform {
+                    translation: at.into(),
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .insert(PhoneScreenTag {})
+            .id()
+    }
+
+    fn image(&mut self, at: [f32; 3], size: f32, texture: Handle<Image>) {
+        self.commands
+            .spawn_bundle(SpriteBundle {
+                sprite: Sprite {
+                    custom_size: Some(Vec2::new(size, size)),
+                    ..Default::default()
+                },
+                texture,
+                transform: Transform {
+                    translation: at.into(),
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .insert(PhoneScreenTag {});
+    }
+
+    fn text(
+        &mut self,
+        at: [f32; 3],
+        text: &str,
+        size: f32,
+        colour: Color,
+        horizontal: HorizontalAlign,
+    ) {
+        self.commands
+            .spawn_bundle(Text2dBundle {
+                text: Text::with_section(
+                    text,
+                    TextStyle {
+                        font: self.font.clone(),
+                        font_size: size,
+                        color: colour,
+                    },
+                    TextAlignment {
+                        vertical: VerticalAlign::Center,
+                        horizontal,
+                    },
+                ),
+                transform: Transform {
+                    translation: at.into(),
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .insert(PhoneScreenTag {});
+    }
+
+    // A red blob with the number in it, unless there's nothing unread
+    fn badge(&mut self, x: f32, y: f32, z: f32, unread: usize) {
+        if unread == 0 {
+            return;
+        }
+        self.rect([x, y, z], Vec2::new(28., 28.), BADGE_COLOUR);
+        let n = if unread > 99 {
+            String::from("99+")
+        } else {
+            unread.to_string()
+        };
+        self.text(
+            [x, y, z + 0.1],
+            &n,
+            18.,
+            Color::WHITE,
+            HorizontalAlign::Center,
+        );
+    }
+
+    fn header(&mut self, title: &str, colour: Color, text_colour: Color, back: bool) {
+        // goes all the way up, so it also hides any bubbles scrolled up past the top
+        let bottom = HEADER_YPOS - HEADER_HEIGHT / 2.;
+        let top = SCREEN_HEIGHT / 2.;
+        self.rect(
+            [screen_xpos(), (top + bottom) / 2., 20.],
+            Vec2::new(SCREEN_INNER_WIDTH + 20., top - bottom),
+            colour,
+        );
+        self.rect(
+            [screen_xpos(), HEADER_YPOS - HEADER_HEIGHT / 2., 20.5],
+            Vec2::new(SCREEN_INNER_WIDTH, 2.),
+            PREVIEW_COLOUR,
+        );
+        self.text(
+            [screen_xpos(), HEADER_YPOS, 21.],
+            &truncate(title, 16),
+            24.,
+            text_colour,
+            HorizontalAlign::Center,
+        );
+        if back {
+            let at = [
+                screen_xpos() - SCREEN_INNER_WIDTH / 2. + 20.,
+                HEADER_YPOS,
+                21.,
+            ];
+            self.text(at, "<", 28., text_colour, HorizontalAlign::Center);
+            self.commands
+                .spawn_bundle((
+                    Transform::from_translation(at.into()),
+                    GlobalTransform::default(),
+                ))
+                .insert(PhoneScreenTag {})
+                .insert(PhoneButton {
+                    action: PhoneAction::Back,
+                    size: Vec2::new(50., HEADER_HEIGHT),
+                });
+        }
+    }
+}
+
+fn draw_home(draw: &mut Draw, phone: &Phone) {
+    draw.header("Home", Color::WHITE, Color::BLACK, false);
+
+    let apps = [
+        (
+            "Messages",
+            MESSAGES_APP_COLOUR,
+            phone.unread_personal(),
+            PhoneScreen::Contacts,
+        ),
+        (
+            "Alerts",
+            ALERTS_APP_COLOUR,
+            phone.log(ALERTS_THREAD).unread,
+            PhoneScreen::Thread(ALERTS_THREAD),
+        ),
+    ];
+    let y = messages_top() - APP_ICON_SIZE / 2. - 20.;
+    for (i, (name, colour, unread, screen)) in apps.iter().enumerate() {
+        let x = screen_xpos() + (i as f32 - 0.5) * (APP_ICON_SIZE + 30.);
+        if i == phone.selected() {
+            draw.rect(
+                [x, y, 12.],
+                Vec2::new(APP_ICON_SIZE + 12., APP_ICON_SIZE + 12.),
+                Color::rgb(0.2, 0.2, 0.2),
+            );
+        }
+        let icon = draw.rect(
+            [x, y, 13.],
+            Vec2::new(APP_ICON_SIZE, APP_ICON_SIZE),
+            *colour,
+        );
+        draw.commands.entity(icon).insert(PhoneButton {
+            action: PhoneAction::Open(*screen),
+            size: Vec2::new(APP_ICON_SIZE, APP_ICON_SIZE),
+        });
+        draw.text(
+            [x, y, 13.5],
+            name,
+            20.,
+            Color::WHITE,
+            HorizontalAlign::Center,
+        );
+        let corner = APP_ICON_SIZE / 2. - 6.;
+        draw.badge(x + corner, y + corner, 14., *unread);
+    }
+}
+
+fn draw_contacts(draw: &mut Draw, phone: &Phone, asset_server: &Res<AssetServer>) {
+    draw.header("Messages", Color::WHITE, Color::BLACK, true);
+
+    let left = screen_xpos() - SCREEN_INNER_WIDTH / 2.;
+    for (row, thread) in phone.contact_threads().into_iter().enumerate() {
+        let contact = phone.thread_contact(thread).unwrap();
+        let log = phone.log(thread);
+        let y = messages_top() - CONTACT_ROW_HEIGHT * (row as f32 + 0.5);
+
+        let size = Vec2::new(SCREEN_INNER_WIDTH, CONTACT_ROW_HEIGHT - 4.);
+        let background = if row == phone.selected() {
+            SELECTED_COLOUR
+        } else {
+            Color::WHITE
+        };
+        let e = draw.rect([screen_xpos(), y, 12.], size, background);
+        draw.commands.entity(e).insert(PhoneButton {
+            action: PhoneAction::Open(PhoneScreen::Thread(thread)),
+            size,
+        });
+
+        let avatar_x = left + 6. + AVATAR_SIZE / 2.;
+        match &contact.avatar {
+            Some(path) => draw.image(
+                [avatar_x, y, 13.],
+                AVATAR_SIZE,
+                asset_server.load(path.as_str()),
+            ),
+            None => {
+                // no picture: their initial on their colour
+                draw.rect(
+                    [avatar_x, y, 13.],
+                    Vec2::new(AVATAR_SIZE, AVATAR_SIZE),
+                    contact.colour,
+                );
+                let initial: String = contact.name.chars().take(1).collect();
+                draw.text(
+                    [avatar_x, y, 13.5],
+                    &initial,
+                    28.,
+                    Color::BLACK,
+                    HorizontalAlign::Center,
+                );
+            }
+        }
+
+        let text_x = left + AVATAR_SIZE + 16.;
+        draw.text(
+            [text_x, y + 11., 13.],
+            &truncate(&contact.name, 16),
+            22.,
+            Color::BLACK,
+            HorizontalAlign::Left,
+        );
+        draw.text(
+            [text_x, y - 13., 13.],
+            &truncate(log.preview().unwrap_or("No messages"), 20),
+            16.,
+            PREVIEW_COLOUR,
+            HorizontalAlign::Left,
+        );
+        draw.badge(left + SCREEN_INNER_WIDTH - 20., y, 14., log.unread);
+    }
+}
+
+fn draw_thread_header(
+    draw: &mut Draw,
+    phone: &Phone,
+    thread: usize,
+    asset_server: &Res<AssetServer>,
+) {
+    let title = phone.thread_title(thread);
+    match phone.thread_contact(thread) {
+        Some(contact) => {
+            draw.header(title, Color::WHITE, Color::BLACK, true);
+            if let Some(path) = &contact.avatar {
+                let x = screen_xpos() + SCREEN_INNER_WIDTH / 2. - 22.;
+                draw.image([x, HEADER_YPOS, 21.], 36., asset_server.load(path.as_str()));
+            }
+        }
+        None => draw.header(title, ALERTS_HEADER_COLOUR, Color::WHITE, true),
+    }
+}
+
+// Cuts `s` down to `n` characters, with ... on the end if it's been cut
+fn truncate(s: &str, n: usize) -> String {
+    if s.chars().count() <= n {
+        return String::from(s);
+    }
+    let cut: String = s.chars().take(n - 3).collect();
+    format!("{}...", cut.trim_end())
+}
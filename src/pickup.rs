@@ -2,28 +2,35 @@ use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
 
 use crate::{
-    environment::tile_coords_to_screen_pos,
-    game::GameState,
-    narrative::NarrativeActions,
-    player::Player,
-    sfx::{SFXSystem, SoundEffect},
-    TILE_SIZE,
+    environment::tile_coords_to_screen_pos, events::PickupCollected, narrative::NarrativeActions,
+    player::Player, TILE_SIZE,
 };
 
 #[derive(Component, Debug, Clone)]
 pub enum Pickup {
     Potplant,
+    Tv,
+    Mask,
+    Sanitiser,
+    KeepApart,
+    Rat,
+    Vaccine,
+}
+
+pub struct PickupPlugin;
+
+impl Plugin for PickupPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(pickup_system);
+    }
 }
 
 pub fn pickup_system(
     mut commands: Commands,
     narrow_phase: Res<NarrowPhase>,
     pickup_query: Query<(Entity, &Pickup, &NarrativeActions)>,
-    time: Res<Time>,
-    asset_server: Res<AssetServer>,
-    mut game_state: ResMut<GameState>,
-    player_query: Query<(Entity, &Player, &Transform)>,
-    mut sfx_system: ResMut<SFXSystem>,
+    player_query: Query<Entity, With<Player>>,
+    mut pickup_events: EventWriter<PickupCollected>,
 ) {
     // For each pickup - ask did someone collide with us?
     for (pickup_entity, pickup, narrative_actions) in pickup_query.iter() {
@@ -37,19 +44,13 @@ pub fn pickup_system(
             };
             if intersecting {
                 collect_pickup(pickup, pickup_entity, collector.entity(), &mut commands);
-                let (player_entity, _, player_transform) = player_query.single();
+                let player_entity = player_query.single();
 
                 if collector.entity() == player_entity {
-                    sfx_system.play_sfx(SoundEffect::Pickup);
-
-                    game_state.do_narrative_actions(
-                        narrative_actions.clone(),
-                        &time,
-                        &mut commands,
-                        &asset_server,
-                        player_transform,
-                        &mut sfx_system,
-                    );
+                    pickup_events.send(PickupCollected {
+                        pickup: pickup.clone(),
+                        actions: narrative_actions.clone(),
+                    });
                 }
             }
         }
@@ -97,13 +98,22 @@ pub fn spawn_pickup(
 
 fn get_dimensions(pickup: &Pickup) -> (f32, f32) {
     match pickup {
-        Pickup::Potplant => (3., 3.),
+        Pickup::Potplant | Pickup::Tv => (3., 3.),
+        Pickup::Mask | Pickup::Sanitiser | Pickup::KeepApart | Pickup::Rat | Pickup::Vaccine => {
+            (1., 1.)
+        }
     }
 }
 
 fn get_image(pickup: &Pickup, asset_server: &AssetServer) -> Handle<Image> {
     let path = match pickup {
         Pickup::Potplant => "potplant.png",
+        Pickup::Tv => "tv.png",
+        Pickup::Mask => "mask.png",
+        Pickup::Sanitiser => "sanitiser.png",
+        Pickup::KeepApart => "keep_apart.png",
+        Pickup::Rat => "rat.png",
+        Pickup::Vaccine => "vaccine.png",
     };
     asset_server.load(path)
 }
@@ -2,11 +2,21 @@ use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
 use bevy_rapier2d::rapier::na::Vector2;
 
+use crate::protection::MaskTag;
 use crate::TILE_SIZE;
 
 pub static SPRITE_SIZE_X: f32 = 100.0;
 pub static SPRITE_SIZE_Y: f32 = 150.0;
 
+pub struct PlayerPlugin;
+
+impl Plugin for PlayerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(setup_player)
+            .add_system(player_movement);
+    }
+}
+
 #[derive(Component)]
 pub struct Player {
     pub speed: f32,
@@ -71,5 +81,16 @@ pub fn setup_player(mut commands: Commands, asset_server: Res<AssetServer>) {
             ..Default::default()
         })
         .insert(ColliderPositionSync::Discrete)
-        .insert(ColliderDebugRender::with_id(1));
+        .insert(ColliderDebugRender::with_id(1))
+        .with_children(|parent| {
+            // Lined up with the face, and only shown while wearing one
+            parent
+                .spawn_bundle(SpriteBundle {
+                    texture: asset_server.load("mask_on.png"),
+                    transform: Transform::from_xyz(0., 0., 0.1),
+                    visibility: Visibility { is_visible: false },
+                    ..Default::default()
+                })
+                .insert(MaskTag {});
+        });
 }
@@ -0,0 +1,408 @@
+// Who's out and about. Each public place has its regulars, some there every day and some only at
+// the weekend, each about at their own time of day, and fewer of them turn out while places are
+// locked down or there's a lot of covid around. A game day only lasts a few seconds, so the hours
+// are taken a quarter of a day at a time rather than one by one. The player walks in on a crowd
+// the right size for the time, then people come and go through the entrances to keep it that way.
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use rand::prelude::*;
+
+use crate::behaviour::Behaviour;
+use crate::clock::GameClock;
+use crate::environment::{
+    environment_colliders, teleporter_tiles, Environment, Location, GRID_SIZE,
+};
+use crate::epidemic::Epidemic;
+use crate::events::LocationChanged;
+use crate::game::{GameState, DAY_LENGTH};
+use crate::npc::{spawn_npc, NPC};
+use crate::pathfinding::{position_tile, Destination, NavGrid, Path};
+use crate::player::Player;
+use crate::rng::GameRng;
+
+// The game starts mid-morning rather than at midnight
+const DAY_START_HOUR: f32 = 8.;
+// Seconds between one person coming or going and the next
+pub const TRICKLE_SECONDS: f64 = 2.;
+// How much of the usual crowd turns out with nothing, one place and two places locked down
+const LOCKDOWN_FACTORS: [f32; 3] = [1., 0.5, 0.2];
+// Each percent of people infectious keeps another 4% at home, up to three quarters of them
+const PREVALENCE_AVOIDANCE: f32 = 4.;
+const MAX_AVOIDANCE: f32 = 0.75;
+// In tiles. Nobody already there is standing right by the player, or in a doorway.
+const ARRIVAL_CLEARANCE: usize = 4;
+// How long someone gets to find their way out before they just go. Steering round a corner can
+// leave them circling the last few tiles.
+const LEAVING_SECONDS: f64 = 20.;
+
+// Came in through an entrance, and will leave through one
+#[derive(Component)]
+pub struct Visitor {}
+
+// On their way out
+#[derive(Component)]
+pub struct Leaving {
+    gone_by: f64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Population {
+    // When someone next comes or goes
+    next_trickle: f64,
+    // Where the player is, and who should be there with them
+    location: Option<Location>,
+    crowd: Vec<(Behaviour, usize)>,
+    staff: Vec<[usize; 2]>,
+}
+
+// Visitors who aren't on their way out yet
+type Staying<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static Behaviour,
+        &'static RigidBodyPositionComponent,
+    ),
+    (With<Visitor>, Without<Leaving>),
+>;
+
+// Everyone on their way out, and whether they've still got somewhere to get to
+type Leavers<'w, 's> =
+    Query<'w, 's, (Entity, &'static Leaving, Option<&'static Destination>), With<NPC>>;
+
+pub struct PopulationPlugin;
+
+impl Plugin for PopulationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Population>()
+            .add_system(
+                population_target_system
+                    .label("population target")
+                    .after("teleport"),
+            )
+            .add_system(population_arrival_system.after("population target"))
+            .add_system(
+                population_system
+                    .after("population target")
+                    .after("pathfinding"),
+            )
+            .add_system(departure_system);
+    }
+}
+
+// Day 1 is a Monday
+fn weekend(date: i32) -> bool {
+    date % 7 == 6 || date % 7 == 0
+}
+
+// 0 to 24, with a whole day every DAY_LENGTH seconds
+pub fn hour_of_day(now: f64) -> f32 {
+    let day = (now / DAY_LENGTH).fract() as f32;
+    (DAY_START_HOUR + 24. * day) % 24.
+}
+
+// Six hours at a time
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeOfDay {
+    Night,
+    Morning,
+    Afternoon,
+    Evening,
+}
+
+impl TimeOfDay {
+    pub fn at(hour: f32) -> Self {
+        match hour as u32 {
+            6..=11 => TimeOfDay::Morning,
+            12..=17 => TimeOfDay::Afternoon,
+            18..=23 => TimeOfDay::Evening,
+            _ => TimeOfDay::Night,
+        }
+    }
+}
+
+const DAYTIME: &[TimeOfDay] = &[TimeOfDay::Morning, TimeOfDay::Afternoon];
+const WAKING_HOURS: &[TimeOfDay] = &[TimeOfDay::Morning, TimeOfDay::Afternoon, TimeOfDay::Evening];
+
+// Which days someone's about
+#[derive(Debug, Clone, Copy)]
+enum Days {
+    Every,
+    Weekdays,
+    Weekends,
+}
+
+impl Days {
+    fn include(&self, date: i32) -> bool {
+        match self {
+            Days::Every => true,
+            Days::Weekdays => !weekend(date),
+            Days::Weekends => weekend(date),
+        }
+    }
+}
+
+// Who a place gets with nothing keeping them away, and the days and times they're about
+fn regulars(location: Location) -> Vec<(Behaviour, f32, Days, &'static [TimeOfDay])> {
+    match location {
+        Location::Park => vec![
+            (Behaviour::Wanderer, 3., Days::Every, DAYTIME),
+            // Everyone else with the day off
+            (
+                Behaviour::Wanderer,
+                2.,
+                Days::Weekends,
+                &[TimeOfDay::Afternoon],
+            ),
+            // Before and after work
+            (
+                Behaviour::jogger(),
+                2.,
+                Days::Every,
+                &[TimeOfDay::Morning, TimeOfDay::Evening],
+            ),
+            (Behaviour::DogWalker, 2., Days::Every, WAKING_HOURS),
+        ],
+        Location::Shops => vec![
+            (Behaviour::shopper(), 3., Days::Every, WAKING_HOURS),
+            // The weekly shop
+            (Behaviour::shopper(), 2., Days::Weekends, DAYTIME),
+        ],
+        // Waiting for their jab
+        Location::Clinic => vec![(Behaviour::Wanderer, 2., Days::Weekdays, DAYTIME)],
+        Location::Home => vec![],
+    }
+}
+
+fn same_kind(a: &Behaviour, b: &Behaviour) -> bool {
+    std::mem::discriminant(a) == std::mem::discriminant(b)
+}
+
+// How many of each kind of visitor `location` should have. Kinds that shouldn't be there at all
+// are left out.
+pub fn target_population(
+    location: Location,
+    date: i32,
+    hour: f32,
+    lockdown_level: usize,
+    prevalence: f64,
+) -> Vec<(Behaviour, usize)> {
+    let factor = LOCKDOWN_FACTORS[lockdown_level.min(LOCKDOWN_FACTORS.len() - 1)]
+        * (1. - (prevalence as f32 * PREVALENCE_AVOIDANCE).min(MAX_AVOIDANCE));
+
+    let mut totals: Vec<(Behaviour, f32)> = vec![];
+    let time = TimeOfDay::at(hour);
+    for (behaviour, peak, days, times) in regulars(location) {
+        if !days.include(date) || !times.contains(&time) {
+            continue;
+        }
+        match totals.iter_mut().find(|(b, _)| same_kind(b, &behaviour)) {
+            Some((_, total)) => *total += peak * factor,
+            None => totals.push((behaviour, peak * factor)),
+        }
+    }
+    totals
+        .into_iter()
+        .map(|(b, n)| (b, n.round() as usize))
+        .filter(|(_, n)| *n > 0)
+        .collect()
+}
+
+// Where the staff stand, if they're working
+fn staff_posts(location: Location, hour: f32) -> Vec<[usize; 2]> {
+    match location {
+        // On the staffed checkout, until they close for the night
+        Location::Shops if TimeOfDay::at(hour) != TimeOfDay::Night => vec![[1, 4]],
+        // Staffing the booths
+        Location::Clinic => vec![[15, 8]],
+        _ => vec![],
+    }
+}
+
+// Just inside each way in
+pub fn entrances(location: Location) -> Vec<[usize; 2]> {
+    match location {
+        // From home, the shops and the clinic
+        Location::Park => vec![[2, 2], [16, 14], [10, 2]],
+        Location::Shops => vec![[1, 10], [1, 16]],
+        Location::Clinic => vec![[9, 16]],
+        Location::Home => vec![],
+    }
+}
+
+fn spawn_visitor(
+    commands: &mut Commands,
+    asset_server: &Res<AssetServer>,
+    tile: [usize; 2],
+    behaviour: Behaviour,
+) {
+    let npc = spawn_npc(commands, asset_server, tile, behaviour);
+    commands.entity(npc).insert(Visitor {});
+}
+
+// Keeps track of who should be wherever the player is, as the day goes by and things change
+pub fn population_target_system(
+    mut population: ResMut<Population>,
+    environment_query: Query<&Environment>,
+    state: Res<GameState>,
+    epidemic: Res<Epidemic>,
+    time: Res<GameClock>,
+) {
+    let hour = hour_of_day(time.seconds_since_startup());
+    let location = match environment_query.get_single() {
+        Ok(e) => e.location,
+        Err(_) => return,
+    };
+    population.location = Some(location);
+    population.crowd = target_population(
+        location,
+        state.date,
+        hour,
+        state.area_access.lockdown_level(),
+        epidemic.prevalence(),
+    );
+    population.staff = staff_posts(location, hour);
+}
+
+// Anywhere walkable at least ARRIVAL_CLEARANCE tiles from everything in `keep_clear`
+fn clear_tiles(grid: &NavGrid, keep_clear: &[[usize; 2]]) -> Vec<[usize; 2]> {
+    (0..GRID_SIZE)
+        .flat_map(|x| (0..GRID_SIZE).map(move |y| [x, y]))
+        .filter(|t| grid.walkable(*t))
+        .filter(|t| {
+            keep_clear
+                .iter()
+                .all(|k| tiles_apart(*t, *k) >= ARRIVAL_CLEARANCE)
+        })
+        .collect()
+}
+
+// Fills a place with however many people should be there when the player walks in
+pub fn population_arrival_system(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut location_events: EventReader<LocationChanged>,
+    mut population: ResMut<Population>,
+    player_query: Query<&RigidBodyPositionComponent, With<Player>>,
+    time: Res<GameClock>,
+    mut rng: ResMut<GameRng>,
+) {
+    for e in location_events.iter() {
+        population.next_trickle = time.seconds_since_startup() + TRICKLE_SECONDS;
+        if population.location != Some(e.to) {
+            continue;
+        }
+
+        for post in &population.staff {
+            spawn_npc(&mut commands, &asset_server, *post, Behaviour::Stationary);
+        }
+
+        // The resource won't have caught up with the move yet
+        let grid = NavGrid::new(&environment_colliders(e.to));
+        let mut keep_clear = teleporter_tiles(e.to);
+        if let Ok(p) = player_query.get_single() {
+            keep_clear.push(position_tile(p.position.translation.vector));
+        }
+        let tiles = clear_tiles(&grid, &keep_clear);
+        if tiles.is_empty() {
+            continue;
+        }
+        for (behaviour, n) in &population.crowd {
+            for _ in 0..*n {
+                let tile = tiles[rng.gen_range(0..tiles.len())];
+                spawn_visitor(&mut commands, &asset_server, tile, *behaviour);
+            }
+        }
+    }
+}
+
+// Every so often, lets someone in if the place is short of their kind, or sends someone home if
+// there are too many
+pub fn population_system(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut population: ResMut<Population>,
+    visitor_query: Staying,
+    mut npc_query: Query<&mut NPC>,
+    time: Res<GameClock>,
+    mut rng: ResMut<GameRng>,
+) {
+    let now = time.seconds_since_startup();
+    if now < population.next_trickle {
+        return;
+    }
+    population.next_trickle = now + TRICKLE_SECONDS;
+
+    let ways_in = match population.location {
+        Some(location) => entrances(location),
+        None => return,
+    };
+    if ways_in.is_empty() {
+        return;
+    }
+    let target = &population.crowd;
+    let present = |behaviour: &Behaviour| {
+        visitor_query
+            .iter()
+            .filter(|(_, b, _)| same_kind(b, behaviour))
+            .count()
+    };
+
+    if let Some((behaviour, _)) = target.iter().find(|(b, n)| present(b) < *n) {
+        let entrance = ways_in[rng.gen_range(0..ways_in.len())];
+        spawn_visitor(&mut commands, &asset_server, entrance, *behaviour);
+        return;
+    }
+
+    let wanted = |behaviour: &Behaviour| {
+        target
+            .iter()
+            .find(|(b, _)| same_kind(b, behaviour))
+            .map(|(_, n)| *n)
+            .unwrap_or(0)
+    };
+    // Whoever's nearest a way out goes first
+    let leaver = visitor_query
+        .iter()
+        .filter(|(_, b, _)| present(b) > wanted(b))
+        .map(|(e, _, p)| {
+            let tile = position_tile(p.position.translation.vector);
+            let (d, exit) = ways_in
+                .iter()
+                .map(|x| (tiles_apart(tile, *x), *x))
+                .min()
+                .unwrap();
+            (d, e, exit)
+        })
+        .min_by_key(|(d, _, _)| *d);
+    if let Some((_, entity, exit)) = leaver {
+        if let Ok(mut npc) = npc_query.get_mut(entity) {
+            // Done browsing or sniffing about, though not mid-conversation
+            if npc.paused_until.is_finite() {
+                npc.paused_until = now;
+            }
+        }
+        commands
+            .entity(entity)
+            .insert(Leaving {
+                gone_by: now + LEAVING_SECONDS,
+            })
+            .remove::<Path>()
+            .insert(Destination::Tile(exit));
+    }
+}
+
+fn tiles_apart(a: [usize; 2], b: [usize; 2]) -> usize {
+    (a[0] as isize - b[0] as isize).unsigned_abs() + (a[1] as isize - b[1] as isize).unsigned_abs()
+}
+
+// Gone, once they're out the door, can't find the way or have taken too long about it
+pub fn departure_system(mut commands: Commands, leaving_query: Leavers, time: Res<GameClock>) {
+    let now = time.seconds_since_startup();
+    for (e, leaving, destination) in leaving_query.iter() {
+        if destination.is_none() || now >= leaving.gone_by {
+            commands.entity(e).despawn();
+        }
+    }
+}
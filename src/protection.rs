@@ -0,0 +1,72 @@
+// Masks, hand sanitiser and keeping 1.5m apart. Each one cuts the covid dose for a while after the
+// player picks it up, or after the narrative hands it out.
+use bevy::prelude::*;
+
+use crate::clock::GameClock;
+use crate::game::GameState;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Protection {
+    Mask,
+    Sanitiser,
+    KeepApart,
+}
+
+impl Protection {
+    fn index(self) -> usize {
+        self as usize
+    }
+
+    // In seconds
+    pub fn duration(self) -> f64 {
+        match self {
+            Protection::Mask => 120.,
+            Protection::Sanitiser => 45.,
+            Protection::KeepApart => 90.,
+        }
+    }
+}
+
+// When each protection runs out
+#[derive(Debug, Clone, Default)]
+pub struct Protections {
+    until: [f64; 3],
+}
+
+impl Protections {
+    // Starts it again from its full duration, even if it hadn't run out yet
+    pub fn start(&mut self, p: Protection, now: f64) {
+        self.until[p.index()] = now + p.duration();
+    }
+
+    pub fn active(&self, p: Protection, now: f64) -> bool {
+        now < self.until[p.index()]
+    }
+}
+
+// The mask drawn over the player's face
+#[derive(Component)]
+pub struct MaskTag {}
+
+pub struct ProtectionPlugin;
+
+impl Plugin for ProtectionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(mask_system);
+    }
+}
+
+pub fn mask_system(
+    mut query: Query<&mut Visibility, With<MaskTag>>,
+    state: Res<GameState>,
+    time: Res<GameClock>,
+) {
+    let wearing = state
+        .protections
+        .active(Protection::Mask, time.seconds_since_startup());
+    for mut v in query.iter_mut() {
+        if v.is_visible != wearing {
+            v.is_visible = wearing;
+        }
+    }
+}
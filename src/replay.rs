@@ -0,0 +1,262 @@
+// Recording the keyboard, one row per change, and feeding a recording back in.
+//
+// With a fixed clock and a seeded rng the game is deterministic, so the seed and the keys held
+// down on every frame are all it takes to play a run again:
+//   melsim --record run.csv
+//   melsim --replay run.csv
+use bevy::input::InputSystem;
+use bevy::prelude::*;
+use std::fs::File;
+use std::str::FromStr;
+
+use crate::clock::GameClock;
+use crate::launch_option;
+use crate::rng::GameRng;
+use crate::stats::ProfileFile;
+
+// Recordings always run on a fixed clock, at this rate unless the recording says otherwise
+pub const RECORDING_STEP: f64 = 1. / 60.;
+
+// Everything the game reads off the keyboard: movement, and using things
+const RECORDED_KEYS: [(KeyCode, &str); 11] = [
+    (KeyCode::W, "W"),
+    (KeyCode::A, "A"),
+    (KeyCode::S, "S"),
+    (KeyCode::D, "D"),
+    (KeyCode::Up, "Up"),
+    (KeyCode::Down, "Down"),
+    (KeyCode::Left, "Left"),
+    (KeyCode::Right, "Right"),
+    (KeyCode::E, "E"),
+    (KeyCode::T, "T"),
+    (KeyCode::F, "F"),
+];
+
+// One bit per entry in RECORDED_KEYS
+type KeyMask = u32;
+
+fn pressed_mask(input: &Input<KeyCode>) -> KeyMask {
+    let mut mask = 0;
+    for (i, (key, _)) in RECORDED_KEYS.iter().enumerate() {
+        if input.pressed(*key) {
+            mask |= 1 << i;
+        }
+    }
+    mask
+}
+
+fn mask2str(mask: KeyMask) -> String {
+    let names: Vec<&str> = RECORDED_KEYS
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| mask & (1 << i) != 0)
+        .map(|(_, (_, name))| *name)
+        .collect();
+    names.join(";")
+}
+
+fn str2mask(s: &str) -> KeyMask {
+    let mut mask = 0;
+    for name in s.split(';').filter(|n| !n.is_empty()) {
+        match RECORDED_KEYS.iter().position(|(_, n)| *n == name) {
+            Some(i) => mask |= 1 << i,
+            None => panic!("bad key in recording: {}", name),
+        }
+    }
+    mask
+}
+
+#[derive(Debug, Clone)]
+pub struct InputRecording {
+    pub seed: u64,
+    pub step: f64,
+    // (frame, keys held from that frame on), in frame order
+    changes: Vec<(u64, KeyMask)>,
+}
+
+impl InputRecording {
+    pub fn load(path: &str) -> Result<InputRecording, Box<dyn std::error::Error>> {
+        let mut rv = InputRecording {
+            seed: 0,
+            step: RECORDING_STEP,
+            changes: vec![],
+        };
+        let mut rdr = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .from_path(path)?;
+        for x in rdr.records() {
+            let x = x?;
+            let (kind, a, b) = (
+                x.get(0).unwrap_or(""),
+                x.get(1).unwrap_or(""),
+                x.get(2).unwrap_or(""),
+            );
+            match kind {
+                "seed" => rv.seed = u64::from_str(a)?,
+                "step" => rv.step = f64::from_str(a)?,
+                "keys" => rv.changes.push((u64::from_str(a)?, str2mask(b))),
+                _ => println!("unknown recording row: {:?}", x),
+            }
+        }
+        Ok(rv)
+    }
+
+    // The frame of the last change, i.e. there's nothing new after this
+    pub fn last_frame(&self) -> u64 {
+        self.changes.last().map(|(frame, _)| *frame).unwrap_or(0)
+    }
+}
+
+// Writes the keys out as they change, so the recording survives the window being closed
+pub struct InputRecorder {
+    writer: csv::Writer<File>,
+    frame: u64,
+    last: Option<KeyMask>,
+}
+
+impl InputRecorder {
+    pub fn create(
+        path: &str,
+        seed: u64,
+        step: f64,
+    ) -> Result<InputRecorder, Box<dyn std::error::Error>> {
+        let mut writer = csv::WriterBuilder::new().flexible(true).from_path(path)?;
+        writer.write_record(["seed", seed.to_string().as_str()])?;
+        writer.write_record(["step", step.to_string().as_str()])?;
+        writer.flush()?;
+        Ok(InputRecorder {
+            writer,
+            frame: 0,
+            last: None,
+        })
+    }
+
+    fn record(&mut self, mask: KeyMask) -> Result<(), Box<dyn std::error::Error>> {
+        if self.last != Some(mask) {
+            self.writer.write_record([
+                "keys",
+                self.frame.to_string().as_str(),
+                mask2str(mask).as_str(),
+            ])?;
+            self.writer.flush()?;
+            self.last = Some(mask);
+        }
+        self.frame += 1;
+        Ok(())
+    }
+}
+
+pub struct InputReplayer {
+    recording: InputRecording,
+    frame: u64,
+    next_change: usize,
+    current: KeyMask,
+}
+
+impl InputReplayer {
+    pub fn new(recording: InputRecording) -> Self {
+        InputReplayer {
+            recording,
+            frame: 0,
+            next_change: 0,
+            current: 0,
+        }
+    }
+
+    pub fn finished(&self) -> bool {
+        self.frame > self.recording.last_frame()
+    }
+
+    fn advance(&mut self) -> KeyMask {
+        while let Some((frame, mask)) = self.recording.changes.get(self.next_change) {
+            if *frame > self.frame {
+                break;
+            }
+            self.current = *mask;
+            self.next_change += 1;
+        }
+        self.frame += 1;
+        self.current
+    }
+}
+
+// Sets up the rng and the clock (and recording or replaying, if asked for) from the command line
+pub fn insert_from_args(app: &mut App) {
+    if let Some(path) = launch_option("--replay") {
+        let recording = match InputRecording::load(&path) {
+            Ok(recording) => recording,
+            Err(e) => panic!("couldn't load recording {}: {}", path, e),
+        };
+        println!("Replaying {}", path);
+        app.insert_resource(GameRng::from_seed(recording.seed))
+            .insert_resource(GameClock::fixed(recording.step))
+            .insert_resource(InputReplayer::new(recording))
+            // Watching a run back doesn't count as playing it again
+            .insert_resource(ProfileFile(None));
+        return;
+    }
+
+    let rng = GameRng::from_args();
+    if let Some(path) = launch_option("--record") {
+        match InputRecorder::create(&path, rng.seed(), RECORDING_STEP) {
+            Ok(recorder) => {
+                println!("Recording to {}", path);
+                app.insert_resource(GameClock::fixed(RECORDING_STEP))
+                    .insert_resource(recorder);
+            }
+            Err(e) => println!("couldn't record to {}: {}", path, e),
+        }
+    }
+    app.insert_resource(rng);
+}
+
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_to_stage(
+            CoreStage::PreUpdate,
+            replay_system.label("replay").after(InputSystem),
+        )
+        .add_system_to_stage(
+            CoreStage::PreUpdate,
+            record_system
+                .label("record")
+                .after(InputSystem)
+                .after("replay"),
+        );
+    }
+}
+
+// Overrides whatever the keyboard says with what's in the recording
+fn replay_system(replayer: Option<ResMut<InputReplayer>>, mut input: ResMut<Input<KeyCode>>) {
+    let mut replayer = match replayer {
+        Some(replayer) => replayer,
+        None => return,
+    };
+    if replayer.finished() {
+        return;
+    }
+    let mask = replayer.advance();
+    if replayer.finished() {
+        println!("Replay finished, the keyboard is yours again");
+    }
+
+    for (i, (key, _)) in RECORDED_KEYS.iter().enumerate() {
+        let want = mask & (1 << i) != 0;
+        if want && !input.pressed(*key) {
+            input.press(*key);
+        } else if !want && input.pressed(*key) {
+            input.release(*key);
+        }
+    }
+}
+
+fn record_system(recorder: Option<ResMut<InputRecorder>>, input: Res<Input<KeyCode>>) {
+    if let Some(mut recorder) = recorder {
+        if let Err(e) = recorder.record(pressed_mask(&input)) {
+            println!("couldn't write recording: {}", e);
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use bevy::ecs::archetype::ArchetypeGeneration;
+use bevy::ecs::schedule::{ParallelSystemContainer, ParallelSystemExecutor};
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::{Error, RngCore, SeedableRng};
+use std::str::FromStr;
+
+use crate::launch_option;
+
+// The one source of randomness in the game. Every random decision draws from here, so a run can
+// be reproduced exactly from its seed and inputs.
+pub struct GameRng {
+    seed: u64,
+    rng: StdRng,
+}
+
+impl GameRng {
+    pub fn from_seed(seed: u64) -> Self {
+        GameRng {
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    // `--seed 1234` on the command line; a fresh seed every run otherwise
+    pub fn from_args() -> Self {
+        let seed = launch_option("--seed").map(|s| match u64::from_str(&s) {
+            Ok(seed) => seed,
+            Err(_) => panic!("bad seed: {}", s),
+        });
+        match seed {
+            Some(seed) => GameRng::from_seed(seed),
+            None => GameRng::default(),
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+impl Default for GameRng {
+    fn default() -> Self {
+        GameRng::from_seed(rand::random())
+    }
+}
+
+// So that everything on `rand::Rng` (gen, gen_range...) works on it
+impl RngCore for GameRng {
+    fn next_u32(&mut self) -> u32 {
+        self.rng.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.rng.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.rng.try_fill_bytes(dest)
+    }
+}
+
+pub struct RngPlugin;
+
+impl Plugin for RngPlugin {
+    fn build(&self, app: &mut App) {
+        // Keeps a seeded one that was put there before us
+        app.init_resource::<GameRng>()
+            .add_startup_system(print_seed);
+        // Everything that's been added by now, i.e. bevy's stages and the physics
+        in_order(&mut app.schedule);
+    }
+}
+
+// Runs a stage's systems one at a time, in an order that's the same every run. Bevy otherwise
+// orders systems that don't say what they come before or after differently from one run to the
+// next, which changes who draws from the rng first, and when the physics steps.
+#[derive(Default)]
+pub struct OrderedExecutor {
+    order: Vec<usize>,
+    archetype_generation: Option<ArchetypeGeneration>,
+}
+
+impl ParallelSystemExecutor for OrderedExecutor {
+    fn rebuild_cached_data(&mut self, systems: &[ParallelSystemContainer]) {
+        // Whatever's free to run next goes in name order
+        self.order.clear();
+        let mut done = vec![false; systems.len()];
+        while self.order.len() < systems.len() {
+            let next = (0..systems.len())
+                .filter(|i| !done[*i])
+                .filter(|i| systems[*i].dependencies().iter().all(|d| done[*d]))
+                .min_by_key(|i| (systems[*i].name(), *i))
+                .expect("systems depend on each other");
+            done[next] = true;
+            self.order.push(next);
+        }
+    }
+
+    fn run_systems(&mut self, systems: &mut [ParallelSystemContainer], world: &mut World) {
+        let archetypes = world.archetypes();
+        let seen = self.archetype_generation.map(|g| g.value()).unwrap_or(0);
+        for archetype in archetypes.iter().skip(seen) {
+            for container in systems.iter_mut() {
+                container.system_mut().new_archetype(archetype);
+            }
+        }
+        self.archetype_generation = Some(archetypes.generation());
+
+        for i in &self.order {
+            if systems[*i].should_run() {
+                systems[*i].system_mut().run((), world);
+            }
+        }
+        // Spawns and inserts land in the same order too, so queries go through things in it
+        for i in &self.order {
+            if systems[*i].should_run() {
+                systems[*i].system_mut().apply_buffers(world);
+            }
+        }
+    }
+}
+
+fn in_order(schedule: &mut Schedule) {
+    let labels: Vec<_> = schedule
+        .iter_stages()
+        .map(|(label, _)| label.dyn_clone())
+        .collect();
+    for label in labels {
+        if let Some(stage) = schedule.get_stage_mut::<SystemStage>(&*label) {
+            stage.set_executor(Box::new(OrderedExecutor::default()));
+        } else if let Some(startup) = schedule.get_stage_mut::<Schedule>(&*label) {
+            in_order(startup);
+        }
+    }
+}
+
+fn print_seed(rng: Res<GameRng>) {
+    println!(
+        "Seed: {} (run with --seed {} to play this run again)",
+        rng.seed(),
+        rng.seed()
+    );
+}
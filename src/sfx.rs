@@ -1,6 +1,9 @@
 use bevy::prelude::*;
 use bevy_kira_audio::{Audio, AudioChannel, AudioSource};
 
+use crate::events::{LocationChanged, NeedRestored, PickupCollected, SanityChanged, TextReceived};
+use crate::needs::Need;
+
 #[derive(Default, Clone, Debug)]
 pub struct SFXSystem {
     pub text: Handle<AudioSource>,
@@ -44,6 +47,41 @@ pub fn setup_sfx(
     audio.set_volume_in_channel(0.1, &sfx_system.channel);
 }
 
+// Picks the sound for everything that happens elsewhere in the game
+pub fn sfx_event_system(
+    mut sanity_events: EventReader<SanityChanged>,
+    mut text_events: EventReader<TextReceived>,
+    mut pickup_events: EventReader<PickupCollected>,
+    mut location_events: EventReader<LocationChanged>,
+    mut need_events: EventReader<NeedRestored>,
+    mut sfx_system: ResMut<SFXSystem>,
+) {
+    for e in sanity_events.iter() {
+        if e.delta > 0 {
+            sfx_system.play_sfx(SoundEffect::SanityUp);
+        } else {
+            sfx_system.play_sfx(SoundEffect::SanityDown);
+        }
+    }
+    for _ in text_events.iter() {
+        sfx_system.play_sfx(SoundEffect::Text);
+    }
+    for _ in pickup_events.iter() {
+        sfx_system.play_sfx(SoundEffect::Pickup);
+    }
+    for _ in location_events.iter() {
+        sfx_system.play_sfx(SoundEffect::EntranceExit);
+    }
+    for e in need_events.iter() {
+        // a phone call sounds like a phone
+        if e.need == Need::Social {
+            sfx_system.play_sfx(SoundEffect::Text);
+        } else {
+            sfx_system.play_sfx(SoundEffect::Pickup);
+        }
+    }
+}
+
 pub fn sfx_system(audio: Res<Audio>, mut sfx_system: ResMut<SFXSystem>) {
     let mut effects = sfx_system.pending_sfx.drain(..).collect::<Vec<_>>();
 
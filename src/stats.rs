@@ -0,0 +1,393 @@
+use bevy::prelude::*;
+use std::collections::{BTreeMap, BTreeSet};
+use std::str::FromStr;
+
+use crate::clock::GameClock;
+use crate::environment::{Environment, Location, TV};
+use crate::events::{
+    CovidExposure, LocationChanged, NeedRestored, PickupCollected, SanityChanged, TextReceived,
+};
+use crate::game::GameState;
+use crate::rng::GameRng;
+use crate::ui;
+
+// Lives next to the narrative, i.e. relative to the working directory
+pub const PROFILE_FILE: &str = "./profile.csv";
+
+// Where finished runs are saved, if anywhere. Tests and replays leave the player's own alone.
+#[derive(Debug, Clone)]
+pub struct ProfileFile(pub Option<String>);
+
+impl Default for ProfileFile {
+    fn default() -> Self {
+        ProfileFile(Some(String::from(PROFILE_FILE)))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Achievement {
+    NeverLeftHome,
+    ParkOnDayOne,
+    WatchedBuffyTwice,
+    SurvivedAFortnight,
+    NoCloseContacts,
+}
+
+pub const ALL_ACHIEVEMENTS: [Achievement; 5] = [
+    Achievement::NeverLeftHome,
+    Achievement::ParkOnDayOne,
+    Achievement::WatchedBuffyTwice,
+    Achievement::SurvivedAFortnight,
+    Achievement::NoCloseContacts,
+];
+
+impl Achievement {
+    pub fn title(self) -> &'static str {
+        match self {
+            Achievement::NeverLeftHome => "Never left home",
+            Achievement::ParkOnDayOne => "Went to the park on day 1",
+            Achievement::WatchedBuffyTwice => "Watched Buffy twice",
+            Achievement::SurvivedAFortnight => "Survived a fortnight",
+            Achievement::NoCloseContacts => "Kept your distance",
+        }
+    }
+
+    fn from_title(s: &str) -> Option<Self> {
+        ALL_ACHIEVEMENTS.iter().copied().find(|a| a.title() == s)
+    }
+}
+
+// Everything we remember about the current run
+#[derive(Debug, Clone, Default)]
+pub struct RunStats {
+    pub days_survived: i32,
+    pub lowest_sanity: Option<i32>,
+    // Indexed by location_index()
+    pub time_in_location: [f64; 4],
+    pub teleports: u32,
+    pub pickups_collected: u32,
+    pub tv_watched: u32,
+    pub close_contacts: u32,
+    pub texts_received: BTreeMap<String, u32>,
+    pub park_on_day_one: bool,
+}
+
+fn location_index(location: Location) -> usize {
+    match location {
+        Location::Home => 0,
+        Location::Park => 1,
+        Location::Shops => 2,
+        Location::Clinic => 3,
+    }
+}
+
+impl RunStats {
+    pub fn record_time(&mut self, location: Location, dt: f64, date: i32) {
+        self.time_in_location[location_index(location)] += dt;
+        self.days_survived = i32::max(self.days_survived, date);
+        if location == Location::Park && date == 1 {
+            self.park_on_day_one = true;
+        }
+    }
+
+    pub fn record_sanity(&mut self, sanity: i32) {
+        self.lowest_sanity = Some(match self.lowest_sanity {
+            Some(lowest) => i32::min(lowest, sanity),
+            None => sanity,
+        });
+    }
+
+    pub fn record_teleport(&mut self) {
+        self.teleports += 1;
+    }
+
+    pub fn record_pickup(&mut self) {
+        self.pickups_collected += 1;
+    }
+
+    // Only sitting down in front of the TV counts, not the narrative putting one in the way
+    pub fn record_need_restored(&mut self, from: &str) {
+        if from == TV {
+            self.tv_watched += 1;
+        }
+    }
+
+    pub fn record_close_contact(&mut self) {
+        self.close_contacts += 1;
+    }
+
+    pub fn record_text(&mut self, sender: &str) {
+        *self.texts_received.entry(String::from(sender)).or_insert(0) += 1;
+    }
+
+    pub fn time_in(&self, location: Location) -> f64 {
+        self.time_in_location[location_index(location)]
+    }
+
+    pub fn achievements(&self) -> Vec<Achievement> {
+        let mut rv = vec![];
+        if self.time_in(Location::Park) == 0.
+            && self.time_in(Location::Shops) == 0.
+            && self.time_in(Location::Clinic) == 0.
+        {
+            rv.push(Achievement::NeverLeftHome);
+        }
+        if self.park_on_day_one {
+            rv.push(Achievement::ParkOnDayOne);
+        }
+        if self.tv_watched >= 2 {
+            rv.push(Achievement::WatchedBuffyTwice);
+        }
+        if self.days_survived >= 14 {
+            rv.push(Achievement::SurvivedAFortnight);
+        }
+        if self.close_contacts == 0 && self.days_survived >= 7 {
+            rv.push(Achievement::NoCloseContacts);
+        }
+        rv
+    }
+
+    pub fn summary_lines(&self) -> Vec<String> {
+        let mut rv = vec![
+            format!("Days survived: {}", self.days_survived),
+            format!("Lowest sanity: {}", self.lowest_sanity.unwrap_or(0)),
+            format!(
+                "Home {:.0}s / Park {:.0}s / Shops {:.0}s / Clinic {:.0}s",
+                self.time_in(Location::Home),
+                self.time_in(Location::Park),
+                self.time_in(Location::Shops),
+                self.time_in(Location::Clinic)
+            ),
+            format!(
+                "Trips: {}  Pickups: {}  Close contacts: {}",
+                self.teleports, self.pickups_collected, self.close_contacts
+            ),
+        ];
+        let texts: Vec<String> = self
+            .texts_received
+            .iter()
+            .map(|(sender, count)| format!("{} {}", sender, count))
+            .collect();
+        if !texts.is_empty() {
+            rv.push(format!("Texts: {}", texts.join(", ")));
+        }
+        rv
+    }
+}
+
+pub struct StatsPlugin;
+
+impl Plugin for StatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RunStats>()
+            .init_resource::<ProfileFile>()
+            .add_system(stats_system)
+            .add_system(finish_run_system);
+    }
+}
+
+pub fn stats_system(
+    mut stats: ResMut<RunStats>,
+    state: Res<GameState>,
+    time: Res<GameClock>,
+    environment_query: Query<&Environment>,
+    mut sanity_events: EventReader<SanityChanged>,
+    mut text_events: EventReader<TextReceived>,
+    mut location_events: EventReader<LocationChanged>,
+    mut pickup_events: EventReader<PickupCollected>,
+    mut exposure_events: EventReader<CovidExposure>,
+    mut need_events: EventReader<NeedRestored>,
+) {
+    if state.run_over() {
+        return;
+    }
+
+    let environment = environment_query.single();
+    stats.record_time(environment.location, time.delta_seconds_f64(), state.date);
+
+    for e in sanity_events.iter() {
+        stats.record_sanity(e.sanity);
+    }
+    for e in text_events.iter() {
+        stats.record_text(&e.sender);
+    }
+    for _ in location_events.iter() {
+        stats.record_teleport();
+    }
+    for _ in pickup_events.iter() {
+        stats.record_pickup();
+    }
+    for _ in exposure_events.iter() {
+        stats.record_close_contact();
+    }
+    for e in need_events.iter() {
+        stats.record_need_restored(e.from);
+    }
+}
+
+// Saves the run into the profile and puts the summary on screen, once the run is over
+pub fn finish_run_system(
+    mut commands: Commands,
+    state: Res<GameState>,
+    stats: Res<RunStats>,
+    profile_file: Res<ProfileFile>,
+    rng: Res<GameRng>,
+    asset_server: Res<AssetServer>,
+    mut finished: Local<bool>,
+) {
+    if *finished || !state.run_over() {
+        return;
+    }
+    *finished = true;
+
+    let mut profile = match &profile_file.0 {
+        Some(path) => Profile::load(path),
+        None => Profile::default(),
+    };
+    let new_achievements = profile.record_run(&stats);
+    if let Some(path) = &profile_file.0 {
+        profile.save(path);
+    }
+
+    let mut lines = stats.summary_lines();
+    for a in stats.achievements() {
+        let new = if new_achievements.contains(&a) {
+            " (new!)"
+        } else {
+            ""
+        };
+        lines.push(format!("* {}{}", a.title(), new));
+    }
+    lines.extend(profile.summary_lines());
+    // Enough to play the same run again
+    lines.push(format!("Seed: {}", rng.seed()));
+    ui::spawn_run_summary(
+        lines,
+        &mut commands,
+        asset_server.load("fonts/monofonto.ttf"),
+    );
+}
+
+// Totals over every run, saved between runs
+#[derive(Debug, Clone, Default)]
+pub struct Profile {
+    pub runs: u32,
+    pub best_days_survived: i32,
+    pub total_days_survived: i32,
+    pub total_close_contacts: u32,
+    pub texts_received: BTreeMap<String, u32>,
+    pub achievements: BTreeSet<Achievement>,
+}
+
+impl Profile {
+    // A missing or unreadable profile just means a fresh start
+    pub fn load(path: &str) -> Profile {
+        let mut rv = Profile::default();
+        let mut rdr = match csv::ReaderBuilder::new().flexible(true).from_path(path) {
+            Ok(rdr) => rdr,
+            Err(_) => return rv,
+        };
+
+        for x in rdr.records() {
+            let x = match x {
+                Ok(x) => x,
+                Err(e) => {
+                    println!("skipping bad profile row: {}", e);
+                    continue;
+                }
+            };
+            let (kind, key, value) = (
+                x.get(0).unwrap_or(""),
+                x.get(1).unwrap_or(""),
+                x.get(2).unwrap_or(""),
+            );
+            match (kind, key) {
+                ("stat", "runs") => rv.runs = u32::from_str(value).unwrap_or(0),
+                ("stat", "best_days_survived") => {
+                    rv.best_days_survived = i32::from_str(value).unwrap_or(0)
+                }
+                ("stat", "total_days_survived") => {
+                    rv.total_days_survived = i32::from_str(value).unwrap_or(0)
+                }
+                ("stat", "total_close_contacts") => {
+                    rv.total_close_contacts = u32::from_str(value).unwrap_or(0)
+                }
+                ("texts", sender) => {
+                    rv.texts_received
+                        .insert(String::from(sender), u32::from_str(value).unwrap_or(0));
+                }
+                ("achievement", title) => {
+                    if let Some(a) = Achievement::from_title(title) {
+                        rv.achievements.insert(a);
+                    }
+                }
+                _ => println!("unknown profile row: {:?}", x),
+            }
+        }
+        rv
+    }
+
+    pub fn save(&self, path: &str) {
+        if let Err(e) = self.write(path) {
+            println!("couldn't save profile: {}", e);
+        }
+    }
+
+    fn write(&self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let mut wtr = csv::Writer::from_path(path)?;
+        wtr.write_record(["kind", "key", "value"])?;
+        wtr.write_record(["stat", "runs", self.runs.to_string().as_str()])?;
+        wtr.write_record([
+            "stat",
+            "best_days_survived",
+            self.best_days_survived.to_string().as_str(),
+        ])?;
+        wtr.write_record([
+            "stat",
+            "total_days_survived",
+            self.total_days_survived.to_string().as_str(),
+        ])?;
+        wtr.write_record([
+            "stat",
+            "total_close_contacts",
+            self.total_close_contacts.to_string().as_str(),
+        ])?;
+        for (sender, count) in &self.texts_received {
+            wtr.write_record(["texts", sender.as_str(), count.to_string().as_str()])?;
+        }
+        for a in &self.achievements {
+            wtr.write_record(["achievement", a.title(), ""])?;
+        }
+        wtr.flush()?;
+        Ok(())
+    }
+
+    // Folds a finished run into the profile. Returns the achievements earned for the first time.
+    pub fn record_run(&mut self, run: &RunStats) -> Vec<Achievement> {
+        self.runs += 1;
+        self.best_days_survived = i32::max(self.best_days_survived, run.days_survived);
+        self.total_days_survived += run.days_survived;
+        self.total_close_contacts += run.close_contacts;
+        for (sender, count) in &run.texts_received {
+            *self.texts_received.entry(sender.clone()).or_insert(0) += count;
+        }
+
+        let mut new_achievements = vec![];
+        for a in run.achievements() {
+            if self.achievements.insert(a) {
+                new_achievements.push(a);
+            }
+        }
+        new_achievements
+    }
+
+    pub fn summary_lines(&self) -> Vec<String> {
+        vec![format!(
+            "Runs: {}  Best: {} days  Achievements: {}/{}",
+            self.runs,
+            self.best_days_survived,
+            self.achievements.len(),
+            ALL_ACHIEVEMENTS.len()
+        )]
+    }
+}
@@ -2,10 +2,10 @@ use crate::{
     environment::{
         create_environment, tile_coords_to_screen_pos, Environment, EnvironmentCollider,
     },
-    music::MusicState,
-    npc::{spawn_npc, NPC},
+    events::{CovidExposure, IsolationBreached, LocationChanged},
+    game::GameState,
+    npc::NPC,
     pickup::Pickup,
-    sfx::{SFXSystem, SoundEffect},
     TILE_SIZE,
 };
 use bevy::prelude::*;
@@ -36,13 +36,16 @@ pub fn teleportation_system(
     teleporter_query: Query<(Entity, &Teleporter)>,
     mut environment_query: Query<(&mut TextureAtlasSprite, &mut Environment)>,
     environment_collider_query: Query<Entity, With<EnvironmentCollider>>,
-    mut music_state: ResMut<MusicState>,
-    asset_server: Res<AssetServer>,
-    mut sfx_system: ResMut<SFXSystem>,
     npc_query: Query<(Entity, &NPC)>,
     pickup_query: Query<(Entity, &Pickup)>,
+    mut location_events: EventWriter<LocationChanged>,
+    state: Res<GameState>,
+    mut breach_events: EventWriter<IsolationBreached>,
+    // Whether the player was already standing on a locked door last frame, so it's only said once
+    mut refused: Local<bool>,
 ) {
     let (player_entity, mut player_position) = player_info.single_mut();
+    let mut refused_now = false;
 
     // For each teleporter ask - has the player collided with us?
     for (teleporter_entity, teleporter) in teleporter_query.iter() {
@@ -50,35 +53,94 @@ pub fn teleportation_system(
             narrow_phase.intersections_with(teleporter_entity.handle())
         {
             if collider_a.entity() == player_entity || collider_b.entity() == player_entity {
-                sfx_system.play_sfx(SoundEffect::EntranceExit);
                 if intersecting {
-                    for (entity, _) in npc_query.iter() {
-                        commands.entity(entity).despawn();
+                    let destination = teleporter.destination;
+                    if !state.area_access.unlocked(destination) {
+                        // Locked by the narrative, so the door stays shut, isolating or not
+                        if !*refused {
+                            println!("{:?} is locked", destination);
+                        }
+                        refused_now = true;
+                        continue;
                     }
-                    for (entity, _) in pickup_query.iter() {
-                        commands.entity(entity).despawn();
+                    if !state.area_access.can_access(destination) {
+                        // Nothing physically stops someone who's isolating
+                        breach_events.send(IsolationBreached { to: destination });
                     }
-
-                    if teleporter.destination == Location::Park {
-                        spawn_npc(&mut commands, &asset_server, [5, 14]);
-                    }
-                    if teleporter.destination == Location::Shops {
-                        spawn_npc(&mut commands, &asset_server, [6, 14]);
-                    }
-
+                    clear_location(&mut commands, &npc_query, &pickup_query);
                     teleport(
                         teleporter,
                         &mut player_position,
                         &mut environment_query,
                         &mut commands,
                         &environment_collider_query,
-                        &mut music_state,
-                        &asset_server,
+                        &mut location_events,
                     );
                 }
             }
         }
     }
+    *refused = refused_now;
+}
+
+// A close contact gets sent straight home
+pub fn covid_teleport_system(
+    mut commands: Commands,
+    mut exposure_events: EventReader<CovidExposure>,
+    mut player_info: Query<&mut RigidBodyPositionComponent, With<Player>>,
+    mut environment_query: Query<(&mut TextureAtlasSprite, &mut Environment)>,
+    environment_collider_query: Query<Entity, With<EnvironmentCollider>>,
+    npc_query: Query<(Entity, &NPC)>,
+    pickup_query: Query<(Entity, &Pickup)>,
+    mut location_events: EventWriter<LocationChanged>,
+    // A close contact still waiting to be sent home
+    mut pending: Local<bool>,
+) {
+    if exposure_events.iter().count() > 0 {
+        *pending = true;
+    }
+    if !*pending {
+        return;
+    }
+    // The player has just gone through a door, and the new room's walls only turn up at the end of
+    // the frame: send them home next frame instead, so only one lot gets swapped at a time
+    if environment_query.iter_mut().any(|(_, e)| e.is_changed()) {
+        return;
+    }
+    *pending = false;
+    // Already home, so there's nowhere to send them
+    if environment_query
+        .iter()
+        .any(|(_, e)| e.location == Location::Home)
+    {
+        return;
+    }
+
+    let mut player_position = player_info.single_mut();
+    let teleporter = Teleporter::new(Location::Home, [5, 5]);
+    clear_location(&mut commands, &npc_query, &pickup_query);
+    teleport(
+        &teleporter,
+        &mut player_position,
+        &mut environment_query,
+        &mut commands,
+        &environment_collider_query,
+        &mut location_events,
+    );
+}
+
+// Nobody and nothing follows the player to the next location
+pub fn clear_location(
+    commands: &mut Commands,
+    npc_query: &Query<(Entity, &NPC)>,
+    pickup_query: &Query<(Entity, &Pickup)>,
+) {
+    for (entity, _) in npc_query.iter() {
+        commands.entity(entity).despawn();
+    }
+    for (entity, _) in pickup_query.iter() {
+        commands.entity(entity).despawn();
+    }
 }
 
 pub fn teleport(
@@ -87,8 +149,7 @@ pub fn teleport(
     environment_query: &mut Query<(&mut TextureAtlasSprite, &mut Environment)>,
     commands: &mut Commands,
     environment_collider_query: &Query<Entity, With<EnvironmentCollider>>,
-    music_state: &mut ResMut<MusicState>,
-    asset_server: &Res<AssetServer>,
+    location_events: &mut EventWriter<LocationChanged>,
 ) {
     let destination = teleporter.destination;
     // First, despawn the current environment
@@ -97,13 +158,7 @@ pub fn teleport(
     }
 
     // Then create the new environment
-    create_environment(destination, commands, music_state);
-    match destination {
-        Location::Park => {
-            // spawn_npc(commands, asset_server);
-        }
-        _ => {}
-    };
+    create_environment(destination, commands);
 
     // Change the sprite
     let (mut sprite, mut environment) = environment_query.single_mut();
@@ -111,11 +166,16 @@ pub fn teleport(
         Location::Home => 0,
         Location::Park => 1,
         Location::Shops => 2,
+        Location::Clinic => 3,
     };
 
     // Then move the player
     player_position.position.translation = teleporter.new_player_location;
     println!("Moving player to {:?}", teleporter.new_player_location);
+    location_events.send(LocationChanged {
+        from: environment.location,
+        to: destination,
+    });
     environment.location = destination;
 }
 
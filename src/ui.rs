@@ -1,4 +1,8 @@
 use crate::{game::*, SCREEN_WIDTH, SCREEN_HEIGHT};
+use crate::clock::GameClock;
+use crate::events::{CovidExposure, SanityChanged};
+use crate::player::Player;
+use crate::needs::{Need, ALL_NEEDS, NEED_MAX};
 use bevy::prelude::*;
 
 #[derive(Component)]
@@ -11,6 +15,12 @@ pub struct SanityBarTag {}
 #[derive(Component)]
 pub struct SanityCoveringTag {}
 
+#[derive(Component)]
+pub struct NeedMeterTag {
+    need: Need,
+    base_y: f32,
+}
+
 #[derive(Component)]
 pub struct CovidRiskElement {
     min_risk: f32,
@@ -24,12 +34,6 @@ pub struct SanityNumberTween {
     base_y: f32,
 }
 
-#[derive(Component)]
-pub struct TextMessageTag {
-    pub bottom_from: f32,
-    pub bottom_to: f32,
-}
-
 #[derive(Component)]
 pub struct CovidTransitionUiTag {
     pub time_left: f32,
@@ -37,10 +41,26 @@ pub struct CovidTransitionUiTag {
 
 pub const TRANSITION_LENGTH: f32 = 3.;
 
+pub struct UiPlugin;
+
+impl Plugin for UiPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(setup_ui)
+            .add_system(update)
+            .add_system(update_sanity_bar_covering)
+            .add_system(update_covid_risk)
+            .add_system(update_need_meters)
+            .add_system(sanity_number_tween)
+            .add_system(covid_transition_ui)
+            .add_system(sanity_number_system)
+            .add_system(covid_alert_system);
+    }
+}
+
 pub fn covid_transition_ui(
     mut commands: Commands,
     mut query: Query<(&mut CovidTransitionUiTag, &mut Transform, Entity)>,
-    time: Res<Time>,
+    time: Res<GameClock>,
 ) {
     for (mut ctt, mut tx, e) in query.iter_mut() {
         ctt.time_left -= time.delta_seconds();
@@ -55,6 +75,47 @@ pub fn covid_transition_ui(
     }
 }
 
+// Every change in sanity pops up over the player
+pub fn sanity_number_system(
+    mut commands: Commands,
+    mut sanity_events: EventReader<SanityChanged>,
+    asset_server: Res<AssetServer>,
+    player: Query<&Transform, With<Player>>,
+) {
+    for e in sanity_events.iter() {
+        let player_tx = player.single();
+        spawn_sanity_number(
+            e.delta,
+            &mut commands,
+            asset_server.load("fonts/monofonto.ttf"),
+            player_tx.translation,
+        );
+    }
+}
+
+// Spawn the scary transition screen
+pub fn covid_alert_system(
+    mut commands: Commands,
+    mut exposure_events: EventReader<CovidExposure>,
+    asset_server: Res<AssetServer>,
+) {
+    for _ in exposure_events.iter() {
+        let xpos = -SCREEN_WIDTH / 2. + 1000. / 2.;
+        commands
+            .spawn_bundle(SpriteBundle {
+                texture: asset_server.load("close_contact_alert.png"),
+                transform: Transform {
+                    translation: [xpos, 0., 50.].into(),
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .insert(CovidTransitionUiTag {
+                time_left: TRANSITION_LENGTH,
+            });
+    }
+}
+
 pub fn spawn_sanity_number(
     number: i32,
     commands: &mut Commands,
@@ -99,6 +160,49 @@ pub fn spawn_sanity_number(
     });
 }
 
+// The end-of-run panel: stats for this run, achievements and the profile totals
+pub fn spawn_run_summary(lines: Vec<String>, commands: &mut Commands, font: Handle<Font>) {
+    let line_height = 28.;
+    let height = (lines.len() as f32 + 1.) * line_height;
+    let xpos = -(SCREEN_WIDTH - 1000.) / 2.;
+    // sits in the lower half of the play area, under the game over art
+    let ypos = -250.;
+    let top = ypos + height / 2.;
+
+    commands.spawn_bundle(SpriteBundle{
+        transform: Transform {
+            translation: [xpos, ypos, 101.].into(),
+            ..Default::default()
+        },
+        sprite: Sprite{
+            color: Color::rgba(0., 0., 0., 0.8),
+            custom_size: Some(Vec2::new(800., height)),
+            ..Default::default()
+        },
+        ..Default::default()
+    });
+
+    let text_style = TextStyle{
+        font: font,
+        font_size: 22.,
+        color: Color::rgb(1., 1., 1.),
+    };
+    let align = TextAlignment{
+        vertical: VerticalAlign::Center,
+        horizontal: HorizontalAlign::Center,
+    };
+    for (i, line) in lines.into_iter().enumerate() {
+        commands.spawn_bundle(Text2dBundle{
+            text: Text::with_section(line, text_style.clone(), align),
+            transform: Transform {
+                translation: [xpos, top - (i as f32 + 1.) * line_height, 102.].into(),
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+    }
+}
+
 pub fn setup_ui(mut commands: Commands, asset_server: Res<AssetServer>) {
     commands.spawn_bundle(UiCameraBundle::default());
     // The bundle holding the status bar i.e. the date
@@ -215,6 +319,61 @@ pub fn setup_ui(mut commands: Commands, asset_server: Res<AssetServer>) {
         ..Default::default()
     }).insert(SanityCoveringTag{});
 
+    // The compact need meters, to the right of the sanity bar
+    let font = asset_server.load("fonts/monofonto.ttf");
+    for (i, need) in ALL_NEEDS.iter().enumerate() {
+        let x = (-SCREEN_WIDTH / 2.) + mhb_bar_offset + mhb_bar_filling_width() + 16. + (i as f32 * need_meter_spacing());
+        // background
+        commands.spawn_bundle(SpriteBundle{
+            transform: Transform {
+                translation: [x, mhb_ypos - 1., 11.].into(),
+                ..Default::default()
+            },
+            sprite: Sprite{
+                color: Color::rgb(0.2, 0.2, 0.2),
+                custom_size: Some(Vec2::new(need_meter_width(), mhb_bar_filling_height())),
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        // filling
+        commands.spawn_bundle(SpriteBundle{
+            transform: Transform {
+                translation: [x, mhb_ypos - 1., 12.].into(),
+                ..Default::default()
+            },
+            sprite: Sprite{
+                color: need.colour(),
+                custom_size: Some(Vec2::new(need_meter_width(), mhb_bar_filling_height())),
+                ..Default::default()
+            },
+            ..Default::default()
+        }).insert(NeedMeterTag{
+            need: *need,
+            base_y: mhb_ypos - 1.,
+        });
+        // label
+        commands.spawn_bundle(Text2dBundle{
+            text: Text::with_section(
+                need.short_label(),
+                TextStyle{
+                    font: font.clone(),
+                    font_size: 14.,
+                    color: Color::rgb(0., 0., 0.),
+                },
+                TextAlignment{
+                    vertical: VerticalAlign::Center,
+                    horizontal: HorizontalAlign::Center,
+                },
+            ),
+            transform: Transform {
+                translation: [x, mhb_ypos - mhb_bar_filling_height() / 2. - 10., 12.].into(),
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+    }
+
     // The Covid risk indicator
     // background
     commands.spawn_bundle(SpriteBundle{
@@ -253,52 +412,23 @@ pub fn rhs_width() -> f32 {
     324.
 }
 
-// Returns vector of lines
-pub fn lay_out_text_monofonto(point_size: f32, width_px: f32, text: &String) -> Vec<String> {
-    let mut last_word = 0;
-    let mut start_of_line = 0;
-    let mut rv: Vec<String> = vec![];
-    for (i, c) in text.chars().enumerate() {
-        if c == '|' {
-            // forced line break
-            rv.push(String::from(&text[start_of_line..i]));
-            start_of_line = i + 1;
-            last_word = start_of_line;
-        } else if c == ' ' {
-            // here we need to update the 'last word' situation
-            last_word = i + 1;
-        } else {
-            // TODO: this breaks if start_of_line==last_word, i.e., a word is too long to fit in a
-            // single line. Text and fix
-            if estimate_width(point_size, 1 + i - start_of_line) > width_px {
-                rv.push(String::from(&text[start_of_line..last_word]));
-                start_of_line = last_word;
-            }
-        }
-    }
-    // copy the last line, if any
-    if start_of_line < text.len() {
-        rv.push(String::from(&text[start_of_line..]));
-    }
-    return rv;
-}
-
-fn estimate_width(point_size: f32, chars: usize) -> f32 {
-    return 0.4417 * point_size * (chars as f32);
-}
-
 pub fn update(mut query: Query<(&mut Text, &DateTag)>, state: Res<GameState>) {
     for (mut x, _) in query.iter_mut() {
-        x.sections[0].value = format!(
+        let mut status = format!(
             "It’s {}, {}{} March 2020",
             march_2020_dow(state.date),
             state.date,
             english_ordinal(state.date)
         );
+        if state.isolation.active() {
+            let left = state.isolation.days_left(state.date);
+            status += &format!("   ·   Isolating: {} day{} to go", left, if left == 1 { "" } else { "s" });
+        }
+        x.sections[0].value = status;
     }
 }
 
-pub fn update_sanity_bar_covering(mut query: Query<(&mut Sprite, &mut Transform, &SanityCoveringTag)>, state: Res<GameState>, time: Res<Time>) {
+pub fn update_sanity_bar_covering(mut query: Query<(&mut Sprite, &mut Transform, &SanityCoveringTag)>, state: Res<GameState>, time: Res<GameClock>) {
     let (mut sprite, mut tx, _) = query.single_mut();
 
     let old_width = match sprite.custom_size {
@@ -333,7 +463,16 @@ pub fn update_sanity_bar_covering(mut query: Query<(&mut Sprite, &mut Transform,
 
 }
 
-pub fn update_covid_risk(mut query: Query<(&CovidRiskElement, &mut Visibility, &mut Transform)>, state: Res<GameState>, time: Res<Time>) {
+pub fn update_need_meters(mut query: Query<(&mut Sprite, &mut Transform, &NeedMeterTag)>, state: Res<GameState>) {
+    for (mut sprite, mut tx, tag) in query.iter_mut() {
+        // shrink from the top down, so the meter stays anchored at the bottom
+        let height = mhb_bar_filling_height() * (state.needs.get(tag.need) / NEED_MAX);
+        sprite.custom_size = Some(Vec2::new(need_meter_width(), height));
+        tx.translation.y = tag.base_y - (mhb_bar_filling_height() - height) / 2.;
+    }
+}
+
+pub fn update_covid_risk(mut query: Query<(&CovidRiskElement, &mut Visibility, &mut Transform)>, state: Res<GameState>, time: Res<GameClock>) {
     let tween_time = ease_in_out_circ((1./0.3) * f64::min(0.3, time.seconds_since_startup() - state.last_covid_risk_shown) as f32);
     for (cre, mut v, mut t) in query.iter_mut() {
         if state.show_covid_risk && state.covid_risk >= cre.min_risk {
@@ -351,16 +490,7 @@ pub fn update_covid_risk(mut query: Query<(&CovidRiskElement, &mut Visibility, &
     }
 }
 
-pub fn text_message_animator(mut query: Query<(&TextMessageTag, &mut Transform)>, state:Res<GameState>, time: Res<Time>) {
-    let tween_time = ease_in_out_circ((1./0.3) * f64::min(0.3, time.seconds_since_startup() - state.last_msg_animation_time) as f32);
-    //let tween_time = ((1./0.3) * f64::min(0.3, time.seconds_since_startup() - state.last_msg_animation_time) as f32);
-    for (tmt, mut t) in query.iter_mut() {
-        let dt = tmt.bottom_to - tmt.bottom_from;
-        t.translation.y = tmt.bottom_from + dt * tween_time;
-    }
-}
-
-pub fn sanity_number_tween(mut commands: Commands, mut query: Query<(&mut SanityNumberTween, &mut Transform, &mut Text, Entity)>, time: Res<Time>) {
+pub fn sanity_number_tween(mut commands: Commands, mut query: Query<(&mut SanityNumberTween, &mut Transform, &mut Text, Entity)>, time: Res<GameClock>) {
     let dt = time.delta_seconds();
     for (mut mhn, mut t, mut txt, ety) in query.iter_mut() {
         mhn.time_left -= dt;
@@ -382,7 +512,7 @@ pub fn sanity_number_tween(mut commands: Commands, mut query: Query<(&mut Sanity
 }
 
 // see: https://easings.net/#easeInOutCirc
-fn ease_in_out_circ(x: f32) -> f32 {
+pub fn ease_in_out_circ(x: f32) -> f32 {
     return if x < 0.5 {
       (1. - f32::sqrt(1. - f32::powf(2. * x, 2.))) / 2.
     } else {
@@ -448,3 +578,5 @@ fn english_ordinal(day: i32) -> &'static str {
 
 fn mhb_bar_filling_width() -> f32 { 721. }
 fn mhb_bar_filling_height() -> f32 { 28. }
+fn need_meter_width() -> f32 { 8. }
+fn need_meter_spacing() -> f32 { 11. }
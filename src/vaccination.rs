@@ -0,0 +1,146 @@
+// Getting vaccinated. The narrative books the player in, the Department of Health texts them the
+// day, and on or after that day a vaccine is waiting for them at the clinic. The first dose books
+// the second, and each one cuts the dose of covid the player takes from everyone around them.
+use bevy::prelude::*;
+
+use crate::environment::{Environment, Location};
+use crate::events::{PickupCollected, SanityChanged, TextReceived};
+use crate::game::GameState;
+use crate::narrative::NarrativeActions;
+use crate::pickup::{spawn_pickup, Pickup};
+
+pub const FULL_COURSE: u8 = 2;
+// Days from booking to the appointment, and from one dose to the next
+const BOOKING_LEAD_DAYS: i32 = 2;
+const DOSE_GAP_DAYS: i32 = 6;
+// What's left of a covid dose after 0, 1 and 2 jabs
+const DOSE_FACTORS: [f32; 3] = [1., 0.6, 0.2];
+// In front of the middle booth
+const VACCINE_LOCATION: [usize; 2] = [9, 6];
+const JAB_SANITY: i32 = 5;
+
+const HEALTH: &str = "Department of Health";
+
+#[derive(Debug, Clone, Default)]
+pub struct Vaccination {
+    doses: u8,
+    // The date of the next appointment, if one's booked
+    appointment: Option<i32>,
+}
+
+impl Vaccination {
+    pub fn doses(&self) -> u8 {
+        self.doses
+    }
+
+    pub fn fully_vaccinated(&self) -> bool {
+        self.doses >= FULL_COURSE
+    }
+
+    pub fn appointment(&self) -> Option<i32> {
+        self.appointment
+    }
+
+    // Returns the day booked, or None if there's nothing left to book
+    pub fn book(&mut self, date: i32) -> Option<i32> {
+        if self.fully_vaccinated() {
+            return None;
+        }
+        let day = date + BOOKING_LEAD_DAYS;
+        println!("vaccine dose {} booked for day {}", self.doses + 1, day);
+        self.appointment = Some(day);
+        self.appointment
+    }
+
+    // Whether there's an appointment today, or one the player's late for
+    pub fn due(&self, date: i32) -> bool {
+        matches!(self.appointment, Some(day) if date >= day)
+    }
+
+    // Returns the day the next dose is booked for, if there is one
+    pub fn record_dose(&mut self, date: i32) -> Option<i32> {
+        self.doses += 1;
+        println!("vaccine dose {} recorded on day {}", self.doses, date);
+        self.appointment = None;
+        if self.fully_vaccinated() {
+            return None;
+        }
+        self.appointment = Some(date + DOSE_GAP_DAYS);
+        self.appointment
+    }
+
+    // Multiplies every covid dose the player takes
+    pub fn dose_factor(&self) -> f32 {
+        DOSE_FACTORS[usize::min(self.doses as usize, DOSE_FACTORS.len() - 1)]
+    }
+}
+
+pub fn booking_text(dose: u8, day: i32) -> String {
+    format!(
+        "Your COVID-19 vaccination (dose {}) is booked for day {} at the vaccination clinic, just past the park. Please bring your Medicare card.",
+        dose, day
+    )
+}
+
+pub struct VaccinationPlugin;
+
+impl Plugin for VaccinationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(vaccine_spawn_system)
+            .add_system(vaccine_system);
+    }
+}
+
+// The vaccine waits at the clinic for as long as the player's due one
+pub fn vaccine_spawn_system(
+    mut commands: Commands,
+    state: Res<GameState>,
+    asset_server: Res<AssetServer>,
+    environment_query: Query<&Environment>,
+    pickup_query: Query<&Pickup>,
+) {
+    let clinic = environment_query.single().location == Location::Clinic;
+    let have_one = pickup_query.iter().any(|p| matches!(p, Pickup::Vaccine));
+    if clinic && state.vaccination.due(state.date) && !have_one {
+        spawn_pickup(
+            Pickup::Vaccine,
+            VACCINE_LOCATION,
+            &mut commands,
+            &asset_server,
+            NarrativeActions::default(),
+        );
+    }
+}
+
+pub fn vaccine_system(
+    mut state: ResMut<GameState>,
+    mut pickup_events: EventReader<PickupCollected>,
+    mut sanity_events: EventWriter<SanityChanged>,
+    mut text_events: EventWriter<TextReceived>,
+) {
+    for e in pickup_events.iter() {
+        if !matches!(e.pickup, Pickup::Vaccine) {
+            continue;
+        }
+        let date = state.date;
+        let body = match state.vaccination.record_dose(date) {
+            Some(day) => format!(
+                "Dose {} recorded. {}",
+                state.vaccination.doses(),
+                booking_text(state.vaccination.doses() + 1, day)
+            ),
+            None => {
+                state.area_access.set_vaccinated(true);
+                format!(
+                    "Dose {} recorded. You are now fully vaccinated. Thank you for doing your part.",
+                    state.vaccination.doses()
+                )
+            }
+        };
+        state.change_sanity(JAB_SANITY, &mut sanity_events);
+        text_events.send(TextReceived {
+            sender: String::from(HEALTH),
+            body,
+        });
+    }
+}
@@ -0,0 +1,152 @@
+// Breaking text into lines that fit a width, measured with the font the text will be drawn in.
+//
+// Lines break at spaces. A word too long for a line of its own is broken between graphemes, with
+// a hyphen if it splits letters. `|` forces a line break, as it does in the narrative files.
+use ab_glyph::{Font as _, FontArc, PxScale, ScaleFont};
+use bevy::prelude::*;
+use unicode_segmentation::UnicodeSegmentation;
+
+pub const FORCED_BREAK: char = '|';
+
+// monofonto's advance, as a fraction of the point size
+const MONOFONTO_ADVANCE: f32 = 0.4417;
+
+// How wide a run of text is, in pixels
+pub trait Measure {
+    fn width(&self, s: &str) -> f32;
+}
+
+// Real glyph advances and kerning from a loaded font
+pub struct FontMeasure {
+    font: FontArc,
+    scale: PxScale,
+}
+
+impl FontMeasure {
+    pub fn new(font: &Font, point_size: f32) -> Self {
+        FontMeasure {
+            font: font.font.clone(),
+            scale: PxScale::from(point_size),
+        }
+    }
+}
+
+impl Measure for FontMeasure {
+    fn width(&self, s: &str) -> f32 {
+        let font = self.font.as_scaled(self.scale);
+        let mut width = 0.;
+        let mut last = None;
+        for c in s.chars() {
+            let id = font.glyph_id(c);
+            if let Some(last) = last {
+                width += font.kern(last, id);
+            }
+            width += font.h_advance(id);
+            last = Some(id);
+        }
+        width
+    }
+}
+
+// Every grapheme the same width. For before the font has loaded.
+pub struct FixedMeasure {
+    pub advance: f32,
+}
+
+impl FixedMeasure {
+    pub fn monofonto(point_size: f32) -> Self {
+        FixedMeasure {
+            advance: MONOFONTO_ADVANCE * point_size,
+        }
+    }
+}
+
+impl Measure for FixedMeasure {
+    fn width(&self, s: &str) -> f32 {
+        self.advance * graphemes(s).len() as f32
+    }
+}
+
+// The font's real metrics if it's loaded, monofonto's fixed advance otherwise
+pub fn measure(font: Option<&Font>, point_size: f32) -> Box<dyn Measure> {
+    match font {
+        Some(font) => Box::new(FontMeasure::new(font, point_size)),
+        None => Box::new(FixedMeasure::monofonto(point_size)),
+    }
+}
+
+// Returns vector of lines
+pub fn wrap_text(measure: &dyn Measure, width: f32, text: &str) -> Vec<String> {
+    let mut rv = vec![];
+    let paragraphs: Vec<&str> = text.split(FORCED_BREAK).collect();
+    for (i, paragraph) in paragraphs.iter().enumerate() {
+        // a break at the very end doesn't start another line
+        if paragraph.is_empty() && i == paragraphs.len() - 1 {
+            break;
+        }
+        wrap_paragraph(measure, width, paragraph, &mut rv);
+    }
+    rv
+}
+
+fn wrap_paragraph(measure: &dyn Measure, width: f32, paragraph: &str, rv: &mut Vec<String>) {
+    let first = rv.len();
+    let mut line = String::new();
+    // Nothing on the line yet, so the next word doesn't need a space in front of it
+    let mut fresh = true;
+    for word in paragraph.split(' ') {
+        let candidate = if fresh {
+            String::from(word)
+        } else {
+            format!("{} {}", line, word)
+        };
+        if measure.width(&candidate) <= width {
+            line = candidate;
+            fresh = false;
+            continue;
+        }
+
+        if !fresh {
+            rv.push(line);
+        }
+        line = break_word(measure, width, word, rv);
+        fresh = line.is_empty();
+    }
+    // an empty paragraph is a blank line, but a word that broke evenly leaves nothing over
+    if !line.is_empty() || rv.len() == first {
+        rv.push(line);
+    }
+}
+
+// Pushes whole lines' worth of `word` and returns what's left over, which fits
+fn break_word(measure: &dyn Measure, width: f32, word: &str, rv: &mut Vec<String>) -> String {
+    let mut rest = graphemes(word);
+    while measure.width(&rest.concat()) > width {
+        // as much as will fit, but always at least one grapheme so this finishes
+        let mut n = 1;
+        while n < rest.len()
+            && measure.width(&with_hyphen(&rest[..n + 1], rest.get(n + 1).copied())) <= width
+        {
+            n += 1;
+        }
+        rv.push(with_hyphen(&rest[..n], rest.get(n).copied()));
+        rest.drain(..n);
+    }
+    rest.concat()
+}
+
+// Hyphenate between two letters; anywhere else (URLs, numbers...) just break
+fn with_hyphen(head: &[&str], next: Option<&str>) -> String {
+    let letter = |g: &str| g.chars().next().map(|c| c.is_alphabetic()).unwrap_or(false);
+    let last = head.last().map(|g| letter(g)).unwrap_or(false);
+    match next {
+        Some(next) if last && letter(next) => format!("{}-", head.concat()),
+        _ => head.concat(),
+    }
+}
+
+// Splits into what a reader would call characters (extended grapheme clusters), so accents,
+// emoji sequences, flags, Hangul syllables and Indic clusters all stay whole
+pub fn graphemes(s: &str) -> Vec<&str> {
+    s.graphemes(true).collect()
+}
@@ -0,0 +1,106 @@
+mod common;
+
+use bevy::prelude::Entity;
+use common::GameHarness;
+use melsim::behaviour::{Behaviour, CHECKOUT_QUEUE};
+use melsim::environment::Location;
+use melsim::pathfinding::Destination;
+use melsim::rng::GameRng;
+
+fn stops(behaviour: &mut Behaviour, location: Location, n: usize) -> Vec<Option<Destination>> {
+    let mut rng = GameRng::from_seed(1);
+    (0..n)
+        .map(|_| {
+            behaviour
+                .next_stop(location, [10, 10], &mut rng)
+                .map(|(d, _)| d)
+        })
+        .collect()
+}
+
+#[test]
+fn joggers_are_quick_and_dog_walkers_slow() {
+    let walking = Behaviour::Wanderer.speed();
+    assert!(Behaviour::jogger().speed() > walking);
+    assert!(Behaviour::DogWalker.speed() < walking);
+    assert_eq!(Behaviour::Stationary.speed(), 0.);
+}
+
+#[test]
+fn shoppers_do_the_aisles_then_queue_at_the_checkout() {
+    let mut shopper = Behaviour::shopper();
+    let stops = stops(&mut shopper, Location::Shops, 8);
+    let checkout = Some(Destination::Tile(CHECKOUT_QUEUE));
+
+    assert!(stops[..3].iter().all(|s| *s != checkout));
+    assert_eq!(stops[3], checkout);
+    // and round again
+    assert!(stops[4..7].iter().all(|s| *s != checkout));
+    assert_eq!(stops[7], checkout);
+}
+
+#[test]
+fn joggers_lap_the_park() {
+    let mut jogger = Behaviour::jogger();
+    let stops = stops(&mut jogger, Location::Park, 10);
+    assert_eq!(stops[..5], stops[5..]);
+    assert!(stops
+        .iter()
+        .all(|s| matches!(s, Some(Destination::Tile(_)))));
+
+    // and just wander anywhere else
+    assert_eq!(
+        jogger.next_stop(Location::Home, [10, 10], &mut GameRng::from_seed(1)),
+        None
+    );
+}
+
+#[test]
+fn followers_head_for_whoever_they_follow() {
+    let mut rng = GameRng::from_seed(1);
+    let mut follower = Behaviour::Follower(None);
+    let (destination, _) = follower
+        .next_stop(Location::Park, [10, 10], &mut rng)
+        .unwrap();
+    assert_eq!(destination, Destination::Player);
+
+    let friend = Entity::from_raw(7);
+    let mut follower = Behaviour::Follower(Some(friend));
+    let (destination, _) = follower
+        .next_stop(Location::Park, [10, 10], &mut rng)
+        .unwrap();
+    assert_eq!(destination, Destination::Entity(friend));
+}
+
+#[test]
+fn wanderers_and_staff_go_nowhere_in_particular() {
+    let mut rng = GameRng::from_seed(1);
+    for mut b in [Behaviour::Wanderer, Behaviour::Stationary] {
+        assert_eq!(b.next_stop(Location::Shops, [10, 10], &mut rng), None);
+    }
+}
+
+#[test]
+fn the_checkout_staff_stay_put() {
+    let mut game = GameHarness::new();
+    game.place_player([1, 17]);
+    game.run_until(1., |g| g.location() == Location::Park);
+    game.place_player([18, 14]);
+    game.run_until(1., |g| g.location() == Location::Shops);
+    game.step(2);
+
+    let staff = |game: &mut GameHarness| {
+        let mut query = game.app.world.query::<(
+            &Behaviour,
+            &bevy_rapier2d::prelude::RigidBodyPositionComponent,
+        )>();
+        query
+            .iter(&game.app.world)
+            .find(|(b, _)| **b == Behaviour::Stationary)
+            .map(|(_, p)| p.position.translation.vector)
+            .unwrap()
+    };
+    let before = staff(&mut game);
+    game.run_for(3.);
+    assert!((staff(&mut game) - before).magnitude() < 0.1);
+}
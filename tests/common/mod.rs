@@ -0,0 +1,268 @@
+// A headless copy of the game for tests: no window, no audio, and a clock that only moves when we
+// step it.
+//
+// Each test binary uses a different part of this.
+#![allow(dead_code)]
+
+use bevy::app::Events;
+use bevy::asset::AssetPlugin;
+use bevy::input::keyboard::KeyboardInput;
+use bevy::input::{ElementState, InputPlugin};
+use bevy::prelude::*;
+use bevy::transform::TransformPlugin;
+use bevy_rapier2d::physics::{NoUserData, RapierPhysicsPlugin};
+use bevy_rapier2d::prelude::*;
+
+use melsim::clock::GameClock;
+use melsim::environment::{tile_coords_to_screen_pos, Environment, Location};
+use melsim::epidemic::{Infection, Sir};
+use melsim::events::TextReceived;
+use melsim::game::GameState;
+use melsim::npc::NPC;
+use melsim::phone::Phone;
+use melsim::player::Player;
+use melsim::replay::{InputRecorder, InputRecording, InputReplayer};
+use melsim::rng::GameRng;
+use melsim::stats::ProfileFile;
+use melsim::{AudioPlugin, MelsimPlugins, TILE_SIZE};
+
+// Matches rapier's default integration step, so physics and the game clock agree
+pub const FRAME: f64 = 1. / 60.;
+// Tests shouldn't depend on luck
+const DEFAULT_SEED: u64 = 2020;
+
+pub struct GameHarness {
+    pub app: App,
+}
+
+impl GameHarness {
+    pub fn new() -> Self {
+        GameHarness::with_seed(DEFAULT_SEED)
+    }
+
+    pub fn with_seed(seed: u64) -> Self {
+        GameHarness::build(seed, |_| {})
+    }
+
+    // Records whatever keys the test presses
+    pub fn recording(seed: u64, path: &str) -> Self {
+        let recorder = InputRecorder::create(path, seed, FRAME).unwrap();
+        GameHarness::build(seed, move |app| {
+            app.insert_resource(recorder);
+        })
+    }
+
+    pub fn replaying(path: &str) -> Self {
+        let recording = InputRecording::load(path).unwrap();
+        assert_eq!(
+            recording.step, FRAME,
+            "recording isn't at the harness's frame rate"
+        );
+        GameHarness::build(recording.seed, move |app| {
+            app.insert_resource(InputReplayer::new(recording));
+        })
+    }
+
+    fn build(seed: u64, setup: impl FnOnce(&mut App)) -> Self {
+        let mut app = App::new();
+        setup(&mut app);
+        app.insert_resource(GameClock::fixed(FRAME))
+            .insert_resource(GameRng::from_seed(seed))
+            // Never touch the player's real profile
+            .insert_resource(ProfileFile(None))
+            .add_plugins(MinimalPlugins)
+            .add_plugin(TransformPlugin)
+            .add_plugin(InputPlugin)
+            .add_plugin(AssetPlugin)
+            .add_asset::<Image>()
+            .add_asset::<TextureAtlas>()
+            .add_asset::<Font>()
+            .add_plugin(RapierPhysicsPlugin::<NoUserData>::default())
+            .add_plugins_with(MelsimPlugins, |group| group.disable::<AudioPlugin>());
+
+        let mut harness = GameHarness { app };
+        // Run the startup systems
+        harness.step(1);
+        harness
+    }
+
+    // Goes through the keyboard events like a real key press, so just_pressed works
+    pub fn press(&mut self, key: KeyCode) {
+        self.send_key(key, ElementState::Pressed);
+    }
+
+    pub fn release(&mut self, key: KeyCode) {
+        self.send_key(key, ElementState::Released);
+    }
+
+    fn send_key(&mut self, key: KeyCode, state: ElementState) {
+        self.app
+            .world
+            .get_resource_mut::<Events<KeyboardInput>>()
+            .unwrap()
+            .send(KeyboardInput {
+                scan_code: 0,
+                key_code: Some(key),
+                state,
+            });
+    }
+
+    pub fn send_text(&mut self, sender: &str, body: &str) {
+        self.app
+            .world
+            .get_resource_mut::<Events<TextReceived>>()
+            .unwrap()
+            .send(TextReceived {
+                sender: String::from(sender),
+                body: String::from(body),
+            });
+    }
+
+    pub fn replay_finished(&self) -> bool {
+        self.app
+            .world
+            .get_resource::<InputReplayer>()
+            .map(|r| r.finished())
+            .unwrap_or(true)
+    }
+
+    pub fn step(&mut self, frames: usize) {
+        for _ in 0..frames {
+            self.app.update();
+        }
+    }
+
+    pub fn run_for(&mut self, seconds: f64) {
+        self.step((seconds / FRAME).ceil() as usize);
+    }
+
+    // Keeps stepping until `done` says so. Panics rather than hanging if it never does.
+    pub fn run_until(&mut self, max_seconds: f64, done: impl Fn(&mut GameHarness) -> bool) {
+        let deadline = self.now() + max_seconds;
+        while !done(self) {
+            assert!(self.now() < deadline, "gave up after {}s", max_seconds);
+            self.step(1);
+        }
+    }
+
+    // Same tile convention as teleporters use for where the player lands
+    pub fn place_player(&mut self, tile: [usize; 2]) {
+        let (x, y) = tile_coords_to_screen_pos(tile[0], 2., tile[1], 3.);
+        self.place_player_at(x / TILE_SIZE, y / TILE_SIZE);
+    }
+
+    // In physics units
+    pub fn place_player_at(&mut self, x: f32, y: f32) {
+        let mut query = self.app.world.query_filtered::<(
+            &mut RigidBodyPositionComponent,
+            &mut RigidBodyVelocityComponent,
+        ), With<Player>>();
+        let (mut position, mut velocity) = query.iter_mut(&mut self.app.world).next().unwrap();
+        position.position.translation = [x, y].into();
+        velocity.linvel = [0., 0.].into();
+    }
+
+    // Right up against the first NPC, e.g. to catch something off them
+    pub fn place_player_by_npc(&mut self) {
+        let (x, y) = self.npc_positions()[0];
+        self.place_player_at(x, y + 1.);
+    }
+
+    pub fn now(&self) -> f64 {
+        self.app
+            .world
+            .get_resource::<GameClock>()
+            .unwrap()
+            .seconds_since_startup()
+    }
+
+    pub fn state(&self) -> &GameState {
+        self.app.world.get_resource::<GameState>().unwrap()
+    }
+
+    pub fn state_mut(&mut self) -> Mut<GameState> {
+        self.app.world.get_resource_mut::<GameState>().unwrap()
+    }
+
+    pub fn phone(&self) -> &Phone {
+        self.app.world.get_resource::<Phone>().unwrap()
+    }
+
+    pub fn phone_mut(&mut self) -> Mut<Phone> {
+        self.app.world.get_resource_mut::<Phone>().unwrap()
+    }
+
+    pub fn location(&mut self) -> Location {
+        let mut query = self.app.world.query::<&Environment>();
+        query.iter(&self.app.world).next().unwrap().location
+    }
+
+    // In physics units
+    pub fn player_position(&mut self) -> (f32, f32) {
+        let mut query = self
+            .app
+            .world
+            .query_filtered::<&RigidBodyPositionComponent, With<Player>>();
+        let v = query
+            .iter(&self.app.world)
+            .next()
+            .unwrap()
+            .position
+            .translation
+            .vector;
+        (v.x, v.y)
+    }
+
+    // In physics units, in spawn order
+    pub fn npc_positions(&mut self) -> Vec<(f32, f32)> {
+        let mut query = self
+            .app
+            .world
+            .query_filtered::<&RigidBodyPositionComponent, With<NPC>>();
+        query
+            .iter(&self.app.world)
+            .map(|p| {
+                let v = p.position.translation.vector;
+                (v.x, v.y)
+            })
+            .collect()
+    }
+
+    // Sets every NPC that's here now, e.g. to make sure they can pass covid on
+    pub fn set_npc_infections(&mut self, state: Sir) {
+        let mut query = self.app.world.query::<&mut Infection>();
+        for mut infection in query.iter_mut(&mut self.app.world) {
+            *infection = Infection::new(state, f64::INFINITY, 1.);
+        }
+    }
+
+    // Everything a run's outcome is judged on, for comparing two runs (or a run and a golden file)
+    pub fn snapshot(&mut self) -> String {
+        let state = self.state();
+        let mut rv = format!(
+            "time {:.3}\ndate {}\nsanity {}\ncovid risk {:.4}\nisolating {}\n",
+            self.now(),
+            state.date,
+            state.get_sanity(),
+            state.covid_risk,
+            state.in_covid_narrative()
+        );
+        rv += &format!("location {:?}\n", self.location());
+
+        let (x, y) = self.player_position();
+        rv += &format!("player {:.3} {:.3}\n", x, y);
+        for (x, y) in self.npc_positions() {
+            rv += &format!("npc {:.3} {:.3}\n", x, y);
+        }
+        rv
+    }
+
+    pub fn count<T: Component>(&mut self) -> usize {
+        self.entities::<T>().len()
+    }
+
+    pub fn entities<T: Component>(&mut self) -> Vec<Entity> {
+        let mut query = self.app.world.query_filtered::<Entity, With<T>>();
+        query.iter(&self.app.world).collect()
+    }
+}
@@ -0,0 +1,86 @@
+#![cfg(feature = "dev-console")]
+mod common;
+
+use bevy::app::Events;
+use common::GameHarness;
+use melsim::console::{parse_command, ConsoleCommand};
+use melsim::environment::Location;
+
+fn run(game: &mut GameHarness, line: &str) {
+    let command = parse_command(line).unwrap();
+    game.app
+        .world
+        .get_resource_mut::<Events<ConsoleCommand>>()
+        .unwrap()
+        .send(command);
+    game.step(2);
+}
+
+#[test]
+fn parses_the_documented_commands() {
+    assert_eq!(
+        parse_command("sanity +20"),
+        Ok(ConsoleCommand::ChangeSanity(20))
+    );
+    assert_eq!(
+        parse_command("sanity -5"),
+        Ok(ConsoleCommand::ChangeSanity(-5))
+    );
+    assert_eq!(
+        parse_command("sanity 50"),
+        Ok(ConsoleCommand::SetSanity(50))
+    );
+    assert_eq!(
+        parse_command("goto park 5 5"),
+        Ok(ConsoleCommand::Goto(Location::Park, [5, 5]))
+    );
+    assert_eq!(
+        parse_command("narrative jump 42"),
+        Ok(ConsoleCommand::NarrativeJump(42))
+    );
+    assert_eq!(
+        parse_command("spawn npc 10 10"),
+        Ok(ConsoleCommand::SpawnNpc([10, 10]))
+    );
+    assert_eq!(
+        parse_command("covid 0.8"),
+        Ok(ConsoleCommand::Covid(Some(0.8)))
+    );
+    assert_eq!(parse_command("covid off"), Ok(ConsoleCommand::Covid(None)));
+    assert_eq!(
+        parse_command("lock shops"),
+        Ok(ConsoleCommand::SetAccess(Location::Shops, false))
+    );
+    assert_eq!(
+        parse_command("time scale 4"),
+        Ok(ConsoleCommand::TimeScale(4.))
+    );
+    assert_eq!(parse_command("day 14"), Ok(ConsoleCommand::Day(14)));
+}
+
+#[test]
+fn rejects_nonsense() {
+    assert!(parse_command("").is_err());
+    assert!(parse_command("goto mars 1 1").is_err());
+    assert!(parse_command("spawn npc 10").is_err());
+    assert!(parse_command("day 14 15").is_err());
+    assert!(parse_command("sanity lots").is_err());
+}
+
+#[test]
+fn commands_change_the_game() {
+    let mut game = GameHarness::new();
+
+    let before = game.state().get_sanity();
+    run(&mut game, "sanity -5");
+    assert_eq!(game.state().get_sanity(), before - 5);
+
+    run(&mut game, "lock shops");
+    assert!(!game.state().area_access.can_access(Location::Shops));
+
+    run(&mut game, "goto park 5 5");
+    assert_eq!(game.location(), Location::Park);
+
+    run(&mut game, "day 14");
+    assert_eq!(game.state().date, 14);
+}
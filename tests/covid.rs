@@ -0,0 +1,79 @@
+use melsim::covid::{dose_fade, dose_rate, dose_rate_in, infection_chance};
+use melsim::environment::{tile_coords_to_screen_pos, transmission_at, Location};
+use melsim::TILE_SIZE;
+
+const FRAME: f32 = 1. / 60.;
+
+// The chance of having caught it after `seconds` at distance `d`
+fn linger(d: f32, seconds: f32) -> f32 {
+    let mut dose = 0.;
+    for _ in 0..(seconds / FRAME) as usize {
+        dose = dose * dose_fade(FRAME) + dose_rate(d) * FRAME;
+    }
+    infection_chance(dose)
+}
+
+#[test]
+fn the_dose_falls_off_with_distance() {
+    assert!(dose_rate(0.) > dose_rate(1.));
+    assert!(dose_rate(1.) > dose_rate(3.));
+    assert!(dose_rate(3.) > 0.);
+    assert_eq!(dose_rate(6.), 0.);
+    assert_eq!(dose_rate(100.), 0.);
+}
+
+#[test]
+fn brushing_past_for_a_frame_is_a_small_risk() {
+    assert!(linger(0., FRAME) < 0.05);
+}
+
+#[test]
+fn hanging_around_at_a_distance_adds_up() {
+    assert!(linger(2.6, 1.) < 0.5);
+    assert!(linger(2.6, 60.) > 0.99);
+}
+
+#[test]
+fn the_dose_fades_once_nobody_is_around() {
+    assert_eq!(dose_fade(0.), 1.);
+    assert!((dose_fade(20.) - 0.5).abs() < 1e-6);
+    assert!(dose_fade(100.) < 0.05);
+}
+
+#[test]
+fn no_dose_no_chance() {
+    assert_eq!(infection_chance(0.), 0.);
+    assert!(infection_chance(1.) < infection_chance(2.));
+    assert!(infection_chance(50.) > 0.999);
+}
+
+// The middle of tile (x, y), in physics units
+fn tile(x: usize, y: usize) -> (f32, f32) {
+    let (x, y) = tile_coords_to_screen_pos(x, 1., y, 1.);
+    (x / TILE_SIZE, y / TILE_SIZE)
+}
+
+fn dose_rate_at(location: Location, (x, y): (f32, f32), d: f32) -> f32 {
+    dose_rate_in(d, &transmission_at(location, x, y))
+}
+
+#[test]
+fn it_spreads_less_in_the_open_air() {
+    let park = dose_rate_at(Location::Park, tile(10, 10), 1.);
+    let shops = dose_rate_at(Location::Shops, tile(10, 16), 1.);
+    assert!(park < dose_rate(1.));
+    assert!(shops > dose_rate(1.));
+}
+
+#[test]
+fn it_carries_further_indoors() {
+    assert_eq!(dose_rate_at(Location::Park, tile(10, 10), 6.), 0.);
+    assert!(dose_rate_at(Location::Shops, tile(10, 16), 6.) > 0.);
+}
+
+#[test]
+fn the_checkout_queue_is_worse_than_the_rest_of_the_shop() {
+    let queue = dose_rate_at(Location::Shops, tile(3, 11), 1.);
+    let shop = dose_rate_at(Location::Shops, tile(10, 16), 1.);
+    assert!(queue > shop);
+}
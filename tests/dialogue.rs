@@ -0,0 +1,266 @@
+mod common;
+
+use bevy::app::Events;
+use bevy::prelude::*;
+use common::GameHarness;
+use melsim::behaviour::Behaviour;
+use melsim::dialogue::{
+    choose_lines, load_dialogue, Conversation, SpeechBubble, ANYONE, DIALOGUE_FILE,
+};
+use melsim::environment::Location;
+use melsim::epidemic::Infection;
+use melsim::events::NarrativeEventFired;
+use melsim::game::GameState;
+use melsim::narrative::{load_csv, NarrativeActions, NarrativeCriterion, SpawnableNpc};
+use melsim::needs::Need;
+use melsim::npc::{Identity, NPC};
+use melsim::population::Visitor;
+use melsim::protection::Protection;
+
+// Leaves one NPC in the park, stood still, and the player just beside them
+fn next_to_an_npc(game: &mut GameHarness) -> String {
+    game.place_player([1, 17]);
+    game.run_until(1., |g| g.location() == Location::Park);
+    game.step(2);
+    let npcs = game.entities::<NPC>();
+    for other in &npcs[1..] {
+        game.app.world.despawn(*other);
+    }
+    let npc = npcs[0];
+    game.app
+        .world
+        .entity_mut(npc)
+        .insert(Behaviour::Stationary)
+        .remove::<Visitor>();
+    game.step(1);
+    let (x, y) = game.npc_positions()[0];
+    game.place_player_at(x + 2.5, y);
+    game.step(1);
+    game.app.world.get::<Identity>(npc).unwrap().name.clone()
+}
+
+fn say(game: &mut GameHarness) {
+    game.press(KeyCode::F);
+    game.step(1);
+    game.release(KeyCode::F);
+    game.step(1);
+}
+
+fn talking(game: &GameHarness) -> bool {
+    let conversation = game.app.world.get_resource::<Conversation>().unwrap();
+    conversation.with().is_some()
+}
+
+#[test]
+fn everyone_has_something_to_say() {
+    let table = load_dialogue(DIALOGUE_FILE);
+    let state = GameState::default();
+    for behaviour in [
+        Behaviour::Wanderer,
+        Behaviour::shopper(),
+        Behaviour::jogger(),
+        Behaviour::DogWalker,
+        Behaviour::Stationary,
+    ] {
+        let lines = choose_lines(&table, behaviour.identity(), &state, 0.).unwrap();
+        assert!(!lines.is_empty(), "{:?}", behaviour);
+    }
+    assert!(choose_lines(&table, "Friend", &state, 0.).is_some());
+}
+
+#[test]
+fn lines_depend_on_who_and_how_things_stand() {
+    let table = load_dialogue(DIALOGUE_FILE);
+    let mut state = GameState::default();
+    let bare = choose_lines(&table, "Friend", &state, 0.).unwrap().to_vec();
+    state.protections.start(Protection::Mask, 0.);
+    let masked = choose_lines(&table, "Friend", &state, 0.).unwrap().to_vec();
+    assert_ne!(bare, masked);
+
+    // Nobody in particular gets the catch-all lines
+    let anyone = choose_lines(&table, ANYONE, &state, 0.).unwrap();
+    let stranger = choose_lines(&table, "Someone new", &state, 0.).unwrap();
+    assert_eq!(anyone, stranger);
+}
+
+#[test]
+fn the_narrative_can_wait_for_a_chat() {
+    let path = std::env::temp_dir().join("melsim-dialogue-narrative.csv");
+    std::fs::write(
+        &path,
+        "Sender,Body (Rough),Body (Polished),Elapsed Time,Cleared All Pickups?,Location change?,\
+         Send Texts?,Change Sanity?,Spawn Item?,Unlock area?,Lock area?,Spawn NPC,Talked to?\n\
+         [FRIEND APPEARS],,,1,,,,,,,,Friend;10;10;Follower,\n\
+         Yourself,Nice to catch up,,,,,,,,,,,Friend\n\
+         Yourself,Where did they go?,,,,,,,,,,,Friend;60\n",
+    )
+    .unwrap();
+
+    let events = load_csv(&path.to_string_lossy());
+    let _ = std::fs::remove_file(&path);
+
+    assert_eq!(events.len(), 3);
+    assert_eq!(events[0].action.spawn_npc[0].name, "Friend");
+    assert!(matches!(
+        &events[1].criterion,
+        NarrativeCriterion::TalkedTo(name, None) if name == "Friend"
+    ));
+    // Or moves on without them, so the story can't get stuck
+    assert!(matches!(
+        &events[2].criterion,
+        NarrativeCriterion::TalkedTo(name, Some(t)) if name == "Friend" && *t == 60.
+    ));
+}
+
+#[test]
+fn talking_it_through_cheers_the_player_up() {
+    let mut game = GameHarness::new();
+    let name = next_to_an_npc(&mut game);
+    game.state_mut().needs.change(Need::Social, -50.);
+    let social = game.state().needs.get(Need::Social);
+
+    say(&mut game);
+    assert!(talking(&game));
+    assert_eq!(game.count::<SpeechBubble>(), 2);
+
+    for _ in 0..10 {
+        if !talking(&game) {
+            break;
+        }
+        say(&mut game);
+    }
+    game.step(1);
+    assert!(!talking(&game));
+    assert_eq!(game.count::<SpeechBubble>(), 0);
+    assert!(game.state().talked_to.contains(&name));
+    assert!(game.state().needs.get(Need::Social) > social + 10.);
+}
+
+#[test]
+fn walking_off_mid_conversation_counts_for_nothing() {
+    let mut game = GameHarness::new();
+    let name = next_to_an_npc(&mut game);
+
+    say(&mut game);
+    assert!(talking(&game));
+    game.place_player([15, 5]);
+    game.step(2);
+
+    assert!(!talking(&game));
+    assert_eq!(game.count::<SpeechBubble>(), 0);
+    assert!(!game.state().talked_to.contains(&name));
+}
+
+#[test]
+fn nobody_to_talk_to_from_across_the_park() {
+    let mut game = GameHarness::new();
+    next_to_an_npc(&mut game);
+    game.place_player([15, 5]);
+    game.step(1);
+
+    say(&mut game);
+    assert!(!talking(&game));
+}
+
+#[test]
+fn long_lines_wrap_inside_the_bubble() {
+    let mut game = GameHarness::new();
+    next_to_an_npc(&mut game);
+    let npc = game.entities::<NPC>()[0];
+    let line = String::from(
+        "Honestly I have not been further than the letterbox in about three weeks now",
+    );
+    let now = game.now();
+    game.app
+        .world
+        .get_resource_mut::<Conversation>()
+        .unwrap()
+        .start(npc, "Someone", &[line], now);
+    game.step(1);
+
+    let mut texts = game.app.world.query_filtered::<&Text, With<SpeechBubble>>();
+    let text = &texts.iter(&game.app.world).next().unwrap().sections[0].value;
+    assert!(text.lines().count() > 1, "{}", text);
+    let mut sprites = game
+        .app
+        .world
+        .query_filtered::<&Sprite, With<SpeechBubble>>();
+    let size = sprites
+        .iter(&game.app.world)
+        .next()
+        .unwrap()
+        .custom_size
+        .unwrap();
+    assert!(size.x <= 240., "{:?}", size);
+    assert!(size.y > 2. * 16., "{:?}", size);
+}
+
+fn friends_here(game: &mut GameHarness) -> usize {
+    let mut query = game.app.world.query::<&Identity>();
+    query
+        .iter(&game.app.world)
+        .filter(|i| i.name == "Friend")
+        .count()
+}
+
+#[test]
+fn the_friend_comes_along_until_theyve_been_talked_to() {
+    let mut game = GameHarness::new();
+    let mut actions = NarrativeActions::default();
+    actions.spawn_npc.push(SpawnableNpc {
+        name: String::from("Friend"),
+        location: [10, 10],
+        behaviour: Behaviour::Follower(None),
+    });
+    game.app
+        .world
+        .get_resource_mut::<Events<NarrativeEventFired>>()
+        .unwrap()
+        .send(NarrativeEventFired { actions });
+    game.step(2);
+    assert_eq!(friends_here(&mut game), 1);
+
+    // Moving on clears everyone out, but the story is still waiting on a chat
+    game.place_player([1, 17]);
+    game.run_until(1., |g| g.location() == Location::Park);
+    game.step(2);
+    assert_eq!(friends_here(&mut game), 1);
+
+    game.state_mut().talked_to.push(String::from("Friend"));
+    game.place_player([18, 14]);
+    game.run_until(1., |g| g.location() == Location::Shops);
+    game.step(2);
+    assert_eq!(friends_here(&mut game), 0);
+}
+
+#[test]
+fn the_friend_doesnt_come_home_or_bring_covid() {
+    let mut game = GameHarness::new();
+    let mut actions = NarrativeActions::default();
+    actions.spawn_npc.push(SpawnableNpc {
+        name: String::from("Friend"),
+        location: [10, 10],
+        behaviour: Behaviour::Follower(None),
+    });
+    game.app
+        .world
+        .get_resource_mut::<Events<NarrativeEventFired>>()
+        .unwrap()
+        .send(NarrativeEventFired { actions });
+    game.step(2);
+    game.place_player([1, 17]);
+    game.run_until(1., |g| g.location() == Location::Park);
+    game.step(2);
+    assert_eq!(friends_here(&mut game), 1);
+    let mut query = game.app.world.query::<(&Identity, &Infection)>();
+    for (identity, infection) in query.iter(&game.app.world) {
+        if identity.name == "Friend" {
+            assert!(!infection.is_infectious());
+        }
+    }
+
+    game.place_player([2, 1]);
+    game.run_until(1., |g| g.location() == Location::Home);
+    game.step(2);
+    assert_eq!(friends_here(&mut game), 0);
+}
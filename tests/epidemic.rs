@@ -0,0 +1,64 @@
+mod common;
+
+use common::GameHarness;
+use melsim::epidemic::{Epidemic, Infection, Sir};
+use melsim::game::DAY_LENGTH;
+
+#[test]
+fn prevalence_grows_then_shrinks() {
+    let mut epidemic = Epidemic::default();
+    let mut prevalence = vec![epidemic.prevalence()];
+    for _ in 0..200 {
+        epidemic.step_day();
+        prevalence.push(epidemic.prevalence());
+
+        let total =
+            epidemic.susceptible + epidemic.exposed + epidemic.infectious + epidemic.recovered;
+        assert!((total - 1.).abs() < 1e-9);
+    }
+
+    let peak = prevalence
+        .iter()
+        .enumerate()
+        .max_by(|a, b| a.1.partial_cmp(b.1).unwrap())
+        .unwrap();
+    assert!(peak.0 > 0 && peak.0 < 200);
+    assert!(*peak.1 > prevalence[0]);
+    assert!(prevalence[200] < prevalence[0]);
+}
+
+#[test]
+fn an_npc_goes_through_incubation_then_recovers() {
+    let mut infection = Infection::new(Sir::Susceptible, 0., 1.);
+    assert!(!infection.expose(0.5, 0.));
+    assert_eq!(infection.state, Sir::Susceptible);
+    assert!(infection.expose(0.5, 0.));
+    assert_eq!(infection.state, Sir::Exposed);
+    assert!(!infection.is_infectious());
+
+    // Still incubating
+    infection.progress(DAY_LENGTH);
+    assert_eq!(infection.state, Sir::Exposed);
+
+    infection.progress(3. * DAY_LENGTH);
+    assert!(infection.is_infectious());
+    infection.progress(9. * DAY_LENGTH);
+    assert!(infection.is_infectious());
+    infection.progress(10. * DAY_LENGTH);
+    assert_eq!(infection.state, Sir::Recovered);
+
+    // and can't catch it again
+    assert!(!infection.expose(100., 10. * DAY_LENGTH));
+}
+
+#[test]
+fn vic_gov_texts_the_case_numbers_every_week() {
+    let mut game = GameHarness::new();
+    game.run_until(8. * DAY_LENGTH, |g| {
+        g.phone()
+            .log(0)
+            .preview()
+            .map(|p| p.starts_with("COVID-19"))
+            .unwrap_or(false)
+    });
+}
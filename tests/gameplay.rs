@@ -0,0 +1,140 @@
+mod common;
+
+use common::GameHarness;
+use melsim::behaviour::Behaviour;
+use melsim::covid::Exposure;
+use melsim::environment::Location;
+use melsim::epidemic::Sir;
+use melsim::npc::NPC;
+use melsim::pickup::Pickup;
+
+// Standing on the Home->Park teleporter, i.e. the front door
+const HOME_TO_PARK: [usize; 2] = [1, 17];
+// Standing on the Park->Shops teleporter
+const PARK_TO_SHOPS: [usize; 2] = [18, 14];
+// Where the Care Package turns up
+const CARE_PACKAGE: [usize; 2] = [1, 15];
+
+fn go_to_park(game: &mut GameHarness) {
+    game.place_player(HOME_TO_PARK);
+    game.run_until(1., |g| g.location() == Location::Park);
+}
+
+// Gives whoever is in the park covid, rather than leaving it to chance
+fn go_to_park_with_covid(game: &mut GameHarness) {
+    go_to_park(game);
+    game.step(2);
+    game.set_npc_infections(Sir::Infectious);
+}
+
+#[test]
+fn park_to_shops_teleporter_moves_player_and_spawns_a_shopper_and_the_checkout_staff() {
+    let mut game = GameHarness::with_seed(2020);
+    go_to_park(&mut game);
+
+    game.place_player(PARK_TO_SHOPS);
+    game.run_until(1., |g| g.location() == Location::Shops);
+    game.step(5);
+
+    assert_eq!(game.location(), Location::Shops);
+    // Two Monday shoppers, with some covid about, and whoever's on the checkout
+    assert_eq!(game.count::<NPC>(), 3);
+    let mut query = game.app.world.query::<&Behaviour>();
+    let behaviours: Vec<Behaviour> = query.iter(&game.app.world).copied().collect();
+    assert!(behaviours.contains(&Behaviour::Stationary));
+    assert!(behaviours
+        .iter()
+        .any(|b| matches!(b, Behaviour::Shopper { .. })));
+}
+
+#[test]
+fn locked_areas_stay_shut() {
+    let mut game = GameHarness::new();
+    game.state_mut()
+        .area_access
+        .set_access(Location::Park, false);
+
+    game.place_player(HOME_TO_PARK);
+    game.run_for(0.5);
+    assert_eq!(game.location(), Location::Home);
+}
+
+#[test]
+fn collecting_the_care_package_adds_20_sanity() {
+    let mut game = GameHarness::new();
+    game.run_until(60., |g| g.count::<Pickup>() == 1);
+    // Sanity ticks every 10 seconds; the package arrives well clear of one
+    let before = game.state().get_sanity();
+
+    game.place_player(CARE_PACKAGE);
+    game.run_until(1., |g| g.count::<Pickup>() == 0);
+    game.step(5);
+
+    assert_eq!(game.state().get_sanity(), before + 20);
+}
+
+#[test]
+fn same_seed_same_npc_paths() {
+    let run = |seed| {
+        let mut game = GameHarness::with_seed(seed);
+        go_to_park(&mut game);
+        game.run_for(8.);
+        game.npc_positions()
+    };
+
+    assert_eq!(run(7), run(7));
+}
+
+#[test]
+fn standing_near_an_npc_builds_up_a_dose_from_them() {
+    let mut game = GameHarness::new();
+    go_to_park_with_covid(&mut game);
+    assert_eq!(game.state().covid_risk, 0.);
+
+    game.place_player_by_npc();
+    // Out in the open air it takes a moment to show on the dial
+    game.run_until(1., |g| g.state().show_covid_risk);
+
+    let mut query = game.app.world.query::<&Exposure>();
+    let doses: Vec<f32> = query.iter(&game.app.world).map(|e| e.dose).collect();
+    assert_eq!(doses.len(), game.count::<NPC>());
+    assert!(doses.iter().any(|d| *d > 0.));
+    // but it's nowhere near certain yet
+    assert!(game.state().covid_risk < 0.5);
+}
+
+#[test]
+fn npcs_without_covid_cant_pass_it_on() {
+    for state in [Sir::Susceptible, Sir::Exposed, Sir::Recovered] {
+        let mut game = GameHarness::new();
+        go_to_park(&mut game);
+        game.step(2);
+        game.set_npc_infections(state);
+
+        game.place_player_by_npc();
+        game.step(10);
+        assert_eq!(game.state().covid_risk, 0., "{:?}", state);
+    }
+}
+
+#[test]
+fn close_contact_switches_to_covid_narrative_and_locks_park_and_shops() {
+    let mut game = GameHarness::new();
+    go_to_park_with_covid(&mut game);
+    assert!(game.state().area_access.can_access(Location::Park));
+    assert!(!game.state().in_covid_narrative());
+
+    // Sticking right next to the NPC makes catching it a near certainty within a few seconds
+    game.run_until(20., |g| {
+        if let Some(&(x, y)) = g.npc_positions().first() {
+            g.place_player_at(x, y + 1.);
+        }
+        g.state().in_covid_narrative()
+    });
+    game.run_for(0.5);
+
+    assert_eq!(game.location(), Location::Home);
+    assert!(!game.state().area_access.can_access(Location::Park));
+    assert!(!game.state().area_access.can_access(Location::Shops));
+    assert!(game.state().area_access.can_access(Location::Home));
+}
@@ -0,0 +1,124 @@
+mod common;
+
+use bevy::prelude::*;
+use common::GameHarness;
+use melsim::environment::{tile_coords_to_screen_pos, Location, GRID_SIZE};
+use melsim::epidemic::Sir;
+use melsim::heatmap::{tile_risk, HeatmapTile, SafetyCircle};
+use melsim::npc::NPC;
+use melsim::protection::{Protection, Protections};
+use melsim::vaccination::Vaccination;
+use melsim::TILE_SIZE;
+
+// The middle of tile (x, y), in physics units
+fn tile(x: usize, y: usize) -> (f32, f32) {
+    let (x, y) = tile_coords_to_screen_pos(x, 1., y, 1.);
+    (x / TILE_SIZE, y / TILE_SIZE)
+}
+
+#[test]
+fn nobody_about_means_no_risk() {
+    let protections = Protections::default();
+    assert_eq!(
+        tile_risk(
+            Location::Shops,
+            [5, 5],
+            &[],
+            &protections,
+            &Vaccination::default(),
+            0.
+        ),
+        0.
+    );
+}
+
+#[test]
+fn the_risk_is_highest_next_to_someone() {
+    let protections = Protections::default();
+    let npc = [tile(10, 10)];
+    let on = tile_risk(
+        Location::Park,
+        [10, 10],
+        &npc,
+        &protections,
+        &Vaccination::default(),
+        0.,
+    );
+    let near = tile_risk(
+        Location::Park,
+        [12, 10],
+        &npc,
+        &protections,
+        &Vaccination::default(),
+        0.,
+    );
+    let far = tile_risk(
+        Location::Park,
+        [19, 10],
+        &npc,
+        &protections,
+        &Vaccination::default(),
+        0.,
+    );
+    assert!(on > near);
+    assert!(near > far);
+    assert_eq!(far, 0.);
+}
+
+#[test]
+fn a_mask_cools_the_map_down() {
+    let mut protections = Protections::default();
+    let npc = [tile(10, 10)];
+    let bare = tile_risk(
+        Location::Shops,
+        [11, 10],
+        &npc,
+        &protections,
+        &Vaccination::default(),
+        0.,
+    );
+    protections.start(Protection::Mask, 0.);
+    let masked = tile_risk(
+        Location::Shops,
+        [11, 10],
+        &npc,
+        &protections,
+        &Vaccination::default(),
+        0.,
+    );
+    assert!(masked < bare);
+}
+
+#[test]
+fn f3_toggles_the_overlay() {
+    let mut game = GameHarness::new();
+    game.place_player([1, 17]);
+    game.run_until(1., |g| g.location() == Location::Park);
+    game.step(2);
+    game.set_npc_infections(Sir::Infectious);
+
+    let visible_tiles = |game: &mut GameHarness| {
+        let mut query = game.app.world.query::<(&HeatmapTile, &Visibility)>();
+        query
+            .iter(&game.app.world)
+            .filter(|(_, v)| v.is_visible)
+            .count()
+    };
+    assert_eq!(game.count::<HeatmapTile>(), GRID_SIZE * GRID_SIZE);
+    assert_eq!(visible_tiles(&mut game), 0);
+    assert_eq!(game.count::<SafetyCircle>(), 0);
+
+    game.press(KeyCode::F3);
+    game.step(2);
+    game.release(KeyCode::F3);
+    game.step(2);
+    assert_eq!(visible_tiles(&mut game), GRID_SIZE * GRID_SIZE);
+    assert_eq!(game.count::<SafetyCircle>(), game.count::<NPC>());
+
+    game.press(KeyCode::F3);
+    game.step(2);
+    game.release(KeyCode::F3);
+    game.step(2);
+    assert_eq!(visible_tiles(&mut game), 0);
+    assert_eq!(game.count::<SafetyCircle>(), 0);
+}
@@ -0,0 +1,212 @@
+mod common;
+
+use bevy::app::Events;
+use bevy::prelude::*;
+use common::GameHarness;
+use melsim::covid::ForcedCovidRisk;
+use melsim::environment::{EnvironmentCollider, Location};
+use melsim::events::CovidExposure;
+use melsim::game::DAY_LENGTH;
+use melsim::isolation::{Isolation, ISOLATION_DAYS};
+use melsim::pickup::Pickup;
+use melsim::ui::DateTag;
+
+const HOME_TO_PARK: [usize; 2] = [1, 17];
+const PARK_TO_SHOPS: [usize; 2] = [18, 14];
+// Where the RATs turn up
+const RAT: [usize; 2] = [12, 10];
+
+// Makes the player a close contact, and decides whether they really caught it
+fn isolate(game: &mut GameHarness, infected: bool) {
+    // Out of the way of the RATs, which would otherwise be picked up straight away
+    game.place_player([5, 5]);
+    game.app
+        .world
+        .get_resource_mut::<ForcedCovidRisk>()
+        .unwrap()
+        .0 = Some(1.);
+    game.run_until(1., |g| g.state().isolation.active());
+    game.app
+        .world
+        .get_resource_mut::<ForcedCovidRisk>()
+        .unwrap()
+        .0 = None;
+    game.state_mut().isolation.infected = infected;
+}
+
+fn have_rat(game: &mut GameHarness) -> bool {
+    let mut query = game.app.world.query::<&Pickup>();
+    query
+        .iter(&game.app.world)
+        .any(|p| matches!(p, Pickup::Rat))
+}
+
+fn take_rat(game: &mut GameHarness) {
+    game.run_until(2. * DAY_LENGTH, have_rat);
+    game.place_player(RAT);
+    game.run_until(1., |g| !have_rat(g));
+    game.step(2);
+}
+
+fn status_bar(game: &mut GameHarness) -> String {
+    let mut query = game.app.world.query_filtered::<&Text, With<DateTag>>();
+    let text = query.iter(&game.app.world).next().unwrap();
+    text.sections[0].value.clone()
+}
+
+#[test]
+fn counting_down_the_days() {
+    let mut isolation = Isolation::default();
+    isolation.start(3, true);
+    assert_eq!(isolation.days_left(3), ISOLATION_DAYS);
+    assert_eq!(isolation.days_left(5), ISOLATION_DAYS - 2);
+    assert_eq!(isolation.days_left(100), 0);
+
+    assert!(!isolation.symptomatic(4));
+    assert!(isolation.symptomatic(5));
+    assert!(isolation.symptomatic(7));
+    assert!(!isolation.symptomatic(8));
+
+    isolation.start(3, false);
+    assert!(!isolation.symptomatic(5));
+}
+
+#[test]
+fn close_contacts_isolate_with_a_countdown_in_the_status_bar() {
+    let mut game = GameHarness::new();
+    isolate(&mut game, true);
+    game.step(1);
+
+    assert!(game.state().in_covid_narrative());
+    assert!(!game.state().area_access.can_access(Location::Park));
+    assert!(status_bar(&mut game).contains("Isolating: 7 days to go"));
+}
+
+#[test]
+fn a_negative_rat_ends_isolation() {
+    let mut game = GameHarness::new();
+    isolate(&mut game, false);
+    take_rat(&mut game);
+
+    assert!(!game.state().isolation.active());
+    assert!(game.state().area_access.can_access(Location::Park));
+    game.run_until(1., |g| !g.state().in_covid_narrative());
+    assert!(!status_bar(&mut game).contains("Isolating"));
+}
+
+#[test]
+fn a_positive_rat_means_more_isolating() {
+    let mut game = GameHarness::new();
+    isolate(&mut game, true);
+    take_rat(&mut game);
+
+    assert!(game.state().isolation.active());
+    assert!(game.state().in_covid_narrative());
+    let preview = game.phone().log(0).preview().unwrap_or("").to_string();
+    assert!(preview.starts_with("Positive"), "{}", preview);
+}
+
+#[test]
+fn isolation_ends_after_the_full_period() {
+    let mut game = GameHarness::new();
+    isolate(&mut game, true);
+    let sanity = game.state().get_sanity();
+
+    game.run_until((ISOLATION_DAYS + 1) as f64 * DAY_LENGTH, |g| {
+        !g.state().isolation.active()
+    });
+    game.run_until(1., |g| !g.state().in_covid_narrative());
+    // Feeling sick took its toll
+    assert!(game.state().get_sanity() < sanity);
+}
+
+#[test]
+fn leaving_home_while_isolating_earns_a_fine_and_starts_again() {
+    let mut game = GameHarness::new();
+    isolate(&mut game, false);
+    game.run_for(DAY_LENGTH);
+    let sanity = game.state().get_sanity();
+
+    game.place_player(HOME_TO_PARK);
+    game.run_until(1., |g| g.location() == Location::Park);
+    game.step(2);
+
+    assert!(game.state().get_sanity() <= sanity - 20);
+    let date = game.state().date;
+    assert!(game.state().isolation.active());
+    assert_eq!(game.state().isolation.days_left(date), ISOLATION_DAYS);
+}
+
+#[test]
+fn a_locked_door_stays_shut_while_isolating() {
+    let mut game = GameHarness::new();
+    isolate(&mut game, false);
+    game.state_mut()
+        .area_access
+        .set_access(Location::Park, false);
+    let sanity = game.state().get_sanity();
+    let date = game.state().date;
+
+    game.place_player(HOME_TO_PARK);
+    game.run_for(0.5);
+
+    assert_eq!(game.location(), Location::Home);
+    // No fine, and the countdown carries on
+    assert!(game.state().get_sanity() > sanity - 20);
+    assert_eq!(game.state().isolation.days_left(date), ISOLATION_DAYS);
+}
+
+#[test]
+fn a_close_contact_at_home_stays_where_they_are() {
+    let mut game = GameHarness::new();
+    game.place_player([14, 14]);
+    game.step(2);
+    let before = game.player_position();
+
+    game.app
+        .world
+        .get_resource_mut::<ForcedCovidRisk>()
+        .unwrap()
+        .0 = Some(1.);
+    game.run_until(1., |g| g.state().isolation.active());
+    game.step(2);
+    assert_eq!(game.location(), Location::Home);
+    let after = game.player_position();
+    assert!((after.0 - before.0).abs() < 0.1 && (after.1 - before.1).abs() < 0.1);
+}
+
+// Frames from standing on the park's door to the shops until the player is through it
+fn walk_into_the_shops(game: &mut GameHarness, exposed_on: Option<usize>) -> usize {
+    game.place_player(HOME_TO_PARK);
+    game.run_until(1., |g| g.location() == Location::Park);
+    game.step(2);
+    game.place_player(PARK_TO_SHOPS);
+    let mut frames = 0;
+    while game.location() == Location::Park {
+        if exposed_on == Some(frames) {
+            game.app
+                .world
+                .get_resource_mut::<Events<CovidExposure>>()
+                .unwrap()
+                .send(CovidExposure { risk: 1. });
+        }
+        game.step(1);
+        frames += 1;
+    }
+    frames
+}
+
+#[test]
+fn a_close_contact_on_the_way_through_a_door_still_ends_up_home() {
+    let mut game = GameHarness::new();
+    let walls_at_home = game.count::<EnvironmentCollider>();
+    let frames = walk_into_the_shops(&mut game, None);
+
+    // Exposed in the very frame the door takes them to the shops
+    let mut game = GameHarness::new();
+    assert_eq!(walk_into_the_shops(&mut game, Some(frames - 1)), frames);
+    assert_eq!(game.location(), Location::Shops);
+    game.step(2);
+    assert_eq!(game.location(), Location::Home);
+    assert_eq!(game.count::<EnvironmentCollider>(), walls_at_home);
+}
@@ -0,0 +1,45 @@
+use melsim::narrative::{load_csv, NarrativeCriterion, NarrativeEvent};
+use melsim::needs::Need;
+
+fn load(name: &str, rows: &str) -> Vec<NarrativeEvent> {
+    let path = std::env::temp_dir().join(name);
+    std::fs::write(
+        &path,
+        format!(
+            "Sender,Body (Rough),Body (Polished),Elapsed Time,Cleared All Pickups?,Location change?,\
+             Send Texts?,Change Sanity?,Spawn Item?,Unlock area?,Lock area?,Spawn NPC,Change Needs?,Need Below?\n{}",
+            rows
+        ),
+    )
+    .unwrap();
+    let events = load_csv(&path.to_string_lossy());
+    let _ = std::fs::remove_file(&path);
+    events
+}
+
+#[test]
+fn both_need_columns_are_need_colon_value() {
+    let events = load(
+        "melsim-needs-narrative.csv",
+        "Yourself,Time for a bath,,1,,,,,,,,,Hygiene:+30;Sleep:-10,\n\
+         Yourself,Starving,,,,,,,,,,,,Hunger:30\n",
+    );
+
+    assert_eq!(
+        events[0].action.change_needs,
+        vec![(Need::Hygiene, 30.), (Need::Sleep, -10.)]
+    );
+    assert!(matches!(
+        events[1].criterion,
+        NarrativeCriterion::NeedBelow(Need::Hunger, t) if t == 30.
+    ));
+}
+
+#[test]
+#[should_panic(expected = "bad Need Below? >>Hunger;30<<")]
+fn a_bad_need_cell_says_which_column() {
+    load(
+        "melsim-bad-needs-narrative.csv",
+        "Yourself,Starving,,1,,,,,,,,,,Hunger;30\n",
+    );
+}
@@ -0,0 +1,57 @@
+use bevy_rapier2d::na::Vector2;
+use melsim::npc::{steer, Obstacle};
+
+fn v(x: f32, y: f32) -> Vector2<f32> {
+    Vector2::new(x, y)
+}
+
+// A wall a tile thick, from x to x + 1, running well above and below the NPC
+fn wall_at(x: f32) -> Obstacle {
+    (v(x, -10.), v(x + 1., 10.))
+}
+
+#[test]
+fn carries_on_across_open_floor() {
+    let steered = steer(v(0., 0.), v(1., 0.), &[], &[], None);
+    assert!((steered - v(1., 0.)).magnitude() < 1e-5);
+}
+
+#[test]
+fn turns_away_from_a_wall_ahead() {
+    let steered = steer(v(0., 0.), v(1., 0.), &[wall_at(2.)], &[], None);
+    assert!(steered.x < 0.9);
+    assert!(steered.y.abs() > 0.1);
+}
+
+#[test]
+fn gets_out_of_a_wall_it_was_pushed_into() {
+    let steered = steer(v(2.1, 0.), v(1., 0.), &[wall_at(2.)], &[], None);
+    assert!(steered.x < 0.);
+}
+
+#[test]
+fn keeps_apart_from_other_npcs() {
+    let steered = steer(v(0., 0.), v(1., 0.), &[], &[v(0., 0.5)], None);
+    assert!(steered.y < 0.);
+}
+
+#[test]
+fn keeps_out_of_the_players_personal_space() {
+    let steered = steer(v(0., 0.), v(1., 0.), &[], &[], Some(v(1., 0.)));
+    assert!(steered.x < 0.5);
+    // unless they don't care
+    let steered = steer(v(0., 0.), v(1., 0.), &[], &[], None);
+    assert!(steered.x > 0.99);
+}
+
+#[test]
+fn always_moves_at_walking_pace() {
+    let steered = steer(
+        v(0., 0.),
+        v(3., 4.),
+        &[wall_at(1.)],
+        &[v(0.2, 0.)],
+        Some(v(-1., 0.)),
+    );
+    assert!((steered.magnitude() - 1.).abs() < 1e-5);
+}
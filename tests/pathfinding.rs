@@ -0,0 +1,119 @@
+mod common;
+
+use common::GameHarness;
+use melsim::behaviour::Behaviour;
+use melsim::environment::{EnvironmentCollider, Location};
+use melsim::npc::NPC;
+use melsim::pathfinding::{position_tile, tile_position, Destination, NavGrid, Path};
+use melsim::population::Visitor;
+
+fn wall(
+    x_coordinates: usize,
+    y_coordinates: usize,
+    width: usize,
+    height: usize,
+) -> EnvironmentCollider {
+    EnvironmentCollider {
+        x_coordinates,
+        y_coordinates,
+        width,
+        height,
+    }
+}
+
+fn adjacent(a: [usize; 2], b: [usize; 2]) -> bool {
+    let dx = (a[0] as isize - b[0] as isize).abs();
+    let dy = (a[1] as isize - b[1] as isize).abs();
+    dx + dy == 1
+}
+
+#[test]
+fn tiles_and_positions_round_trip() {
+    for tile in [[0, 0], [2, 3], [10, 15], [17, 16]] {
+        assert_eq!(position_tile(tile_position(tile)), tile);
+    }
+}
+
+#[test]
+fn the_path_goes_round_a_wall() {
+    // Right across the middle, bar a gap at the bottom
+    let grid = NavGrid::new(&[wall(10, 0, 1, 15)]);
+    let path = grid.find_path([2, 2], [15, 2]).unwrap();
+
+    assert_eq!(path.first(), Some(&[2, 2]));
+    assert_eq!(path.last(), Some(&[15, 2]));
+    assert!(path.windows(2).all(|w| adjacent(w[0], w[1])));
+    assert!(path.iter().all(|t| grid.walkable(*t)));
+    // It had to go down past the end of the wall
+    assert!(path.iter().any(|t| t[1] >= 15));
+}
+
+#[test]
+fn no_way_through_a_solid_wall() {
+    let grid = NavGrid::new(&[wall(10, 0, 1, 20)]);
+    assert_eq!(grid.find_path([2, 2], [15, 2]), None);
+}
+
+#[test]
+fn heading_into_furniture_stops_beside_it() {
+    let grid = NavGrid::new(&[wall(8, 8, 4, 4)]);
+    assert!(!grid.walkable([9, 9]));
+
+    let path = grid.find_path([2, 2], [9, 9]).unwrap();
+    let end = *path.last().unwrap();
+    assert!(grid.walkable(end));
+    assert_eq!(grid.nearest_walkable([9, 9]), Some(end));
+}
+
+#[test]
+fn the_shops_have_a_way_around_the_aisles() {
+    let mut game = GameHarness::new();
+    game.place_player([1, 17]);
+    game.run_until(1., |g| g.location() == Location::Park);
+    game.place_player([18, 15]);
+    game.run_until(1., |g| g.location() == Location::Shops);
+    game.step(2);
+
+    let grid = game.app.world.get_resource::<NavGrid>().unwrap().clone();
+    // Top aisle
+    assert!(!grid.walkable([10, 1]));
+    // From the door to the back, between the aisles
+    let path = grid.find_path([5, 11], [16, 4]).unwrap();
+    assert!(path.iter().all(|t| grid.walkable(*t)));
+    assert!(path.windows(2).all(|w| adjacent(w[0], w[1])));
+}
+
+#[test]
+fn an_npc_sent_somewhere_walks_there() {
+    let mut game = GameHarness::new();
+    game.place_player([1, 17]);
+    game.run_until(1., |g| g.location() == Location::Park);
+    game.step(2);
+    // Out of the way, by the home sign
+    game.place_player([5, 5]);
+
+    // Nothing else to do once it's there, and not about to go home
+    let npc = game.entities::<NPC>()[0];
+    let goal = [10, 10];
+    let mut entity = game.app.world.entity_mut(npc);
+    entity.insert(Behaviour::Wanderer);
+    entity.remove::<Visitor>();
+    entity.remove::<Path>();
+    entity.insert(Destination::Tile(goal));
+    // Other NPCs have places to be too
+    let arrived = |g: &mut GameHarness| g.app.world.get::<Destination>(npc).is_none();
+    game.run_until(40., arrived);
+    assert!(arrived(&mut game));
+
+    let mut query = game
+        .app
+        .world
+        .query::<&bevy_rapier2d::prelude::RigidBodyPositionComponent>();
+    let position = query
+        .get(&game.app.world, npc)
+        .unwrap()
+        .position
+        .translation
+        .vector;
+    assert!((position - tile_position(goal)).magnitude() < 1.);
+}
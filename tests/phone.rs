@@ -0,0 +1,207 @@
+mod common;
+
+use bevy::prelude::{Color, KeyCode};
+use bevy::text::Font;
+use common::GameHarness;
+use melsim::narrative::load_csv;
+use melsim::phone::{
+    load_contacts, BubbleStyle, Channel, MessageBubble, MessageLog, PhoneScreen, ALERTS_THREAD,
+    CONTACTS_FILE,
+};
+
+fn tap(game: &mut GameHarness, key: KeyCode) {
+    game.press(key);
+    game.step(1);
+    game.release(key);
+    game.step(1);
+}
+
+fn open_thread(game: &mut GameHarness, sender: &str) {
+    game.phone_mut().open_thread_of(sender);
+    game.step(1);
+}
+
+fn unread(game: &GameHarness, sender: &str) -> usize {
+    let phone = game.phone();
+    phone.log(phone.thread_for(sender).unwrap()).unread
+}
+
+#[test]
+fn long_history_only_spawns_what_fits_on_the_phone() {
+    let mut game = GameHarness::new();
+    open_thread(&mut game, "Mum");
+    for i in 0..300 {
+        game.send_text(
+            "Mum",
+            &format!("Message number {}, are you eating enough?", i),
+        );
+        game.step(1);
+    }
+
+    let bubbles = game.count::<MessageBubble>();
+    assert!(bubbles > 0);
+    assert!(bubbles < 30, "{} bubbles spawned", bubbles);
+}
+
+#[test]
+fn texts_arriving_while_scrolled_back_are_flagged_until_back_at_the_bottom() {
+    let mut game = GameHarness::new();
+    open_thread(&mut game, "Friend");
+    for i in 0..20 {
+        game.send_text("Friend", &format!("Did you see this? {}", i));
+        game.step(1);
+    }
+
+    tap(&mut game, KeyCode::PageUp);
+    game.send_text("Friend", "Hello?");
+    game.send_text("Friend", "Are you ignoring me");
+    game.step(1);
+    assert_eq!(unread(&game, "Friend"), 2);
+
+    tap(&mut game, KeyCode::End);
+    assert_eq!(unread(&game, "Friend"), 0);
+}
+
+#[test]
+fn a_short_history_has_nothing_to_scroll_back_to() {
+    let mut game = GameHarness::new();
+    open_thread(&mut game, "Friend");
+    for i in 0..5 {
+        game.send_text("Friend", &format!("hello {}", i));
+        game.step(1);
+    }
+
+    for _ in 0..20 {
+        tap(&mut game, KeyCode::PageUp);
+    }
+    // Still looking at the newest, so this one isn't flagged
+    game.send_text("Friend", "Are you there?");
+    game.step(1);
+    assert_eq!(unread(&game, "Friend"), 0);
+}
+
+#[test]
+fn a_new_text_keeps_the_bubbles_already_there() {
+    let mut game = GameHarness::new();
+    open_thread(&mut game, "Friend");
+    for i in 0..3 {
+        game.send_text("Friend", &format!("hello {}", i));
+        game.step(1);
+    }
+    game.step(1);
+    let before = game.entities::<MessageBubble>();
+    assert_eq!(before.len(), 3);
+
+    game.send_text("Friend", "Are you there?");
+    game.step(2);
+    let after = game.entities::<MessageBubble>();
+    assert_eq!(after.len(), 4);
+    assert!(before.iter().all(|e| after.contains(e)));
+}
+
+#[test]
+fn texts_go_to_their_senders_thread_and_alerts_share_one() {
+    let mut game = GameHarness::new();
+    game.send_text("Mum", "Have you eaten?");
+    game.send_text("VIC GOV", "Stay home");
+    game.send_text("Department of Health", "Get tested");
+    game.step(1);
+
+    let phone = game.phone();
+    assert_eq!(phone.thread_for("VIC GOV"), Some(ALERTS_THREAD));
+    assert_eq!(
+        phone.thread_for("Department of Health"),
+        Some(ALERTS_THREAD)
+    );
+    assert_eq!(phone.log(ALERTS_THREAD).len(), 2);
+    assert_eq!(unread(&game, "Mum"), 1);
+    assert_eq!(unread(&game, "Dad"), 0);
+    assert_eq!(phone.unread_personal(), 1);
+    assert!(phone.notification.is_some());
+    // Nothing gets drawn until a thread is opened
+    assert_eq!(game.count::<MessageBubble>(), 0);
+}
+
+#[test]
+fn contacts_come_from_the_data_file() {
+    let game = GameHarness::new();
+    let phone = game.phone();
+    let dan = phone.thread_for("Dictator Dan").unwrap();
+    assert_eq!(dan, ALERTS_THREAD);
+
+    let mum = phone.thread_for("Mum").unwrap();
+    let contact = phone.thread_contact(mum).unwrap();
+    assert_eq!(contact.channel, Channel::Personal);
+    assert!(contact.avatar.is_some());
+}
+
+#[test]
+fn everyone_who_texts_is_a_contact() {
+    let contacts = load_contacts(CONTACTS_FILE);
+    for file in ["./narrative/main.csv", "./narrative/covid.csv"] {
+        for event in load_csv(file) {
+            for text in event.action.send_texts {
+                assert!(
+                    contacts.iter().any(|c| c.sender == text.sender),
+                    "{} has no row in {}",
+                    text.sender,
+                    CONTACTS_FILE
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn keyboard_gets_around_the_phone() {
+    let mut game = GameHarness::new();
+    game.send_text("Dad", "She'll be right");
+    game.send_text("Mum", "Call your father");
+    game.step(1);
+    assert_eq!(game.phone().screen(), PhoneScreen::Home);
+
+    // Messages, then the most recent contact
+    tap(&mut game, KeyCode::Return);
+    assert_eq!(game.phone().screen(), PhoneScreen::Contacts);
+    tap(&mut game, KeyCode::Return);
+    let mum = game.phone().thread_for("Mum").unwrap();
+    assert_eq!(game.phone().screen(), PhoneScreen::Thread(mum));
+    assert_eq!(unread(&game, "Mum"), 0);
+    assert_eq!(unread(&game, "Dad"), 1);
+    assert!(game.count::<MessageBubble>() > 0);
+
+    tap(&mut game, KeyCode::Escape);
+    assert_eq!(game.phone().screen(), PhoneScreen::Contacts);
+    tap(&mut game, KeyCode::Home);
+    assert_eq!(game.phone().screen(), PhoneScreen::Home);
+
+    tap(&mut game, KeyCode::Tab);
+    tap(&mut game, KeyCode::Return);
+    assert_eq!(game.phone().screen(), PhoneScreen::Thread(ALERTS_THREAD));
+}
+
+#[test]
+fn texts_that_beat_the_font_are_wrapped_again_once_it_loads() {
+    let font = Font::try_from_bytes(std::fs::read("assets/fonts/monofonto.ttf").unwrap()).unwrap();
+    let style = BubbleStyle {
+        colour: Color::WHITE,
+        text_colour: Color::BLACK,
+    };
+    let text = "Wide words like WWWWWW MMMMMM and a few more of them WWWW MMMM WWWW to wrap";
+
+    let mut early = MessageLog::default();
+    early.add("Mum", style, text, None, 0., true);
+    early.add("Mum", style, "ok", Some(&font), 0., true);
+    assert!(early.estimated());
+
+    let mut late = MessageLog::default();
+    late.add("Mum", style, text, Some(&font), 0., true);
+    late.add("Mum", style, "ok", Some(&font), 0., true);
+    assert!(!late.estimated());
+
+    early.relayout(&font);
+    assert!(!early.estimated());
+    assert_eq!(early.lines(0), late.lines(0));
+    assert_eq!(early.lines(1), late.lines(1));
+    assert_eq!(early.newest().unwrap(), "ok");
+}
@@ -0,0 +1,157 @@
+mod common;
+
+use bevy_rapier2d::na::Vector2;
+use common::GameHarness;
+use melsim::environment::{teleporter_tiles, Location};
+use melsim::game::DAY_LENGTH;
+use melsim::npc::NPC;
+use melsim::pathfinding::{position_tile, tile_position};
+use melsim::population::{
+    entrances, hour_of_day, target_population, Leaving, Visitor, TRICKLE_SECONDS,
+};
+
+// Day 1 is a Monday
+const MONDAY: i32 = 1;
+const SATURDAY: i32 = 6;
+const EARLY: f32 = 7.;
+const MIDDAY: f32 = 13.;
+const EVENING: f32 = 19.;
+const NIGHT: f32 = 3.;
+
+fn total(location: Location, date: i32, hour: f32, lockdown: usize, prevalence: f64) -> usize {
+    target_population(location, date, hour, lockdown, prevalence)
+        .iter()
+        .map(|(_, n)| n)
+        .sum()
+}
+
+fn tiles_apart(a: [usize; 2], b: [usize; 2]) -> usize {
+    (a[0] as isize - b[0] as isize).unsigned_abs() + (a[1] as isize - b[1] as isize).unsigned_abs()
+}
+
+fn go_to_park(game: &mut GameHarness) {
+    game.place_player([1, 17]);
+    game.run_until(1., |g| g.location() == Location::Park);
+}
+
+#[test]
+fn the_day_starts_in_the_morning_and_goes_round() {
+    assert_eq!(hour_of_day(0.), 8.);
+    assert_eq!(hour_of_day(DAY_LENGTH / 2.), 20.);
+    assert_eq!(hour_of_day(DAY_LENGTH), 8.);
+    for i in 0..100 {
+        let hour = hour_of_day(i as f64 * 0.37);
+        assert!((0. ..24.).contains(&hour), "{}", hour);
+    }
+}
+
+#[test]
+fn every_weekday_gets_the_same_crowd() {
+    for date in MONDAY + 1..SATURDAY {
+        assert_eq!(
+            target_population(Location::Park, date, MIDDAY, 0, 0.),
+            target_population(Location::Park, MONDAY, MIDDAY, 0, 0.)
+        );
+    }
+    // Nobody comes round
+    assert_eq!(total(Location::Home, MONDAY, MIDDAY, 0, 0.), 0);
+}
+
+#[test]
+fn quiet_at_night_and_a_different_crowd_by_day() {
+    assert_eq!(total(Location::Park, MONDAY, NIGHT, 0, 0.), 0);
+    assert_eq!(total(Location::Shops, MONDAY, NIGHT, 0, 0.), 0);
+    assert!(total(Location::Park, MONDAY, MIDDAY, 0, 0.) > 0);
+    assert!(total(Location::Shops, MONDAY, MIDDAY, 0, 0.) > 0);
+
+    // Joggers first thing, strollers in the middle of the day
+    let early = target_population(Location::Park, MONDAY, EARLY, 0, 0.);
+    let midday = target_population(Location::Park, MONDAY, MIDDAY, 0, 0.);
+    let evening = target_population(Location::Park, MONDAY, EVENING, 0, 0.);
+    assert_ne!(early, midday);
+    assert_ne!(midday, evening);
+}
+
+#[test]
+fn lockdowns_and_covid_keep_people_at_home() {
+    let usual = total(Location::Park, MONDAY, MIDDAY, 0, 0.);
+    assert!(total(Location::Park, MONDAY, MIDDAY, 1, 0.) < usual);
+    assert!(
+        total(Location::Park, MONDAY, MIDDAY, 2, 0.) < total(Location::Park, MONDAY, MIDDAY, 1, 0.)
+    );
+    assert!(total(Location::Park, MONDAY, MIDDAY, 0, 0.2) < usual);
+}
+
+#[test]
+fn busier_at_the_weekend_except_the_clinic() {
+    let (park, shops, clinic) = (Location::Park, Location::Shops, Location::Clinic);
+    assert!(total(park, SATURDAY, MIDDAY, 0, 0.) > total(park, MONDAY, MIDDAY, 0, 0.));
+    assert!(total(shops, SATURDAY, MIDDAY, 0, 0.) > total(shops, MONDAY, MIDDAY, 0, 0.));
+    assert!(total(clinic, MONDAY, MIDDAY, 0, 0.) > 0);
+    assert_eq!(total(clinic, SATURDAY, MIDDAY, 0, 0.), 0);
+}
+
+#[test]
+fn nobody_arrives_on_top_of_the_player_or_in_a_doorway() {
+    for seed in 0..5 {
+        let mut game = GameHarness::with_seed(seed);
+        go_to_park(&mut game);
+        game.step(1);
+
+        let (x, y) = game.player_position();
+        let player = position_tile(Vector2::new(x, y));
+        let doors = teleporter_tiles(Location::Park);
+        assert!(game.count::<NPC>() > 0);
+        for (x, y) in game.npc_positions() {
+            let npc = position_tile(Vector2::new(x, y));
+            assert!(tiles_apart(npc, player) >= 4, "{:?} by {:?}", npc, player);
+            assert!(doors.iter().all(|d| tiles_apart(npc, *d) >= 4), "{:?}", npc);
+        }
+    }
+}
+
+#[test]
+fn the_park_already_has_people_in_it() {
+    let mut game = GameHarness::new();
+    go_to_park(&mut game);
+    game.step(2);
+
+    assert!(game.count::<NPC>() > 0);
+    assert_eq!(game.count::<Visitor>(), game.count::<NPC>());
+}
+
+#[test]
+fn people_come_in_through_the_entrances() {
+    let mut game = GameHarness::new();
+    go_to_park(&mut game);
+    game.step(2);
+    for npc in game.entities::<NPC>() {
+        game.app.world.despawn(npc);
+    }
+
+    game.run_until(2. * TRICKLE_SECONDS, |g| g.count::<NPC>() > 0);
+    assert_eq!(game.count::<NPC>(), 1);
+    let (x, y) = game.npc_positions()[0];
+    assert!(entrances(Location::Park).iter().any(|e| {
+        let at = tile_position(*e);
+        (at.x - x).abs() < 0.5 && (at.y - y).abs() < 0.5
+    }));
+}
+
+#[test]
+fn a_lockdown_sends_people_home() {
+    let mut game = GameHarness::new();
+    go_to_park(&mut game);
+    game.step(2);
+    game.state_mut()
+        .area_access
+        .set_access(Location::Park, false);
+    game.state_mut()
+        .area_access
+        .set_access(Location::Shops, false);
+
+    game.run_until(2. * TRICKLE_SECONDS, |g| g.count::<Leaving>() > 0);
+    let leaver = game.entities::<Leaving>()[0];
+    game.run_until(60., |g| g.app.world.get_entity(leaver).is_none());
+    assert!(game.app.world.get_entity(leaver).is_none());
+}
@@ -0,0 +1,108 @@
+mod common;
+
+use bevy::prelude::*;
+use common::GameHarness;
+use melsim::environment::Location;
+use melsim::epidemic::Sir;
+use melsim::narrative::{load_csv, NarrativeCriterion};
+use melsim::protection::{MaskTag, Protection, Protections};
+
+const HOME_TO_PARK: [usize; 2] = [1, 17];
+
+fn go_to_park(game: &mut GameHarness) {
+    game.place_player(HOME_TO_PARK);
+    game.run_until(1., |g| g.location() == Location::Park);
+}
+
+fn protect(game: &mut GameHarness, p: Protection, started: f64) {
+    game.state_mut().protections.start(p, started);
+}
+
+fn mask_visible(game: &mut GameHarness) -> bool {
+    let mut query = game
+        .app
+        .world
+        .query_filtered::<&Visibility, With<MaskTag>>();
+    query.iter(&game.app.world).next().unwrap().is_visible
+}
+
+#[test]
+fn protections_run_out() {
+    let mut protections = Protections::default();
+    assert!(!protections.active(Protection::Mask, 0.));
+
+    protections.start(Protection::Mask, 10.);
+    assert!(protections.active(Protection::Mask, 10.));
+    assert!(protections.active(Protection::Mask, 9.9 + Protection::Mask.duration()));
+    assert!(!protections.active(Protection::Mask, 10. + Protection::Mask.duration()));
+    assert!(!protections.active(Protection::Sanitiser, 10.));
+}
+
+#[test]
+fn each_protection_cuts_the_dose() {
+    let risk_after_standing_by_npc = |p: Option<Protection>| {
+        let mut game = GameHarness::new();
+        go_to_park(&mut game);
+        game.step(2);
+        game.set_npc_infections(Sir::Infectious);
+        if let Some(p) = p {
+            let now = game.now();
+            protect(&mut game, p, now);
+        }
+        game.place_player_by_npc();
+        game.step(10);
+        game.state().covid_risk
+    };
+
+    let unprotected = risk_after_standing_by_npc(None);
+    assert!(unprotected > 0.);
+    for p in [
+        Protection::Mask,
+        Protection::Sanitiser,
+        Protection::KeepApart,
+    ] {
+        let protected = risk_after_standing_by_npc(Some(p));
+        assert!(protected < unprotected, "{:?}", p);
+    }
+}
+
+#[test]
+fn the_mask_shows_on_the_player_until_it_runs_out() {
+    let mut game = GameHarness::new();
+    assert!(!mask_visible(&mut game));
+
+    // Put on long enough ago that it has a second left
+    let started = game.now() - Protection::Mask.duration() + 1.;
+    protect(&mut game, Protection::Mask, started);
+    game.step(1);
+    assert!(mask_visible(&mut game));
+
+    game.run_for(1.1);
+    assert!(!mask_visible(&mut game));
+}
+
+#[test]
+fn the_narrative_can_hand_out_protections_and_check_for_them() {
+    let path = std::env::temp_dir().join("melsim-protection-narrative.csv");
+    std::fs::write(
+        &path,
+        "Sender,Body (Rough),Body (Polished),Elapsed Time,Cleared All Pickups?,Location change?,\
+         Send Texts?,Change Sanity?,Spawn Item?,Unlock area?,Lock area?,Spawn NPC,Protect?,Protected?\n\
+         VIC GOV,Masks are now mandatory,,1,,,,,,,,,Mask;Keep 1.5m,\n\
+         Yourself,Got my mask on,,,,,,,,,,,,Mask\n",
+    )
+    .unwrap();
+
+    let events = load_csv(&path.to_string_lossy());
+    let _ = std::fs::remove_file(&path);
+
+    assert_eq!(events.len(), 2);
+    assert_eq!(
+        events[0].action.protections,
+        vec![Protection::Mask, Protection::KeepApart]
+    );
+    assert!(matches!(
+        events[1].criterion,
+        NarrativeCriterion::Protected(Protection::Mask)
+    ));
+}
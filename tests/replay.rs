@@ -0,0 +1,78 @@
+mod common;
+
+use bevy::prelude::KeyCode;
+use common::GameHarness;
+use std::fs;
+use std::path::PathBuf;
+
+// How long to keep going after the last key change in a recording
+const SETTLE_TIME: f64 = 2.;
+
+fn temp_path(name: &str) -> String {
+    let path: PathBuf = std::env::temp_dir().join(name);
+    path.to_string_lossy().into_owned()
+}
+
+#[test]
+fn a_recording_replays_to_the_same_run() {
+    let path = temp_path("melsim-replay-test.csv");
+
+    let mut recorded = GameHarness::recording(99, &path);
+    recorded.press(KeyCode::Right);
+    recorded.run_for(1.);
+    recorded.press(KeyCode::Up);
+    recorded.run_for(0.5);
+    recorded.release(KeyCode::Right);
+    recorded.release(KeyCode::Up);
+    // Make a phone call, then wait around for the narrative
+    recorded.press(KeyCode::T);
+    recorded.step(1);
+    recorded.release(KeyCode::T);
+    recorded.run_for(12.);
+    let expected = recorded.snapshot();
+
+    let mut replayed = GameHarness::replaying(&path);
+    while replayed.now() < recorded.now() {
+        replayed.step(1);
+    }
+    assert_eq!(replayed.snapshot(), expected);
+    let _ = fs::remove_file(&path);
+}
+
+// Replays each recording and compares the end of the run with its golden file. Run with
+// UPDATE_GOLDEN=1 to write the golden files afresh, then check the diff over before committing.
+#[test]
+fn recordings_match_golden_files() {
+    let update = std::env::var("UPDATE_GOLDEN").map_or(false, |v| v == "1");
+    let mut checked = 0;
+    for entry in fs::read_dir("tests/recordings").unwrap() {
+        let recording = entry.unwrap().path();
+        let name = recording
+            .file_stem()
+            .unwrap()
+            .to_string_lossy()
+            .into_owned();
+        let golden = PathBuf::from("tests/golden").join(format!("{}.txt", name));
+
+        let mut game = GameHarness::replaying(&recording.to_string_lossy());
+        game.run_until(600., |g| g.replay_finished());
+        game.run_for(SETTLE_TIME);
+        let snapshot = game.snapshot();
+
+        if update {
+            println!("writing golden file {:?}", golden);
+            fs::create_dir_all("tests/golden").unwrap();
+            fs::write(&golden, &snapshot).unwrap();
+        } else {
+            let expected = fs::read_to_string(&golden).unwrap_or_else(|_| {
+                panic!(
+                    "no golden file {:?} for {}, run with UPDATE_GOLDEN=1 to write one",
+                    golden, name
+                )
+            });
+            assert_eq!(snapshot, expected, "{} has changed", name);
+        }
+        checked += 1;
+    }
+    assert!(checked > 0);
+}
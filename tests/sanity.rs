@@ -0,0 +1,23 @@
+use melsim::environment::Location;
+use melsim::game::location_sanity_tick;
+
+#[test]
+fn home_wears_on_you_the_longer_you_stay_in() {
+    assert_eq!(location_sanity_tick(Location::Home, 0, 10.), -1);
+    assert_eq!(location_sanity_tick(Location::Home, 0, 120.), -2);
+}
+
+#[test]
+fn the_park_helps_unless_its_busy() {
+    assert_eq!(location_sanity_tick(Location::Park, 0, 10.), 1);
+    assert_eq!(location_sanity_tick(Location::Park, 0, 60.), 2);
+    assert_eq!(location_sanity_tick(Location::Park, 3, 60.), 1);
+}
+
+#[test]
+fn the_shops_depend_on_the_crowd_and_the_queue() {
+    assert_eq!(location_sanity_tick(Location::Shops, 0, 10.), 1);
+    assert_eq!(location_sanity_tick(Location::Shops, 2, 10.), 0);
+    assert_eq!(location_sanity_tick(Location::Shops, 4, 10.), -2);
+    assert_eq!(location_sanity_tick(Location::Shops, 0, 90.), 0);
+}
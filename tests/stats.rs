@@ -0,0 +1,133 @@
+mod common;
+
+use bevy::app::Events;
+use common::GameHarness;
+use melsim::environment::{Location, TV};
+use melsim::events::NarrativeEventFired;
+use melsim::narrative::NarrativeActions;
+use melsim::stats::{Achievement, Profile, ProfileFile, RunStats};
+
+fn temp_profile(name: &str) -> String {
+    let path = std::env::temp_dir().join(name);
+    let _ = std::fs::remove_file(&path);
+    String::from(path.to_string_lossy())
+}
+
+#[test]
+fn staying_in_all_fortnight() {
+    let mut run = RunStats::default();
+    for date in 1..=14 {
+        run.record_time(Location::Home, 5., date);
+    }
+
+    let achievements = run.achievements();
+    assert!(achievements.contains(&Achievement::NeverLeftHome));
+    assert!(achievements.contains(&Achievement::SurvivedAFortnight));
+    assert!(achievements.contains(&Achievement::NoCloseContacts));
+    assert!(!achievements.contains(&Achievement::ParkOnDayOne));
+}
+
+#[test]
+fn out_on_day_one_and_caught_out() {
+    let mut run = RunStats::default();
+    run.record_time(Location::Home, 2., 1);
+    run.record_time(Location::Park, 2., 1);
+    run.record_close_contact();
+    run.record_time(Location::Home, 5., 8);
+
+    let achievements = run.achievements();
+    assert!(achievements.contains(&Achievement::ParkOnDayOne));
+    assert!(!achievements.contains(&Achievement::NeverLeftHome));
+    assert!(!achievements.contains(&Achievement::NoCloseContacts));
+}
+
+#[test]
+fn buffy_only_counts_when_the_player_sits_down_to_it() {
+    let mut run = RunStats::default();
+    // The narrative's TV pickups are just pickups
+    run.record_pickup();
+    run.record_pickup();
+    run.record_need_restored("fridge");
+    run.record_need_restored(TV);
+    assert!(!run.achievements().contains(&Achievement::WatchedBuffyTwice));
+
+    run.record_need_restored(TV);
+    assert!(run.achievements().contains(&Achievement::WatchedBuffyTwice));
+}
+
+#[test]
+fn the_profile_adds_runs_up_and_only_announces_new_achievements() {
+    let mut first = RunStats::default();
+    first.record_time(Location::Home, 5., 3);
+    first.record_text("Mum");
+    first.record_text("Mum");
+    let mut second = RunStats::default();
+    second.record_time(Location::Park, 5., 1);
+    second.record_time(Location::Park, 5., 5);
+    second.record_close_contact();
+    second.record_text("Mum");
+
+    let mut profile = Profile::default();
+    assert_eq!(profile.record_run(&first), vec![Achievement::NeverLeftHome]);
+    assert_eq!(profile.record_run(&second), vec![Achievement::ParkOnDayOne]);
+    assert!(profile.record_run(&first).is_empty());
+
+    assert_eq!(profile.runs, 3);
+    assert_eq!(profile.best_days_survived, 5);
+    assert_eq!(profile.total_days_survived, 11);
+    assert_eq!(profile.total_close_contacts, 1);
+    assert_eq!(profile.texts_received["Mum"], 5);
+    assert_eq!(profile.achievements.len(), 2);
+}
+
+#[test]
+fn the_profile_survives_a_save_and_load() {
+    let path = temp_profile("melsim-profile-round-trip.csv");
+    let mut run = RunStats::default();
+    run.record_time(Location::Park, 5., 1);
+    run.record_close_contact();
+    run.record_text("Dad");
+    let mut profile = Profile::default();
+    profile.record_run(&run);
+
+    profile.save(&path);
+    let loaded = Profile::load(&path);
+    let _ = std::fs::remove_file(&path);
+
+    assert_eq!(loaded.runs, profile.runs);
+    assert_eq!(loaded.best_days_survived, profile.best_days_survived);
+    assert_eq!(loaded.total_days_survived, profile.total_days_survived);
+    assert_eq!(loaded.total_close_contacts, profile.total_close_contacts);
+    assert_eq!(loaded.texts_received, profile.texts_received);
+    assert_eq!(loaded.achievements, profile.achievements);
+}
+
+#[test]
+fn a_missing_profile_is_a_fresh_start() {
+    let profile = Profile::load(&temp_profile("melsim-profile-missing.csv"));
+    assert_eq!(profile.runs, 0);
+    assert!(profile.achievements.is_empty());
+}
+
+#[test]
+fn a_finished_run_goes_wherever_the_profile_file_says() {
+    let path = temp_profile("melsim-profile-finished-run.csv");
+    let mut game = GameHarness::new();
+    game.app
+        .world
+        .insert_resource(ProfileFile(Some(path.clone())));
+
+    game.app
+        .world
+        .get_resource_mut::<Events<NarrativeEventFired>>()
+        .unwrap()
+        .send(NarrativeEventFired {
+            actions: NarrativeActions::new_with_sanity(Some(-200)),
+        });
+    game.run_until(1., |g| g.state().run_over());
+    game.step(2);
+
+    let profile = Profile::load(&path);
+    let _ = std::fs::remove_file(&path);
+    assert_eq!(profile.runs, 1);
+}
@@ -0,0 +1,117 @@
+mod common;
+
+use common::GameHarness;
+use melsim::environment::Location;
+use melsim::epidemic::Sir;
+use melsim::game::{AreaAccessControl, DAY_LENGTH};
+use melsim::pickup::Pickup;
+use melsim::vaccination::Vaccination;
+
+const HOME_TO_PARK: [usize; 2] = [1, 17];
+const PARK_TO_CLINIC: [usize; 2] = [10, 0];
+// Where the vaccine waits
+const VACCINE: [usize; 2] = [9, 5];
+
+fn have_vaccine(game: &mut GameHarness) -> bool {
+    let mut query = game.app.world.query::<&Pickup>();
+    query
+        .iter(&game.app.world)
+        .any(|p| matches!(p, Pickup::Vaccine))
+}
+
+fn go_to_clinic(game: &mut GameHarness) {
+    // The narrative locks the park early on
+    game.state_mut()
+        .area_access
+        .set_access(Location::Park, true);
+    game.place_player(HOME_TO_PARK);
+    game.run_until(1., |g| g.location() == Location::Park);
+    game.place_player(PARK_TO_CLINIC);
+    game.run_until(1., |g| g.location() == Location::Clinic);
+    game.step(2);
+    // Nobody there to give the player covid while they wait
+    game.set_npc_infections(Sir::Recovered);
+}
+
+fn latest_alert(game: &GameHarness) -> String {
+    game.phone().log(0).newest().unwrap_or_default()
+}
+
+#[test]
+fn two_doses_a_few_days_apart() {
+    let mut vaccination = Vaccination::default();
+    assert_eq!(vaccination.book(1), Some(3));
+    assert!(!vaccination.due(2));
+    assert!(vaccination.due(3));
+    assert!(vaccination.due(4));
+
+    let next = vaccination.record_dose(4).unwrap();
+    assert!(next > 4);
+    assert!(!vaccination.due(4));
+    assert!(!vaccination.fully_vaccinated());
+
+    assert_eq!(vaccination.record_dose(next), None);
+    assert!(vaccination.fully_vaccinated());
+    assert_eq!(vaccination.appointment(), None);
+    assert_eq!(vaccination.book(next), None);
+}
+
+#[test]
+fn each_dose_cuts_the_covid_dose() {
+    let mut vaccination = Vaccination::default();
+    assert_eq!(vaccination.dose_factor(), 1.);
+    vaccination.record_dose(1);
+    let one = vaccination.dose_factor();
+    vaccination.record_dose(7);
+    let two = vaccination.dose_factor();
+    assert!(one < 1.);
+    assert!(two < one);
+}
+
+#[test]
+fn the_fully_vaccinated_get_into_places_locked_to_everyone_else() {
+    let mut access = AreaAccessControl::default();
+    access.set_access(Location::Shops, false);
+    access.set_vaccinated_only(Location::Shops);
+    assert!(!access.can_access(Location::Shops));
+
+    access.set_vaccinated(true);
+    assert!(access.can_access(Location::Shops));
+
+    // but isolating still means staying home
+    access.set_isolating(true);
+    assert!(!access.can_access(Location::Shops));
+}
+
+#[test]
+fn no_vaccine_before_the_appointment() {
+    let mut game = GameHarness::new();
+    game.step(1);
+    let date = game.state().date;
+    game.state_mut().vaccination.book(date);
+
+    go_to_clinic(&mut game);
+    game.step(10);
+    assert!(!have_vaccine(&mut game));
+}
+
+#[test]
+fn turning_up_on_the_day_gets_the_first_dose_and_books_the_second() {
+    let mut game = GameHarness::new();
+    game.step(1);
+    let date = game.state().date;
+    let day = game.state_mut().vaccination.book(date).unwrap();
+    game.run_until(3. * DAY_LENGTH, |g| g.state().date >= day);
+
+    go_to_clinic(&mut game);
+    game.run_until(1., have_vaccine);
+    game.place_player(VACCINE);
+    game.run_until(1., |g| !have_vaccine(g));
+    game.step(2);
+
+    assert_eq!(game.state().vaccination.doses(), 1);
+    assert!(game.state().vaccination.appointment().unwrap() > day);
+    let alert = latest_alert(&game);
+    assert!(alert.starts_with("Dose 1 recorded"), "{}", alert);
+    assert!(alert.contains("(dose 2)"), "{}", alert);
+}
@@ -0,0 +1,150 @@
+use bevy::text::Font;
+use melsim::wrap::{graphemes, wrap_text, FixedMeasure, FontMeasure, Measure};
+
+// Ten characters to a line
+const WIDTH: f32 = 100.;
+
+fn fixed() -> FixedMeasure {
+    FixedMeasure { advance: 10. }
+}
+
+fn font(path: &str) -> Font {
+    Font::try_from_bytes(std::fs::read(path).unwrap()).unwrap()
+}
+
+fn wrap(text: &str) -> Vec<String> {
+    wrap_text(&fixed(), WIDTH, text)
+}
+
+#[test]
+fn empty_text_has_no_lines() {
+    assert!(wrap("").is_empty());
+}
+
+#[test]
+fn short_text_is_one_line() {
+    assert_eq!(wrap("hi there"), vec!["hi there"]);
+}
+
+#[test]
+fn breaks_at_spaces() {
+    assert_eq!(
+        wrap("the quick brown fox jumps"),
+        vec!["the quick", "brown fox", "jumps"]
+    );
+}
+
+#[test]
+fn a_word_exactly_the_width_fits() {
+    assert_eq!(
+        wrap("abcdefghij abcdefghij"),
+        vec!["abcdefghij", "abcdefghij"]
+    );
+}
+
+#[test]
+fn forced_breaks() {
+    assert_eq!(wrap("one|two"), vec!["one", "two"]);
+    assert_eq!(wrap("one||two"), vec!["one", "", "two"]);
+    assert_eq!(wrap("one|"), vec!["one"]);
+}
+
+#[test]
+fn long_words_are_hyphenated_between_letters() {
+    let word = "Supercalifragilisticexpialidocious";
+    let lines = wrap(word);
+    assert!(lines.len() > 1);
+    for l in &lines[..lines.len() - 1] {
+        assert!(l.ends_with('-'), "{:?}", lines);
+    }
+    for l in &lines {
+        assert!(fixed().width(l) <= WIDTH, "{:?}", lines);
+    }
+    assert_eq!(lines.concat().replace('-', ""), word);
+}
+
+#[test]
+fn long_words_carry_on_after_the_break() {
+    assert_eq!(
+        wrap("a abcdefghijklmno pq"),
+        vec!["a", "abcdefghi-", "jklmno pq"]
+    );
+}
+
+#[test]
+fn non_letters_are_broken_without_a_hyphen() {
+    let lines = wrap("phone 1800675398, 2020-03-16");
+    assert_eq!(lines, vec!["phone", "1800675398", ",", "2020-03-16"]);
+    assert!(lines.iter().all(|l| fixed().width(l) <= WIDTH));
+}
+
+#[test]
+fn non_ascii_text_does_not_panic_or_split_characters() {
+    let text = "It’s Monday, 2nd March 2020 — café, naïve, résumé";
+    let lines = wrap(text);
+    assert_eq!(lines.join(" "), text);
+}
+
+#[test]
+fn combining_marks_stay_with_their_letter() {
+    // e + combining acute
+    let word = "cafe\u{301}cafe\u{301}cafe\u{301}";
+    assert_eq!(graphemes(word).len(), 12);
+    let lines = wrap_text(&fixed(), 50., word);
+    for l in &lines {
+        assert!(!l.starts_with('\u{301}'), "{:?}", lines);
+    }
+    assert_eq!(lines.concat().replace('-', ""), word);
+}
+
+#[test]
+fn emoji_sequences_are_one_grapheme() {
+    // family (ZWJ sequence), thumbs up with skin tone, and the Australian flag
+    let family = "\u{1f468}\u{200d}\u{1f469}\u{200d}\u{1f467}";
+    let thumbs = "\u{1f44d}\u{1f3fd}";
+    let flag = "\u{1f1e6}\u{1f1fa}";
+    let text = format!("{}{}{}{}", family, thumbs, flag, flag);
+    assert_eq!(graphemes(&text), vec![family, thumbs, flag, flag]);
+
+    let lines = wrap_text(&fixed(), 10., &text);
+    assert_eq!(lines, vec![family, thumbs, flag, flag]);
+}
+
+#[test]
+fn other_scripts_keep_their_clusters_whole() {
+    // A Hangul syllable spelt out in jamo
+    assert_eq!(graphemes("\u{1100}\u{1161}\u{11a8}").len(), 1);
+    // Devanagari ki, with a spacing vowel sign
+    assert_eq!(graphemes("\u{915}\u{93f}\u{915}\u{93f}").len(), 2);
+    // The Arabic number sign goes in front of the digit it marks
+    assert_eq!(graphemes("\u{600}\u{661}").len(), 1);
+}
+
+#[test]
+fn a_very_narrow_width_still_finishes() {
+    let lines = wrap_text(&fixed(), 1., "abc de");
+    assert_eq!(lines, vec!["a-", "b-", "c", "d-", "e"]);
+}
+
+#[test]
+fn real_font_lines_fit() {
+    for path in ["assets/fonts/monofonto.ttf", "assets/fonts/SFPro.ttf"] {
+        let font = font(path);
+        for size in [14., 24., 40.] {
+            let measure = FontMeasure::new(&font, size);
+            let text = "Did you know that violets are able to grow faster when you fertilise \
+                        them with duck eggs? Antidisestablishmentarianism!";
+            for l in wrap_text(&measure, 225., text) {
+                assert!(measure.width(&l) <= 225., "{} at {}: {:?}", path, size, l);
+            }
+        }
+    }
+}
+
+#[test]
+fn real_font_widths_are_proportional() {
+    let font = font("assets/fonts/SFPro.ttf");
+    let measure = FontMeasure::new(&font, 24.);
+    assert!(measure.width("iiii") < measure.width("WWWW"));
+    assert!(measure.width("WWWW") < FontMeasure::new(&font, 48.).width("WWWW"));
+}